@@ -0,0 +1,223 @@
+// src-tauri/src/core/adjustments.rs
+
+use crate::core::settings::{self, AdjustmentOptions};
+use image::{ColorType, DynamicImage, Rgba32FImage};
+
+// Percentuale di pixel ignorati ai due estremi dall'auto-levels
+const AUTO_LEVELS_CLIP: f64 = 0.005;
+const HISTOGRAM_BINS: usize = 256;
+
+// Coefficienti di luminanza Rec. 709
+const LUMA_R: f32 = 0.2126;
+const LUMA_G: f32 = 0.7152;
+const LUMA_B: f32 = 0.0722;
+
+/// Applica le regolazioni tonali e di colore in ordine fisso:
+/// auto-levels → luminosità → contrasto → gamma → saturazione → scala di grigi → seppia.
+pub fn apply_adjustments(img: &DynamicImage, options: &AdjustmentOptions) -> DynamicImage {
+    if options.is_identity() {
+        return img.clone();
+    }
+
+    let mut working = img.to_rgba32f();
+
+    if options.auto_levels {
+        auto_levels(&mut working);
+    }
+
+    let gamma = if options.gamma > 0.0 {
+        options.gamma
+    } else {
+        1.0
+    };
+    let contrast = 1.0 + options.contrast.clamp(-1.0, 1.0);
+    let saturation = 1.0 + options.saturation.clamp(-1.0, 1.0);
+    let brightness = options.brightness.clamp(-1.0, 1.0);
+
+    for pixel in working.pixels_mut() {
+        let mut rgb = [pixel[0], pixel[1], pixel[2]];
+
+        for value in rgb.iter_mut() {
+            *value += brightness;
+            *value = (*value - 0.5) * contrast + 0.5;
+            *value = value.clamp(0.0, 1.0).powf(1.0 / gamma);
+        }
+
+        if saturation != 1.0 {
+            let luma = luminance(&rgb);
+            for value in rgb.iter_mut() {
+                *value = luma + (*value - luma) * saturation;
+            }
+        }
+
+        if options.grayscale {
+            let luma = luminance(&rgb);
+            rgb = [luma; 3];
+        }
+
+        if options.sepia {
+            let [r, g, b] = rgb;
+            rgb = [
+                0.393 * r + 0.769 * g + 0.189 * b,
+                0.349 * r + 0.686 * g + 0.168 * b,
+                0.272 * r + 0.534 * g + 0.131 * b,
+            ];
+        }
+
+        for (channel, value) in rgb.iter().enumerate() {
+            pixel[channel] = value.clamp(0.0, 1.0);
+        }
+    }
+
+    // Il seppia richiede i canali colore anche per sorgenti in scala di grigi
+    let target = match img.color() {
+        ColorType::L8 | ColorType::L16 if options.sepia => ColorType::Rgb8,
+        ColorType::La8 | ColorType::La16 if options.sepia => ColorType::Rgba8,
+        other => other,
+    };
+
+    settings::convert_to_color_type(DynamicImage::ImageRgba32F(working), target)
+}
+
+fn luminance(rgb: &[f32; 3]) -> f32 {
+    LUMA_R * rgb[0] + LUMA_G * rgb[1] + LUMA_B * rgb[2]
+}
+
+/// Estende ogni canale all'intero intervallo, ignorando gli estremi del clip
+fn auto_levels(img: &mut Rgba32FImage) {
+    let total = (img.width() as u64 * img.height() as u64) as f64;
+    if total == 0.0 {
+        return;
+    }
+
+    let last_bin = (HISTOGRAM_BINS - 1) as f32;
+    let mut histograms = [[0u64; HISTOGRAM_BINS]; 3];
+    for pixel in img.pixels() {
+        for (channel, histogram) in histograms.iter_mut().enumerate() {
+            let bin = (pixel[channel].clamp(0.0, 1.0) * last_bin).round() as usize;
+            histogram[bin] += 1;
+        }
+    }
+
+    let clip = (total * AUTO_LEVELS_CLIP) as u64;
+    let levels: Vec<(f32, f32)> = histograms
+        .iter()
+        .map(|histogram| {
+            let low = percentile_bin(histogram.iter().enumerate(), clip);
+            let high = percentile_bin(histogram.iter().enumerate().rev(), clip);
+            (low as f32 / last_bin, high as f32 / last_bin)
+        })
+        .collect();
+
+    for pixel in img.pixels_mut() {
+        for (channel, &(low, high)) in levels.iter().enumerate() {
+            // Canale piatto: niente da estendere
+            if high - low < 1.0 / last_bin {
+                continue;
+            }
+            pixel[channel] = ((pixel[channel] - low) / (high - low)).clamp(0.0, 1.0);
+        }
+    }
+}
+
+/// Primo bin in cui il conteggio cumulativo supera la soglia di clip
+fn percentile_bin<'a>(bins: impl Iterator<Item = (usize, &'a u64)>, clip: u64) -> usize {
+    let mut cumulative = 0u64;
+    let mut last = 0;
+    for (index, &count) in bins {
+        last = index;
+        cumulative += count;
+        if cumulative > clip {
+            return index;
+        }
+    }
+    last
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn neutral() -> AdjustmentOptions {
+        AdjustmentOptions::default()
+    }
+
+    fn solid(r: u8, g: u8, b: u8) -> DynamicImage {
+        DynamicImage::ImageRgb8(image::RgbImage::from_pixel(4, 4, image::Rgb([r, g, b])))
+    }
+
+    #[test]
+    fn test_identity_is_noop() {
+        assert!(neutral().is_identity());
+        let img = solid(10, 20, 30);
+        assert_eq!(apply_adjustments(&img, &neutral()).to_rgb8(), img.to_rgb8());
+    }
+
+    #[test]
+    fn test_brightness_and_grayscale() {
+        let options = AdjustmentOptions {
+            brightness: 0.1,
+            ..neutral()
+        };
+        let brighter = apply_adjustments(&solid(100, 100, 100), &options).to_rgb8();
+        assert!(brighter.get_pixel(0, 0)[0] > 120);
+
+        let options = AdjustmentOptions {
+            grayscale: true,
+            ..neutral()
+        };
+        let gray = apply_adjustments(&solid(200, 50, 10), &options);
+        assert_eq!(gray.color(), ColorType::Rgb8);
+        let pixel = gray.to_rgb8().get_pixel(0, 0).0;
+        assert!(pixel[0] == pixel[1] && pixel[1] == pixel[2]);
+    }
+
+    #[test]
+    fn test_saturation_and_sepia() {
+        let options = AdjustmentOptions {
+            saturation: -1.0,
+            ..neutral()
+        };
+        let desaturated = apply_adjustments(&solid(200, 50, 10), &options).to_rgb8();
+        let pixel = desaturated.get_pixel(0, 0).0;
+        assert!(pixel[0].abs_diff(pixel[2]) <= 1);
+
+        // Il seppia promuove la scala di grigi a RGB con tono caldo
+        let options = AdjustmentOptions {
+            sepia: true,
+            ..neutral()
+        };
+        let gray = DynamicImage::ImageLuma8(image::GrayImage::from_pixel(2, 2, image::Luma([128])));
+        let sepia = apply_adjustments(&gray, &options);
+        assert_eq!(sepia.color(), ColorType::Rgb8);
+        let pixel = sepia.to_rgb8().get_pixel(0, 0).0;
+        assert!(pixel[0] > pixel[1] && pixel[1] > pixel[2]);
+    }
+
+    #[test]
+    fn test_auto_levels_stretches_range() {
+        // Gradiente compresso tra 64 e 192
+        let narrow =
+            image::RgbImage::from_fn(128, 1, |x, _| image::Rgb([64 + x as u8, 64 + x as u8, 64]));
+        let options = AdjustmentOptions {
+            auto_levels: true,
+            ..neutral()
+        };
+        let stretched = apply_adjustments(&DynamicImage::ImageRgb8(narrow), &options).to_rgb8();
+
+        assert!(stretched.get_pixel(0, 0)[0] <= 2);
+        assert!(stretched.get_pixel(127, 0)[0] >= 253);
+        // Il canale blu è piatto e resta invariato
+        assert_eq!(stretched.get_pixel(64, 0)[2], 64);
+    }
+
+    #[test]
+    fn test_gamma() {
+        let options = AdjustmentOptions {
+            gamma: 2.0,
+            ..neutral()
+        };
+        let lifted = apply_adjustments(&solid(64, 64, 64), &options).to_rgb8();
+        assert!(lifted.get_pixel(0, 0)[0] > 120);
+    }
+}
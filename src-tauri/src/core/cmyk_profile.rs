@@ -0,0 +1,295 @@
+// src-tauri/src/core/cmyk_profile.rs
+
+use std::sync::OnceLock;
+
+/// Punti della griglia CLUT per canale (9^4 nodi, interpolati da LCMS2)
+const GRID_POINTS: usize = 9;
+
+/// Bianco di riferimento del PCS (D50)
+const D50: [f64; 3] = [0.9642, 1.0, 0.8249];
+
+/// Lab (D50) della carta e dei solidi ISO 12647-2 PC1 (patinata, simile a FOGRA39).
+/// Ordine dei primari di Neugebauer: bit 0 = C, bit 1 = M, bit 2 = Y.
+const PAPER_LAB: [f64; 3] = [95.0, 0.0, -2.0];
+const NEUGEBAUER_LAB: [[f64; 3]; 8] = [
+    PAPER_LAB,
+    [55.0, -37.0, -50.0],
+    [48.0, 74.0, -3.0],
+    [24.0, 22.0, -46.0],
+    [89.0, -5.0, 93.0],
+    [50.0, -65.0, 27.0],
+    [47.0, 68.0, 48.0],
+    [23.0, 0.0, 0.0],
+];
+const BLACK_LAB: [f64; 3] = [16.0, 0.0, 0.0];
+
+/// Incremento del punto al 50% (CMY e nero)
+const DOT_GAIN_CMY: f64 = 0.14;
+const DOT_GAIN_K: f64 = 0.17;
+
+/// Profilo CMYK incorporato usato per i JPEG CMYK senza profilo embedded.
+/// Modella la stampa offset su carta patinata (ISO 12647-2, tipo FOGRA39):
+/// Neugebauer con correzione di Yule-Nielsen e incremento del punto.
+pub fn default_cmyk_icc() -> &'static [u8] {
+    static PROFILE: OnceLock<Vec<u8>> = OnceLock::new();
+    PROFILE.get_or_init(build_profile)
+}
+
+/// Serializza un profilo ICC v2 (output, CMYK -> Lab) con un solo tag A2B0 lut16.
+/// LCMS2 usa l'A2B0 per tutti gli intenti quando mancano A2B1/A2B2.
+fn build_profile() -> Vec<u8> {
+    let paper = lab_to_xyz(PAPER_LAB);
+    let tags: [(&[u8; 4], Vec<u8>); 4] = [
+        (b"desc", desc_tag("Coated offset (ISO 12647-2, built-in)")),
+        (b"cprt", text_tag("No copyright, use freely")),
+        (b"wtpt", xyz_tag(paper)),
+        (b"A2B0", lut16_tag()),
+    ];
+
+    let mut offset = 128 + 4 + tags.len() * 12;
+    let mut table = Vec::new();
+    let mut data = Vec::new();
+    for (signature, tag) in &tags {
+        table.extend_from_slice(*signature);
+        table.extend_from_slice(&(offset as u32).to_be_bytes());
+        table.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+        data.extend_from_slice(tag);
+        // I tag sono allineati a 4 byte
+        while data.len() % 4 != 0 {
+            data.push(0);
+        }
+        offset = 128 + 4 + tags.len() * 12 + data.len();
+    }
+
+    let mut header = [0u8; 128];
+    header[8..12].copy_from_slice(&0x0210_0000u32.to_be_bytes());
+    header[12..16].copy_from_slice(b"prtr");
+    header[16..20].copy_from_slice(b"CMYK");
+    header[20..24].copy_from_slice(b"Lab ");
+    header[36..40].copy_from_slice(b"acsp");
+    for (index, value) in D50.iter().enumerate() {
+        let start = 68 + index * 4;
+        header[start..start + 4].copy_from_slice(&s15_fixed16(*value));
+    }
+
+    let size = 128 + 4 + table.len() + data.len();
+    header[0..4].copy_from_slice(&(size as u32).to_be_bytes());
+
+    let mut profile = Vec::with_capacity(size);
+    profile.extend_from_slice(&header);
+    profile.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+    profile.extend_from_slice(&table);
+    profile.extend_from_slice(&data);
+    profile
+}
+
+/// Tag lut16Type: curve identità, CLUT CMYK -> Lab (codifica Lab v2 a 16 bit)
+fn lut16_tag() -> Vec<u8> {
+    let mut tag = Vec::new();
+    tag.extend_from_slice(b"mft2");
+    tag.extend_from_slice(&[0; 4]);
+    tag.extend_from_slice(&[4, 3, GRID_POINTS as u8, 0]);
+    // Matrice identità (usata solo con ingresso XYZ)
+    for row in 0..3 {
+        for col in 0..3 {
+            tag.extend_from_slice(&s15_fixed16(if row == col { 1.0 } else { 0.0 }));
+        }
+    }
+    // Tabelle di ingresso e uscita lineari con due campioni
+    tag.extend_from_slice(&2u16.to_be_bytes());
+    tag.extend_from_slice(&2u16.to_be_bytes());
+    for _ in 0..4 {
+        tag.extend_from_slice(&[0x00, 0x00, 0xFF, 0xFF]);
+    }
+
+    // Il primo canale (C) varia più lentamente
+    let last = (GRID_POINTS - 1) as f64;
+    for node in 0..GRID_POINTS.pow(4) {
+        let [c, m, y, k] =
+            [3, 2, 1, 0].map(|digit| (node / GRID_POINTS.pow(digit) % GRID_POINTS) as f64 / last);
+        for value in encode_lab(xyz_to_lab(print_xyz(c, m, y, k))) {
+            tag.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+
+    for _ in 0..3 {
+        tag.extend_from_slice(&[0x00, 0x00, 0xFF, 0xFF]);
+    }
+    tag
+}
+
+/// XYZ relativo alla carta (carta = bianco D50) per una copertura CMYK in [0, 1]
+fn print_xyz(c: f64, m: f64, y: f64, k: f64) -> [f64; 3] {
+    let paper = lab_to_xyz(PAPER_LAB);
+    let black = lab_to_xyz(BLACK_LAB);
+    let [c, m, y] = [c, m, y].map(|coverage| dot_gain(coverage, DOT_GAIN_CMY));
+    let k = dot_gain(k, DOT_GAIN_K);
+
+    // Pesi di Demichel sui primari, sommati nello spazio di Yule-Nielsen (n = 2)
+    let mut root = [0.0; 3];
+    for (index, lab) in NEUGEBAUER_LAB.iter().enumerate() {
+        let weight = [c, m, y]
+            .iter()
+            .enumerate()
+            .map(|(bit, coverage)| {
+                if index & (1 << bit) != 0 {
+                    *coverage
+                } else {
+                    1.0 - coverage
+                }
+            })
+            .product::<f64>();
+        let xyz = lab_to_xyz(*lab);
+        for channel in 0..3 {
+            root[channel] += weight * xyz[channel].sqrt();
+        }
+    }
+
+    // Il nero filtra la luce riflessa in proporzione alla copertura
+    [0, 1, 2].map(|channel| {
+        let filter = 1.0 - k + k * (black[channel] / paper[channel]).sqrt();
+        let xyz = (root[channel] * filter).powi(2);
+        xyz * D50[channel] / paper[channel]
+    })
+}
+
+/// Incremento del punto con massimo al 50%: t + 4 · gain · t · (1 - t)
+fn dot_gain(coverage: f64, gain: f64) -> f64 {
+    coverage + 4.0 * gain * coverage * (1.0 - coverage)
+}
+
+fn lab_to_xyz([l, a, b]: [f64; 3]) -> [f64; 3] {
+    let fy = (l + 16.0) / 116.0;
+    let f = [fy + a / 500.0, fy, fy - b / 200.0];
+    [0, 1, 2].map(|channel| {
+        let t = f[channel];
+        let ratio = if t > 6.0 / 29.0 {
+            t.powi(3)
+        } else {
+            3.0 * (6.0f64 / 29.0).powi(2) * (t - 4.0 / 29.0)
+        };
+        ratio * D50[channel]
+    })
+}
+
+fn xyz_to_lab(xyz: [f64; 3]) -> [f64; 3] {
+    let f = [0, 1, 2].map(|channel| {
+        let t = xyz[channel] / D50[channel];
+        if t > (6.0f64 / 29.0).powi(3) {
+            t.cbrt()
+        } else {
+            t / (3.0 * (6.0f64 / 29.0).powi(2)) + 4.0 / 29.0
+        }
+    });
+    [
+        116.0 * f[1] - 16.0,
+        500.0 * (f[0] - f[1]),
+        200.0 * (f[1] - f[2]),
+    ]
+}
+
+/// Codifica Lab v2 a 16 bit: L · 652.80, (a + 128) · 256
+fn encode_lab([l, a, b]: [f64; 3]) -> [u16; 3] {
+    [l * 652.8, (a + 128.0) * 256.0, (b + 128.0) * 256.0]
+        .map(|value| value.round().clamp(0.0, 65535.0) as u16)
+}
+
+fn s15_fixed16(value: f64) -> [u8; 4] {
+    ((value * 65536.0).round() as i32).to_be_bytes()
+}
+
+fn xyz_tag(xyz: [f64; 3]) -> Vec<u8> {
+    let mut tag = b"XYZ \0\0\0\0".to_vec();
+    for value in xyz {
+        tag.extend_from_slice(&s15_fixed16(value));
+    }
+    tag
+}
+
+fn text_tag(text: &str) -> Vec<u8> {
+    let mut tag = b"text\0\0\0\0".to_vec();
+    tag.extend_from_slice(text.as_bytes());
+    tag.push(0);
+    tag
+}
+
+/// textDescriptionType (v2): solo la parte ASCII, Unicode e ScriptCode vuoti
+fn desc_tag(description: &str) -> Vec<u8> {
+    let mut tag = b"desc\0\0\0\0".to_vec();
+    tag.extend_from_slice(&(description.len() as u32 + 1).to_be_bytes());
+    tag.extend_from_slice(description.as_bytes());
+    tag.push(0);
+    tag.extend_from_slice(&[0; 8]);
+    tag.extend_from_slice(&[0; 3]);
+    tag.extend_from_slice(&[0; 67]);
+    tag
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lcms2::{Intent, PixelFormat, Profile, Transform};
+
+    fn to_srgb(cmyk: &[[u8; 4]]) -> Vec<[u8; 3]> {
+        let source = Profile::new_icc(default_cmyk_icc()).unwrap();
+        let transform = Transform::new(
+            &source,
+            PixelFormat::CMYK_8,
+            &Profile::new_srgb(),
+            PixelFormat::RGB_8,
+            Intent::RelativeColorimetric,
+        )
+        .unwrap();
+        let mut output = vec![[0u8; 3]; cmyk.len()];
+        transform.transform_pixels(cmyk, &mut output);
+        output
+    }
+
+    #[test]
+    fn test_default_profile_is_valid_cmyk() {
+        let icc = default_cmyk_icc();
+        assert_eq!(&icc[16..20], b"CMYK");
+        assert_eq!(
+            u32::from_be_bytes(icc[0..4].try_into().unwrap()) as usize,
+            icc.len()
+        );
+
+        let profile = Profile::new_icc(icc).unwrap();
+        assert_eq!(profile.color_space(), lcms2::ColorSpaceSignature::CmykData);
+    }
+
+    #[test]
+    fn test_default_profile_colors() {
+        let rgb = to_srgb(&[
+            [0, 0, 0, 0],
+            [255, 0, 0, 0],
+            [0, 255, 0, 0],
+            [0, 0, 255, 0],
+            [0, 0, 0, 255],
+            [128, 128, 128, 0],
+        ]);
+
+        // Carta bianca (relativo colorimetrico)
+        assert!(rgb[0].iter().all(|&channel| channel >= 250), "{:?}", rgb[0]);
+        // Ciano, magenta e giallo pieni
+        assert!(
+            rgb[1][0] < 80 && rgb[1][1] > 120 && rgb[1][2] > 180,
+            "{:?}",
+            rgb[1]
+        );
+        assert!(
+            rgb[2][0] > 180 && rgb[2][1] < 80 && rgb[2][2] > 100,
+            "{:?}",
+            rgb[2]
+        );
+        assert!(
+            rgb[3][0] > 220 && rgb[3][1] > 200 && rgb[3][2] < 80,
+            "{:?}",
+            rgb[3]
+        );
+        // Nero pieno scuro e neutro
+        assert!(rgb[4].iter().all(|&channel| channel < 70), "{:?}", rgb[4]);
+        // L'incremento del punto scurisce il 50%
+        assert!(rgb[5].iter().all(|&channel| channel < 140), "{:?}", rgb[5]);
+    }
+}
@@ -1,11 +1,24 @@
 // src-tauri/src/core/color_management.rs
 
-use crate::core::color_profile::ColorProfile;
-use image::{DynamicImage, ImageBuffer, Rgb, Rgba};
-use lcms2::{Intent, PixelFormat, Profile, ToneCurve, Transform};
+use crate::core::cmyk_profile::default_cmyk_icc;
+use crate::core::color_profile::{Chromaticities, ColorProfile, PngColorChunks};
+use crate::core::image_decoder::CmykImage;
+use crate::core::settings::OutputColorSpace;
+use crate::core::transform_cache::{
+    profile_hash, transform_rows, SharedTransform, TransformCache, TransformKey,
+};
+use image::{DynamicImage, GrayImage, ImageBuffer, Rgb, Rgba, Rgba32FImage};
+use lcms2::{
+    ColorSpaceSignature, Flags, Intent, Locale, PixelFormat, Profile, Tag, TagSignature,
+    ThreadContext, ToneCurve, Transform, MLU,
+};
+use std::sync::OnceLock;
+
+/// Numero di campioni delle tabelle di trasferimento (interpolate linearmente)
+const TRANSFER_LUT_SIZE: usize = 4096;
 
 /// Intento di rendering per la conversione dei colori
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RenderingIntent {
     /// Perceptual - Mantiene le relazioni tra i colori (migliore per foto)
     Perceptual,
@@ -28,114 +41,604 @@ impl RenderingIntent {
     }
 }
 
-/// Manager per le conversioni di profilo colore
+/// Parametri di una conversione: intento e compensazione del punto nero (BPC).
+/// La BPC evita di schiacciare le ombre in colorimetrico relativo da sorgenti ProPhoto.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConversionSettings {
+    pub intent: RenderingIntent,
+    pub black_point_compensation: bool,
+}
+
+impl ConversionSettings {
+    pub fn new(intent: RenderingIntent, black_point_compensation: bool) -> Self {
+        Self {
+            intent,
+            black_point_compensation,
+        }
+    }
+}
+
+impl From<RenderingIntent> for ConversionSettings {
+    fn from(intent: RenderingIntent) -> Self {
+        Self::new(intent, false)
+    }
+}
+
+/// Tabella di trasferimento campionata da una ToneCurve LCMS2.
+/// Evita di chiamare LCMS2 per ogni canale di ogni pixel.
+pub struct TransferLut {
+    table: Vec<f32>,
+}
+
+impl TransferLut {
+    /// Campiona la curva su [0, 1]
+    pub fn from_curve(curve: &ToneCurve) -> Self {
+        let last = (TRANSFER_LUT_SIZE - 1) as f32;
+        let table = (0..TRANSFER_LUT_SIZE)
+            .map(|i| curve.eval(i as f32 / last))
+            .collect();
+
+        Self { table }
+    }
+
+    /// Valuta la curva con interpolazione lineare tra i campioni
+    pub fn apply(&self, value: f32) -> f32 {
+        let last = self.table.len() - 1;
+        let pos = value.clamp(0.0, 1.0) * last as f32;
+        let index = (pos as usize).min(last - 1);
+        let frac = pos - index as f32;
+
+        self.table[index] + (self.table[index + 1] - self.table[index]) * frac
+    }
+}
+
+/// Tabella sRGB -> lineare (condivisa tra i thread)
+fn srgb_to_linear_lut() -> &'static TransferLut {
+    static LUT: OnceLock<TransferLut> = OnceLock::new();
+    LUT.get_or_init(|| TransferLut::from_curve(&ColorManager::srgb_tone_curve()))
+}
+
+/// Tabella lineare -> sRGB (condivisa tra i thread)
+fn linear_to_srgb_lut() -> &'static TransferLut {
+    static LUT: OnceLock<TransferLut> = OnceLock::new();
+    LUT.get_or_init(|| TransferLut::from_curve(&ColorManager::srgb_tone_curve().reversed()))
+}
+
+/// Converte un'immagine sRGB in luce lineare (RGBA f32) con alpha premoltiplicato.
+/// Il premoltiplicato evita aloni scuri sui bordi trasparenti durante il resize.
+pub fn to_linear_premultiplied(img: &DynamicImage) -> Rgba32FImage {
+    let lut = srgb_to_linear_lut();
+    let mut linear = img.to_rgba32f();
+
+    for pixel in linear.pixels_mut() {
+        let alpha = pixel[3];
+        for channel in 0..3 {
+            pixel[channel] = lut.apply(pixel[channel]) * alpha;
+        }
+    }
+
+    linear
+}
+
+/// Riporta un'immagine lineare premoltiplicata in sRGB (RGBA f32, alpha dritto)
+pub fn from_linear_premultiplied(linear: &Rgba32FImage) -> Rgba32FImage {
+    let lut = linear_to_srgb_lut();
+    let mut encoded = linear.clone();
+
+    for pixel in encoded.pixels_mut() {
+        let alpha = pixel[3].clamp(0.0, 1.0);
+        for channel in 0..3 {
+            pixel[channel] = if alpha > 0.0 {
+                lut.apply(pixel[channel] / alpha)
+            } else {
+                0.0
+            };
+        }
+        pixel[3] = alpha;
+    }
+
+    encoded
+}
+
+/// Manager per le conversioni di profilo colore.
+/// Non contiene profili LCMS2: può essere condiviso tra i worker Rayon,
+/// le trasformazioni vengono prese dalla cache globale.
 pub struct ColorManager {
-    srgb_profile: Profile,
+    destination: OutputColorSpace,
+    destination_icc: Vec<u8>,
+    destination_id: u64,
+    /// ID MD5 del profilo di destinazione (header ICC, byte 84-99)
+    destination_profile_id: Option<[u8; 16]>,
 }
 
 impl ColorManager {
-    /// Crea un nuovo ColorManager con i profili predefiniti
+    /// Crea un nuovo ColorManager con destinazione sRGB
     pub fn new() -> Result<Self, String> {
-        // Crea profilo sRGB usando il profilo built-in di LCMS2
-        let srgb_profile = Profile::new_srgb();
+        Self::with_destination(&OutputColorSpace::Srgb)
+    }
+
+    /// Crea un ColorManager che converte verso lo spazio colore indicato
+    pub fn with_destination(destination: &OutputColorSpace) -> Result<Self, String> {
+        let destination_profile = match destination {
+            // Profilo sRGB built-in di LCMS2
+            OutputColorSpace::Srgb => Profile::new_srgb(),
+            OutputColorSpace::DisplayP3 => Self::create_display_p3_profile()?,
+            OutputColorSpace::AdobeRgb => Self::create_adobe_rgb_profile()?,
+            OutputColorSpace::Custom { path } => {
+                let icc_data = std::fs::read(path)
+                    .map_err(|e| format!("Failed to read ICC profile {}: {}", path, e))?;
+                let profile = Profile::new_icc(&icc_data)
+                    .map_err(|e| format!("Failed to parse ICC profile {}: {}", path, e))?;
 
-        Ok(Self { srgb_profile })
+                if profile.color_space() != ColorSpaceSignature::RgbData {
+                    return Err(format!(
+                        "Output ICC profile must be RGB, got {:?}",
+                        profile.color_space()
+                    ));
+                }
+
+                // Per i profili custom conserva i byte originali del file
+                return Ok(Self {
+                    destination: destination.clone(),
+                    destination_id: profile_hash(&icc_data),
+                    destination_profile_id: icc_profile_id(&icc_data),
+                    destination_icc: icc_data,
+                });
+            }
+        };
+
+        let destination_icc = destination_profile
+            .icc()
+            .map_err(|e| format!("Failed to serialize ICC profile: {}", e))?;
+
+        Ok(Self {
+            destination: destination.clone(),
+            destination_id: profile_hash(&destination_icc),
+            destination_profile_id: icc_profile_id(&destination_icc),
+            destination_icc,
+        })
     }
 
-    /// Converte un'immagine da un profilo colore sorgente a sRGB
-    pub fn convert_to_srgb(
+    /// True se la destinazione è sRGB (il default del web)
+    pub fn is_srgb_destination(&self) -> bool {
+        self.destination == OutputColorSpace::Srgb
+    }
+
+    /// True se il profilo ICC indicato equivale a quello di destinazione
+    /// (i pixel sono già nello spazio colore di output): stessi byte, stesso
+    /// ID MD5 o, per i profili RGB, stessa colorimetria (es. lo sRGB
+    /// IEC61966-2.1 delle fotocamere rispetto allo sRGB built-in di LCMS2)
+    pub fn is_destination_icc(&self, icc_data: &[u8]) -> bool {
+        if icc_data == self.destination_icc.as_slice() {
+            return true;
+        }
+        if self.destination_profile_id.is_some()
+            && icc_profile_id(icc_data) == self.destination_profile_id
+        {
+            return true;
+        }
+        self.is_colorimetric_match(icc_data)
+    }
+
+    /// Una griglia di colori RGB convertita verso la destinazione resta
+    /// invariata (entro un livello di arrotondamento)
+    fn is_colorimetric_match(&self, icc_data: &[u8]) -> bool {
+        if icc_color_space(icc_data) != Some(b"RGB ") {
+            return false;
+        }
+        let Ok(transform) = self.transform(
+            profile_hash(icc_data),
+            || {
+                Profile::new_icc(icc_data)
+                    .map_err(|e| format!("Failed to parse ICC profile: {}", e))
+            },
+            PixelFormat::RGB_8,
+            PixelFormat::RGB_8,
+            RenderingIntent::RelativeColorimetric.into(),
+        ) else {
+            return false;
+        };
+
+        const LEVELS: [u8; 6] = [0, 51, 102, 153, 204, 255];
+        let input: Vec<u8> = LEVELS
+            .iter()
+            .flat_map(|&r| LEVELS.iter().flat_map(move |&g| LEVELS.map(|b| [r, g, b])))
+            .flatten()
+            .collect();
+        let mut output = vec![0u8; input.len()];
+        transform.transform_pixels(&input, &mut output);
+
+        input
+            .iter()
+            .zip(&output)
+            .all(|(expected, actual)| expected.abs_diff(*actual) <= 1)
+    }
+
+    /// Profilo ICC di destinazione serializzato, da embeddare nell'output
+    pub fn destination_icc(&self) -> Result<Vec<u8>, String> {
+        Ok(self.destination_icc.clone())
+    }
+
+    /// Profilo LCMS2 di destinazione (creato solo quando manca una trasformazione in cache)
+    fn destination_profile(&self) -> Result<Profile, String> {
+        Profile::new_icc(&self.destination_icc)
+            .map_err(|e| format!("Failed to parse destination profile: {}", e))
+    }
+
+    /// Converte un'immagine da un profilo colore sorgente allo spazio di destinazione
+    pub fn convert_to_destination(
         &self,
         img: &DynamicImage,
         source_profile: &ColorProfile,
-        intent: RenderingIntent,
+        conversion: ConversionSettings,
     ) -> Result<DynamicImage, String> {
-        // Se è già sRGB, non fare nulla
-        if matches!(source_profile, ColorProfile::Srgb) {
+        // Se sorgente e destinazione sono già sRGB, non fare nulla
+        if matches!(source_profile, ColorProfile::Srgb) && self.is_srgb_destination() {
             return Ok(img.clone());
         }
 
-        // Ottieni il profilo sorgente
-        let source_lcms_profile = self.get_source_profile(source_profile)?;
+        // Il profilo sorgente viene costruito solo se la trasformazione non è in cache
+        let source_id = profile_hash(("named", format!("{:?}", source_profile)));
+        self.convert_image(
+            img,
+            source_id,
+            || Self::get_source_profile(source_profile),
+            conversion,
+        )
+    }
 
-        // Converti l'immagine
-        match img {
-            DynamicImage::ImageRgb8(rgb_img) => {
-                let converted = self.convert_rgb_image(
-                    rgb_img,
-                    &source_lcms_profile,
-                    &self.srgb_profile,
-                    intent,
-                )?;
-                Ok(DynamicImage::ImageRgb8(converted))
-            }
-            DynamicImage::ImageRgba8(rgba_img) => {
-                let converted = self.convert_rgba_image(
-                    rgba_img,
-                    &source_lcms_profile,
-                    &self.srgb_profile,
-                    intent,
-                )?;
-                Ok(DynamicImage::ImageRgba8(converted))
+    /// Converte un'immagine con profilo ICC embedded
+    pub fn convert_with_embedded_profile(
+        &self,
+        img: &DynamicImage,
+        icc_data: &[u8],
+        conversion: ConversionSettings,
+    ) -> Result<DynamicImage, String> {
+        let source_id = profile_hash(icc_data);
+        let source_profile = || {
+            Profile::new_icc(icc_data).map_err(|e| format!("Failed to parse ICC profile: {}", e))
+        };
+
+        match (icc_color_space(icc_data), img) {
+            (Some(b"RGB "), _) => self.convert_image(img, source_id, source_profile, conversion),
+            (Some(b"GRAY"), DynamicImage::ImageLuma8(gray_img)) => {
+                self.convert_gray_image(gray_img, source_id, source_profile, conversion)
             }
-            _ => {
-                // Per altri formati, converti prima in RGB8 e poi converti
-                let rgb_img = img.to_rgb8();
-                let converted = self.convert_rgb_image(
-                    &rgb_img,
-                    &source_lcms_profile,
-                    &self.srgb_profile,
-                    intent,
-                )?;
-                Ok(DynamicImage::ImageRgb8(converted))
+            (color_space, _) => Err(format!(
+                "Unsupported ICC color space {:?} for {:?} pixels",
+                color_space.map(|signature| String::from_utf8_lossy(signature).to_string()),
+                img.color()
+            )),
+        }
+    }
+
+    /// Converte pixel CMYK nello spazio di destinazione con il profilo CMYK indicato.
+    /// Senza profilo usa quello incorporato (offset patinato); la conversione ingenua
+    /// resta solo come ultima risorsa se LCMS2 non riesce a usarlo.
+    pub fn convert_cmyk(
+        &self,
+        cmyk: &CmykImage,
+        icc_data: Option<&[u8]>,
+        conversion: ConversionSettings,
+    ) -> Result<DynamicImage, String> {
+        if let Some(icc_data) = icc_data {
+            return self.convert_cmyk_with_profile(cmyk, icc_data, conversion);
+        }
+
+        println!("Warning: CMYK image without profile, using built-in default CMYK profile");
+        match self.convert_cmyk_with_profile(cmyk, default_cmyk_icc(), conversion) {
+            Ok(converted) => Ok(converted),
+            Err(e) => {
+                println!(
+                    "Warning: default CMYK profile failed ({}), using naive conversion",
+                    e
+                );
+                let rgb = DynamicImage::ImageRgb8(naive_cmyk_to_rgb(cmyk)?);
+                if self.is_srgb_destination() {
+                    return Ok(rgb);
+                }
+                self.convert_to_destination(&rgb, &ColorProfile::Srgb, conversion)
             }
         }
     }
 
-    /// Converte un'immagine con profilo ICC embedded
-    pub fn convert_with_embedded_profile(
+    fn convert_cmyk_with_profile(
         &self,
-        img: &DynamicImage,
+        cmyk: &CmykImage,
         icc_data: &[u8],
-        intent: RenderingIntent,
+        conversion: ConversionSettings,
+    ) -> Result<DynamicImage, String> {
+        if icc_color_space(icc_data) != Some(b"CMYK") {
+            return Err("Expected a CMYK profile".to_string());
+        }
+
+        let transform = self.transform(
+            profile_hash(icc_data),
+            || {
+                Profile::new_icc(icc_data)
+                    .map_err(|e| format!("Failed to parse CMYK profile: {}", e))
+            },
+            PixelFormat::CMYK_8,
+            PixelFormat::RGB_8,
+            conversion,
+        )?;
+
+        let mut output: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::new(cmyk.width, cmyk.height);
+        let width = cmyk.width as usize;
+        transform_rows(&transform, &cmyk.pixels, &mut output, width * 4, width * 3);
+
+        Ok(DynamicImage::ImageRgb8(output))
+    }
+
+    /// Maschera dei pixel fuori dal gamut di destinazione (255 = fuori gamut).
+    /// Usa il gamut check di LCMS2: una trasformazione di proofing verso Lab
+    /// marca con il codice di allarme i colori non riproducibili nella destinazione.
+    pub fn gamut_mask(
+        &self,
+        img: &DynamicImage,
+        source_icc: Option<&[u8]>,
+        source_profile: &ColorProfile,
+    ) -> Result<GrayImage, String> {
+        let source_icc = match source_icc {
+            Some(icc_data) if icc_color_space(icc_data) == Some(b"RGB ") => icc_data.to_vec(),
+            Some(_) => return Err("Gamut check requires an RGB source profile".to_string()),
+            None => Self::get_source_profile(source_profile)?
+                .icc()
+                .map_err(|e| format!("Failed to serialize source profile: {}", e))?,
+        };
+
+        // Allarme a zero: in Lab 16 bit corrisponde a L=0, a=-128, b=-128,
+        // un valore che nessun colore reale produce
+        let mut context = ThreadContext::new();
+        context.set_alarm_codes([0; 16]);
+
+        let source = Profile::new_icc_context(&context, &source_icc)
+            .map_err(|e| format!("Failed to parse source profile: {}", e))?;
+        let destination = Profile::new_icc_context(&context, &self.destination_icc)
+            .map_err(|e| format!("Failed to parse destination profile: {}", e))?;
+        let lab = Profile::new_lab4_context(
+            &context,
+            &lcms2::CIExyY {
+                x: 0.3457,
+                y: 0.3585,
+                Y: 1.0,
+            },
+        )
+        .map_err(|e| format!("Failed to create Lab profile: {}", e))?;
+
+        let transform: Transform<[u8; 3], [u16; 3], ThreadContext> =
+            Transform::new_proofing_context(
+                &context,
+                &source,
+                PixelFormat::RGB_8,
+                &lab,
+                PixelFormat::Lab_16,
+                &destination,
+                Intent::RelativeColorimetric,
+                Intent::RelativeColorimetric,
+                Flags::GAMUT_CHECK,
+            )
+            .map_err(|e| format!("Failed to create gamut check transform: {}", e))?;
+
+        let rgba = img.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let mut mask = GrayImage::new(width, height);
+        let mut input = vec![[0u8; 3]; width as usize];
+        let mut output = vec![[0u16; 3]; width as usize];
+
+        for (row, mask_row) in rgba.rows().zip(mask.chunks_exact_mut(width as usize)) {
+            for (rgb, pixel) in input.iter_mut().zip(row.clone()) {
+                *rgb = [pixel[0], pixel[1], pixel[2]];
+            }
+            transform.transform_pixels(&input, &mut output);
+
+            for ((mask_value, lab), pixel) in mask_row.iter_mut().zip(&output).zip(row) {
+                // I pixel completamente trasparenti non contano
+                if *lab == [0, 0, 0] && pixel[3] > 0 {
+                    *mask_value = 255;
+                }
+            }
+        }
+
+        Ok(mask)
+    }
+
+    /// Converte un'immagine in scala di grigi con profilo Gray.
+    /// Verso sRGB resta in scala di grigi (curva sRGB), altrimenti diventa RGB.
+    fn convert_gray_image(
+        &self,
+        img: &image::GrayImage,
+        source_id: u64,
+        source_profile: impl FnOnce() -> Result<Profile, String>,
+        conversion: ConversionSettings,
     ) -> Result<DynamicImage, String> {
-        let source_profile = Profile::new_icc(icc_data)
-            .map_err(|e| format!("Failed to parse ICC profile: {}", e))?;
+        let (width, height) = img.dimensions();
 
+        if self.is_srgb_destination() {
+            let key = TransformKey::new(
+                source_id,
+                profile_hash("gray-srgb"),
+                PixelFormat::GRAY_8,
+                PixelFormat::GRAY_8,
+                conversion,
+            );
+            let transform = TransformCache::global().get_or_create(key, source_profile, || {
+                let white_point = lcms2::CIExyY {
+                    x: 0.3127,
+                    y: 0.3290,
+                    Y: 1.0,
+                };
+                Profile::new_gray(&white_point, &Self::srgb_tone_curve())
+                    .map_err(|e| format!("Failed to create gray profile: {}", e))
+            })?;
+
+            let mut output = image::GrayImage::new(width, height);
+            transform_rows(
+                &transform,
+                img.as_raw(),
+                &mut output,
+                width as usize,
+                width as usize,
+            );
+            return Ok(DynamicImage::ImageLuma8(output));
+        }
+
+        let transform = self.transform(
+            source_id,
+            source_profile,
+            PixelFormat::GRAY_8,
+            PixelFormat::RGB_8,
+            conversion,
+        )?;
+
+        let mut output: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::new(width, height);
+        transform_rows(
+            &transform,
+            img.as_raw(),
+            &mut output,
+            width as usize,
+            width as usize * 3,
+        );
+        Ok(DynamicImage::ImageRgb8(output))
+    }
+
+    /// Converte i pixel dal profilo sorgente al profilo di destinazione
+    fn convert_image(
+        &self,
+        img: &DynamicImage,
+        source_id: u64,
+        source_profile: impl FnOnce() -> Result<Profile, String>,
+        conversion: ConversionSettings,
+    ) -> Result<DynamicImage, String> {
         match img {
             DynamicImage::ImageRgb8(rgb_img) => {
                 let converted =
-                    self.convert_rgb_image(rgb_img, &source_profile, &self.srgb_profile, intent)?;
+                    self.convert_rgb_image(rgb_img, source_id, source_profile, conversion)?;
                 Ok(DynamicImage::ImageRgb8(converted))
             }
             DynamicImage::ImageRgba8(rgba_img) => {
                 let converted =
-                    self.convert_rgba_image(rgba_img, &source_profile, &self.srgb_profile, intent)?;
+                    self.convert_rgba_image(rgba_img, source_id, source_profile, conversion)?;
+                Ok(DynamicImage::ImageRgba8(converted))
+            }
+            // Oltre 8 bit per canale: trasformazione a 16 bit, senza perdere precisione
+            _ if img.color().bytes_per_pixel() > img.color().channel_count() => {
+                let (width, height) = (img.width(), img.height());
+                if img.color().has_alpha() {
+                    let pixels = self.convert_16bit_pixels(
+                        img.to_rgba16().as_raw(),
+                        width,
+                        4,
+                        source_id,
+                        source_profile,
+                        conversion,
+                    )?;
+                    ImageBuffer::from_raw(width, height, pixels)
+                        .map(DynamicImage::ImageRgba16)
+                        .ok_or_else(|| "Invalid 16-bit buffer size".to_string())
+                } else {
+                    let pixels = self.convert_16bit_pixels(
+                        img.to_rgb16().as_raw(),
+                        width,
+                        3,
+                        source_id,
+                        source_profile,
+                        conversion,
+                    )?;
+                    ImageBuffer::from_raw(width, height, pixels)
+                        .map(DynamicImage::ImageRgb16)
+                        .ok_or_else(|| "Invalid 16-bit buffer size".to_string())
+                }
+            }
+            _ if img.color().has_alpha() => {
+                let rgba_img = img.to_rgba8();
+                let converted =
+                    self.convert_rgba_image(&rgba_img, source_id, source_profile, conversion)?;
                 Ok(DynamicImage::ImageRgba8(converted))
             }
             _ => {
+                // Per altri formati, converti prima in RGB8 e poi converti
                 let rgb_img = img.to_rgb8();
                 let converted =
-                    self.convert_rgb_image(&rgb_img, &source_profile, &self.srgb_profile, intent)?;
+                    self.convert_rgb_image(&rgb_img, source_id, source_profile, conversion)?;
                 Ok(DynamicImage::ImageRgb8(converted))
             }
         }
     }
 
+    /// Trasformazione (dalla cache) dal profilo sorgente alla destinazione
+    fn transform(
+        &self,
+        source_id: u64,
+        source_profile: impl FnOnce() -> Result<Profile, String>,
+        input_format: PixelFormat,
+        output_format: PixelFormat,
+        conversion: ConversionSettings,
+    ) -> Result<SharedTransform, String> {
+        let key = TransformKey::new(
+            source_id,
+            self.destination_id,
+            input_format,
+            output_format,
+            conversion,
+        );
+        TransformCache::global().get_or_create(key, source_profile, || self.destination_profile())
+    }
+
+    /// Costruisce un profilo ICC equivalente ai chunk PNG sRGB/gAMA/cHRM.
+    /// Senza cHRM si assumono le primarie sRGB; senza gAMA la curva sRGB.
+    pub fn icc_from_png_chunks(chunks: &PngColorChunks) -> Result<Vec<u8>, String> {
+        let profile = if chunks.srgb {
+            Profile::new_srgb()
+        } else {
+            let chrm = chunks.chromaticities.unwrap_or(Chromaticities::SRGB);
+            let xy_y = |(x, y): (f64, f64)| lcms2::CIExyY { x, y, Y: 1.0 };
+            let primaries = lcms2::CIExyYTRIPLE {
+                Red: xy_y(chrm.red),
+                Green: xy_y(chrm.green),
+                Blue: xy_y(chrm.blue),
+            };
+
+            // gAMA è la gamma di codifica: la curva di decodifica è il suo inverso
+            let curve = match chunks.gamma {
+                Some(gamma) if gamma > 0.0 => ToneCurve::new(1.0 / gamma),
+                _ => Self::srgb_tone_curve(),
+            };
+            let transfer_function = [&curve, &curve, &curve];
+
+            let mut profile =
+                Profile::new_rgb(&xy_y(chrm.white), &primaries, &transfer_function)
+                    .map_err(|e| format!("Failed to create profile from PNG chunks: {}", e))?;
+            set_profile_description(&mut profile, "PNG gAMA/cHRM");
+            profile
+        };
+
+        profile
+            .icc()
+            .map_err(|e| format!("Failed to serialize ICC profile: {}", e))
+    }
+
+    /// Curva di trasferimento sRGB (IEC 61966-2-1): tratto lineare + gamma 2.4
+    pub fn srgb_tone_curve() -> ToneCurve {
+        ToneCurve::new_parametric(4, &[2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045])
+            .unwrap_or_else(|_| ToneCurve::new(2.2))
+    }
+
     /// Ottiene il profilo LCMS2 corrispondente al ColorProfile
-    fn get_source_profile(&self, source: &ColorProfile) -> Result<Profile, String> {
+    fn get_source_profile(source: &ColorProfile) -> Result<Profile, String> {
         match source {
             ColorProfile::Srgb => Ok(Profile::new_srgb()),
             ColorProfile::AdobeRgb => {
                 // Crea profilo Adobe RGB (1998)
-                self.create_adobe_rgb_profile()
+                Self::create_adobe_rgb_profile()
             }
             ColorProfile::DisplayP3 => {
                 // Crea profilo Display P3
-                self.create_display_p3_profile()
+                Self::create_display_p3_profile()
             }
             ColorProfile::ProPhotoRgb => {
                 // Crea profilo ProPhoto RGB
-                self.create_prophoto_rgb_profile()
+                Self::create_prophoto_rgb_profile()
             }
             ColorProfile::Unknown(_) => {
                 // Per profili sconosciuti, assumiamo sRGB come fallback
@@ -146,7 +649,7 @@ impl ColorManager {
     }
 
     /// Crea un profilo Adobe RGB (1998)
-    fn create_adobe_rgb_profile(&self) -> Result<Profile, String> {
+    fn create_adobe_rgb_profile() -> Result<Profile, String> {
         // Primarie Adobe RGB (1998)
         let primaries = lcms2::CIExyYTRIPLE {
             Red: lcms2::CIExyY {
@@ -173,16 +676,18 @@ impl ColorManager {
             Y: 1.0,
         };
 
-        // Gamma 2.2 (Adobe RGB usa gamma 2.2)
-        let gamma_curve = ToneCurve::new(2.2);
+        // Adobe RGB (1998) usa gamma 563/256 (≈ 2.2)
+        let gamma_curve = ToneCurve::new(2.199_218_75);
         let transfer_function = [&gamma_curve, &gamma_curve, &gamma_curve];
 
-        Profile::new_rgb(&white_point, &primaries, &transfer_function)
-            .map_err(|e| format!("Failed to create Adobe RGB profile: {}", e))
+        let mut profile = Profile::new_rgb(&white_point, &primaries, &transfer_function)
+            .map_err(|e| format!("Failed to create Adobe RGB profile: {}", e))?;
+        set_profile_description(&mut profile, "Adobe RGB (1998)");
+        Ok(profile)
     }
 
     /// Crea un profilo Display P3
-    fn create_display_p3_profile(&self) -> Result<Profile, String> {
+    fn create_display_p3_profile() -> Result<Profile, String> {
         // Primarie Display P3
         let primaries = lcms2::CIExyYTRIPLE {
             Red: lcms2::CIExyY {
@@ -209,16 +714,18 @@ impl ColorManager {
             Y: 1.0,
         };
 
-        // Display P3 usa la stessa gamma di sRGB (2.2)
-        let gamma_curve = ToneCurve::new(2.2);
+        // Display P3 usa la curva di trasferimento sRGB (non una gamma 2.2 pura)
+        let gamma_curve = Self::srgb_tone_curve();
         let transfer_function = [&gamma_curve, &gamma_curve, &gamma_curve];
 
-        Profile::new_rgb(&white_point, &primaries, &transfer_function)
-            .map_err(|e| format!("Failed to create Display P3 profile: {}", e))
+        let mut profile = Profile::new_rgb(&white_point, &primaries, &transfer_function)
+            .map_err(|e| format!("Failed to create Display P3 profile: {}", e))?;
+        set_profile_description(&mut profile, "Display P3");
+        Ok(profile)
     }
 
     /// Crea un profilo ProPhoto RGB
-    fn create_prophoto_rgb_profile(&self) -> Result<Profile, String> {
+    fn create_prophoto_rgb_profile() -> Result<Profile, String> {
         // Primarie ProPhoto RGB (ROMM RGB)
         let primaries = lcms2::CIExyYTRIPLE {
             Red: lcms2::CIExyY {
@@ -257,37 +764,30 @@ impl ColorManager {
     fn convert_rgb_image(
         &self,
         img: &ImageBuffer<Rgb<u8>, Vec<u8>>,
-        source_profile: &Profile,
-        dest_profile: &Profile,
-        intent: RenderingIntent,
+        source_id: u64,
+        source_profile: impl FnOnce() -> Result<Profile, String>,
+        conversion: ConversionSettings,
     ) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, String> {
         let (width, height) = img.dimensions();
         let mut output = ImageBuffer::new(width, height);
 
-        // Crea la trasformazione
-        let transform = Transform::new(
+        let transform = self.transform(
+            source_id,
             source_profile,
             PixelFormat::RGB_8,
-            dest_profile,
             PixelFormat::RGB_8,
-            intent.to_lcms2(),
-        )
-        .map_err(|e| format!("Failed to create transform: {}", e))?;
+            conversion,
+        )?;
 
-        // Converti l'immagine riga per riga per efficienza
+        // Converti l'immagine riga per riga (in parallelo per le immagini grandi)
         let bytes_per_row = (width * 3) as usize;
-        let input_data = img.as_raw();
-        let output_data = output.as_mut();
-
-        for row in 0..height as usize {
-            let input_offset = row * bytes_per_row;
-            let output_offset = row * bytes_per_row;
-
-            let input_row = &input_data[input_offset..input_offset + bytes_per_row];
-            let output_row = &mut output_data[output_offset..output_offset + bytes_per_row];
-
-            transform.transform_pixels(input_row, output_row);
-        }
+        transform_rows(
+            &transform,
+            img.as_raw(),
+            &mut output,
+            bytes_per_row,
+            bytes_per_row,
+        );
 
         Ok(output)
     }
@@ -296,39 +796,114 @@ impl ColorManager {
     fn convert_rgba_image(
         &self,
         img: &ImageBuffer<Rgba<u8>, Vec<u8>>,
-        source_profile: &Profile,
-        dest_profile: &Profile,
-        intent: RenderingIntent,
+        source_id: u64,
+        source_profile: impl FnOnce() -> Result<Profile, String>,
+        conversion: ConversionSettings,
     ) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, String> {
         let (width, height) = img.dimensions();
         let mut output = ImageBuffer::new(width, height);
 
-        // Crea la trasformazione per RGBA
-        let transform = Transform::new(
+        let transform = self.transform(
+            source_id,
             source_profile,
             PixelFormat::RGBA_8,
-            dest_profile,
             PixelFormat::RGBA_8,
-            intent.to_lcms2(),
-        )
-        .map_err(|e| format!("Failed to create transform: {}", e))?;
+            conversion,
+        )?;
 
-        // Converti l'immagine riga per riga
+        // Converti l'immagine riga per riga (in parallelo per le immagini grandi)
         let bytes_per_row = (width * 4) as usize;
-        let input_data = img.as_raw();
-        let output_data = output.as_mut();
+        transform_rows(
+            &transform,
+            img.as_raw(),
+            &mut output,
+            bytes_per_row,
+            bytes_per_row,
+        );
 
-        for row in 0..height as usize {
-            let input_offset = row * bytes_per_row;
-            let output_offset = row * bytes_per_row;
+        Ok(output)
+    }
 
-            let input_row = &input_data[input_offset..input_offset + bytes_per_row];
-            let output_row = &mut output_data[output_offset..output_offset + bytes_per_row];
+    /// Converte pixel RGB/RGBA a 16 bit per canale (ordine dei byte nativo)
+    fn convert_16bit_pixels(
+        &self,
+        pixels: &[u16],
+        width: u32,
+        channels: usize,
+        source_id: u64,
+        source_profile: impl FnOnce() -> Result<Profile, String>,
+        conversion: ConversionSettings,
+    ) -> Result<Vec<u16>, String> {
+        let format = if channels == 4 {
+            PixelFormat::RGBA_16
+        } else {
+            PixelFormat::RGB_16
+        };
+        let transform = self.transform(source_id, source_profile, format, format, conversion)?;
 
-            transform.transform_pixels(input_row, output_row);
-        }
+        let input: Vec<u8> = pixels
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        let mut output = vec![0u8; input.len()];
+        let bytes_per_row = width as usize * channels * 2;
+        transform_rows(
+            &transform,
+            &input,
+            &mut output,
+            bytes_per_row,
+            bytes_per_row,
+        );
 
-        Ok(output)
+        Ok(output
+            .chunks_exact(2)
+            .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]))
+            .collect())
+    }
+}
+
+/// ID del profilo (MD5 nell'header, byte 84-99); se manca lo calcola LCMS2
+/// come da specifica ICC (flag, intento e ID azzerati)
+fn icc_profile_id(icc_data: &[u8]) -> Option<[u8; 16]> {
+    let stored: [u8; 16] = icc_data.get(84..100)?.try_into().ok()?;
+    if stored != [0; 16] {
+        return Some(stored);
+    }
+
+    let mut profile = Profile::new_icc(icc_data).ok()?;
+    profile.set_default_profile_id();
+    let mut id = [0u8; 16];
+    for (bytes, word) in id.chunks_exact_mut(4).zip(profile.profile_id().ID32) {
+        bytes.copy_from_slice(&word.to_ne_bytes());
+    }
+    Some(id)
+}
+
+/// Spazio colore dichiarato nell'header ICC (byte 16-19), es. "RGB ", "GRAY", "CMYK"
+pub(crate) fn icc_color_space(icc_data: &[u8]) -> Option<&[u8; 4]> {
+    icc_data.get(16..20)?.try_into().ok()
+}
+
+/// Conversione CMYK -> RGB senza profilo: R = 255 · (1 - C) · (1 - K)
+fn naive_cmyk_to_rgb(cmyk: &CmykImage) -> Result<image::RgbImage, String> {
+    let pixels = cmyk
+        .pixels
+        .chunks_exact(4)
+        .flat_map(|px| {
+            let white = 255 - px[3] as u32;
+            [0, 1, 2].map(|channel| ((255 - px[channel] as u32) * white / 255) as u8)
+        })
+        .collect();
+
+    image::RgbImage::from_raw(cmyk.width, cmyk.height, pixels)
+        .ok_or_else(|| "Invalid CMYK buffer size".to_string())
+}
+
+/// Imposta la descrizione del profilo (il nome mostrato da browser e editor)
+fn set_profile_description(profile: &mut Profile, description: &str) {
+    let mut mlu = MLU::new(1);
+    if mlu.set_text_ascii(description, Locale::none()) {
+        profile.write_tag(TagSignature::ProfileDescriptionTag, Tag::MLU(&mlu));
     }
 }
 
@@ -350,22 +925,19 @@ mod tests {
 
     #[test]
     fn test_adobe_rgb_profile() {
-        let manager = ColorManager::new().unwrap();
-        let profile = manager.create_adobe_rgb_profile();
+        let profile = ColorManager::create_adobe_rgb_profile();
         assert!(profile.is_ok());
     }
 
     #[test]
     fn test_display_p3_profile() {
-        let manager = ColorManager::new().unwrap();
-        let profile = manager.create_display_p3_profile();
+        let profile = ColorManager::create_display_p3_profile();
         assert!(profile.is_ok());
     }
 
     #[test]
     fn test_prophoto_rgb_profile() {
-        let manager = ColorManager::new().unwrap();
-        let profile = manager.create_prophoto_rgb_profile();
+        let profile = ColorManager::create_prophoto_rgb_profile();
         assert!(profile.is_ok());
     }
 
@@ -373,8 +945,400 @@ mod tests {
     fn test_srgb_no_conversion() {
         let manager = ColorManager::new().unwrap();
         let img = DynamicImage::new_rgb8(100, 100);
-        let result =
-            manager.convert_to_srgb(&img, &ColorProfile::Srgb, RenderingIntent::Perceptual);
+        let result = manager.convert_to_destination(
+            &img,
+            &ColorProfile::Srgb,
+            RenderingIntent::Perceptual.into(),
+        );
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_is_destination_icc() {
+        let srgb = ColorManager::new().unwrap();
+        let display_p3 = ColorManager::with_destination(&OutputColorSpace::DisplayP3).unwrap();
+        let srgb_icc = Profile::new_srgb().icc().unwrap();
+        let p3_icc = display_p3.destination_icc().unwrap();
+
+        assert!(srgb.is_destination_icc(&srgb_icc));
+        assert!(!srgb.is_destination_icc(&p3_icc));
+        assert!(display_p3.is_destination_icc(&p3_icc));
+        assert!(!display_p3.is_destination_icc(&srgb_icc));
+
+        // Stesso profilo con flag diversi nell'header: l'ID MD5 li ignora
+        let mut flagged_icc = srgb_icc.clone();
+        flagged_icc[44..48].copy_from_slice(&[0, 0, 0, 1]);
+        assert_eq!(icc_profile_id(&flagged_icc), icc_profile_id(&srgb_icc));
+        assert!(srgb.is_destination_icc(&flagged_icc));
+
+        // sRGB delle fotocamere: byte e descrizione diversi, stessa colorimetria
+        let d65 = lcms2::CIExyY {
+            x: 0.3127,
+            y: 0.3290,
+            Y: 1.0,
+        };
+        let primaries = lcms2::CIExyYTRIPLE {
+            Red: lcms2::CIExyY {
+                x: 0.64,
+                y: 0.33,
+                Y: 1.0,
+            },
+            Green: lcms2::CIExyY {
+                x: 0.30,
+                y: 0.60,
+                Y: 1.0,
+            },
+            Blue: lcms2::CIExyY {
+                x: 0.15,
+                y: 0.06,
+                Y: 1.0,
+            },
+        };
+        let curve = ColorManager::srgb_tone_curve();
+        let mut camera = Profile::new_rgb(&d65, &primaries, &[&curve, &curve, &curve]).unwrap();
+        set_profile_description(&mut camera, "sRGB IEC61966-2.1");
+        let camera_icc = camera.icc().unwrap();
+        assert_ne!(camera_icc, srgb_icc);
+        assert!(srgb.is_destination_icc(&camera_icc));
+        assert!(!display_p3.is_destination_icc(&camera_icc));
+
+        // Gamma 2.2 al posto della curva sRGB: colorimetria diversa
+        let gamma = ToneCurve::new(2.2);
+        let gamma_icc = Profile::new_rgb(&d65, &primaries, &[&gamma, &gamma, &gamma])
+            .unwrap()
+            .icc()
+            .unwrap();
+        assert!(!srgb.is_destination_icc(&gamma_icc));
+    }
+
+    #[test]
+    fn test_convert_16bit_keeps_depth_and_alpha() {
+        let display_p3 = ColorManager::with_destination(&OutputColorSpace::DisplayP3).unwrap();
+        let conversion = RenderingIntent::RelativeColorimetric.into();
+
+        // Valori a 16 bit non rappresentabili con 8 bit
+        let rgb16 = DynamicImage::ImageRgb16(ImageBuffer::from_fn(64, 1, |x, _| {
+            let value = 30_000 + x as u16;
+            Rgb([value, value, value])
+        }));
+        let converted = display_p3
+            .convert_to_destination(&rgb16, &ColorProfile::Srgb, conversion)
+            .unwrap();
+        let DynamicImage::ImageRgb16(converted) = converted else {
+            panic!("expected a 16-bit RGB image");
+        };
+        // I grigi restano grigi e i livelli vicini restano distinti
+        let first = converted.get_pixel(0, 0)[1];
+        let last = converted.get_pixel(63, 0)[1];
+        assert!(last > first && last - first > 32, "{} {}", first, last);
+
+        let rgba16 = DynamicImage::ImageRgba16(ImageBuffer::from_pixel(
+            2,
+            2,
+            Rgba([50_000, 2_000, 2_000, 12_345]),
+        ));
+        let converted = display_p3
+            .convert_to_destination(&rgba16, &ColorProfile::Srgb, conversion)
+            .unwrap();
+        assert!(matches!(converted, DynamicImage::ImageRgba16(_)));
+        assert_eq!(converted.to_rgba16().get_pixel(1, 1)[3], 12_345);
+
+        // Anche a 8 bit l'alpha viene copiato
+        let rgba8 =
+            DynamicImage::ImageRgba8(ImageBuffer::from_pixel(2, 2, Rgba([200, 10, 10, 77])));
+        let converted = display_p3
+            .convert_to_destination(&rgba8, &ColorProfile::Srgb, conversion)
+            .unwrap();
+        assert_eq!(converted.to_rgba8().get_pixel(0, 0)[3], 77);
+    }
+
+    #[test]
+    fn test_convert_with_embedded_srgb_profile() {
+        let manager = ColorManager::new().unwrap();
+        let icc = Profile::new_srgb().icc().unwrap();
+
+        let mut rgb = image::RgbImage::new(2, 2);
+        rgb.put_pixel(0, 0, Rgb([200, 100, 50]));
+        let img = DynamicImage::ImageRgb8(rgb);
+
+        // sRGB -> sRGB deve lasciare i valori (quasi) invariati
+        let converted = manager
+            .convert_with_embedded_profile(&img, &icc, RenderingIntent::RelativeColorimetric.into())
+            .unwrap()
+            .to_rgb8();
+        let pixel = converted.get_pixel(0, 0);
+        assert!(pixel[0].abs_diff(200) <= 1 && pixel[1].abs_diff(100) <= 1);
+
+        // Profili non RGB o non validi vengono rifiutati
+        assert!(manager
+            .convert_with_embedded_profile(
+                &img,
+                b"not a profile",
+                RenderingIntent::Perceptual.into()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_display_p3_uses_srgb_curve() {
+        // Un grigio neutro in Display P3 resta lo stesso grigio in sRGB
+        let manager = ColorManager::new().unwrap();
+        let img = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(1, 1, Rgb([64, 64, 64])));
+        let converted = manager
+            .convert_to_destination(
+                &img,
+                &ColorProfile::DisplayP3,
+                RenderingIntent::RelativeColorimetric.into(),
+            )
+            .unwrap()
+            .to_rgb8();
+        assert!(converted.get_pixel(0, 0)[1].abs_diff(64) <= 1);
+    }
+
+    #[test]
+    fn test_convert_srgb_to_display_p3() {
+        let manager = ColorManager::with_destination(&OutputColorSpace::DisplayP3).unwrap();
+        assert!(!manager.is_srgb_destination());
+
+        // Il rosso sRGB puro sta dentro il gamut P3: il canale rosso scende
+        let img = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(1, 1, Rgb([255, 0, 0])));
+        let converted = manager
+            .convert_to_destination(
+                &img,
+                &ColorProfile::Srgb,
+                RenderingIntent::RelativeColorimetric.into(),
+            )
+            .unwrap()
+            .to_rgb8();
+        let pixel = converted.get_pixel(0, 0);
+        assert!(pixel[0] < 245 && pixel[1] > 20);
+
+        // Il profilo da embeddare si riconosce come Display P3
+        let icc = manager.destination_icc().unwrap();
+        let description = Profile::new_icc(&icc)
+            .unwrap()
+            .info(lcms2::InfoType::Description, Locale::none())
+            .unwrap();
+        assert_eq!(
+            crate::core::color_profile::profile_from_name(&description),
+            ColorProfile::DisplayP3
+        );
+    }
+
+    #[test]
+    fn test_custom_destination_profile() {
+        let path = std::env::temp_dir().join("iron_test_custom_destination.icc");
+        std::fs::write(&path, Profile::new_srgb().icc().unwrap()).unwrap();
+
+        let destination = OutputColorSpace::Custom {
+            path: path.to_string_lossy().to_string(),
+        };
+        let manager = ColorManager::with_destination(&destination).unwrap();
+        assert_eq!(
+            manager.destination_icc().unwrap(),
+            std::fs::read(&path).unwrap()
+        );
+
+        std::fs::remove_file(&path).ok();
+
+        let missing = OutputColorSpace::Custom {
+            path: "/nonexistent/profile.icc".to_string(),
+        };
+        assert!(ColorManager::with_destination(&missing).is_err());
+    }
+
+    #[test]
+    fn test_convert_cmyk_without_profile() {
+        let manager = ColorManager::new().unwrap();
+        // Ciano pieno, nero pieno, carta bianca
+        let cmyk = CmykImage {
+            width: 3,
+            height: 1,
+            pixels: vec![255, 0, 0, 0, 0, 0, 0, 255, 0, 0, 0, 0],
+        };
+
+        let rgb = manager
+            .convert_cmyk(&cmyk, None, RenderingIntent::Perceptual.into())
+            .unwrap()
+            .to_rgb8();
+        // Profilo incorporato: ciano di stampa, nero non assoluto, carta bianca
+        let cyan = rgb.get_pixel(0, 0);
+        assert!(cyan[0] < 80 && cyan[1] > 120 && cyan[2] > 180, "{:?}", cyan);
+        assert!(rgb.get_pixel(1, 0).0.iter().all(|&channel| channel < 70));
+        assert!(rgb.get_pixel(2, 0).0.iter().all(|&channel| channel >= 250));
+
+        // Un profilo RGB non è accettato come sorgente CMYK
+        let srgb_icc = Profile::new_srgb().icc().unwrap();
+        assert!(manager
+            .convert_cmyk(&cmyk, Some(&srgb_icc), RenderingIntent::Perceptual.into())
+            .is_err());
+    }
+
+    #[test]
+    fn test_convert_gray_with_embedded_profile() {
+        let white_point = lcms2::CIExyY {
+            x: 0.3127,
+            y: 0.3290,
+            Y: 1.0,
+        };
+        let gray_icc = Profile::new_gray(&white_point, &ToneCurve::new(2.2))
+            .unwrap()
+            .icc()
+            .unwrap();
+        let img = DynamicImage::ImageLuma8(image::GrayImage::from_pixel(2, 2, image::Luma([128])));
+
+        // Verso sRGB resta in scala di grigi
+        let manager = ColorManager::new().unwrap();
+        let converted = manager
+            .convert_with_embedded_profile(&img, &gray_icc, RenderingIntent::Perceptual.into())
+            .unwrap();
+        assert_eq!(converted.color(), image::ColorType::L8);
+
+        // Verso P3 diventa RGB neutro
+        let manager = ColorManager::with_destination(&OutputColorSpace::DisplayP3).unwrap();
+        let converted = manager
+            .convert_with_embedded_profile(&img, &gray_icc, RenderingIntent::Perceptual.into())
+            .unwrap()
+            .to_rgb8();
+        let pixel = converted.get_pixel(0, 0);
+        assert!(pixel[0].abs_diff(pixel[2]) <= 1);
+    }
+
+    #[test]
+    fn test_black_point_compensation() {
+        // Sorgente con il nero sollevato (curva che parte dal 10%)
+        let white_point = lcms2::CIExyY {
+            x: 0.3127,
+            y: 0.3290,
+            Y: 1.0,
+        };
+        let primaries = lcms2::CIExyYTRIPLE {
+            Red: lcms2::CIExyY {
+                x: 0.64,
+                y: 0.33,
+                Y: 1.0,
+            },
+            Green: lcms2::CIExyY {
+                x: 0.30,
+                y: 0.60,
+                Y: 1.0,
+            },
+            Blue: lcms2::CIExyY {
+                x: 0.15,
+                y: 0.06,
+                Y: 1.0,
+            },
+        };
+        let table: Vec<u16> = (0..256u32)
+            .map(|i| (6554 + i * (65535 - 6554) / 255) as u16)
+            .collect();
+        let curve = ToneCurve::new_tabulated(&table);
+        let icc = Profile::new_rgb(&white_point, &primaries, &[&curve, &curve, &curve])
+            .unwrap()
+            .icc()
+            .unwrap();
+
+        let manager = ColorManager::new().unwrap();
+        let img = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(1, 1, Rgb([0, 0, 0])));
+
+        let without = manager
+            .convert_with_embedded_profile(&img, &icc, RenderingIntent::RelativeColorimetric.into())
+            .unwrap()
+            .to_rgb8();
+        let with = manager
+            .convert_with_embedded_profile(
+                &img,
+                &icc,
+                ConversionSettings::new(RenderingIntent::RelativeColorimetric, true),
+            )
+            .unwrap()
+            .to_rgb8();
+
+        // Con la BPC il nero della sorgente diventa il nero della destinazione
+        assert!(without.get_pixel(0, 0)[1] > 20);
+        assert!(with.get_pixel(0, 0)[1] < 5);
+    }
+
+    #[test]
+    fn test_gamut_mask() {
+        // Verde ProPhoto saturo (fuori sRGB), grigio neutro e verde trasparente
+        let mut rgba = image::RgbaImage::new(3, 1);
+        rgba.put_pixel(0, 0, Rgba([0, 255, 0, 255]));
+        rgba.put_pixel(1, 0, Rgba([128, 128, 128, 255]));
+        rgba.put_pixel(2, 0, Rgba([0, 255, 0, 0]));
+        let img = DynamicImage::ImageRgba8(rgba);
+
+        let manager = ColorManager::new().unwrap();
+        let mask = manager
+            .gamut_mask(&img, None, &ColorProfile::ProPhotoRgb)
+            .unwrap();
+        assert_eq!(mask.as_raw(), &vec![255, 0, 0]);
+
+        // Un'immagine sRGB è sempre dentro il gamut sRGB
+        let mask = manager.gamut_mask(&img, None, &ColorProfile::Srgb).unwrap();
+        assert!(mask.pixels().all(|pixel| pixel[0] == 0));
+
+        // Verso ProPhoto il verde sRGB rientra nel gamut
+        let path = std::env::temp_dir().join("iron_test_gamut_prophoto.icc");
+        let prophoto_icc = ColorManager::create_prophoto_rgb_profile()
+            .unwrap()
+            .icc()
+            .unwrap();
+        std::fs::write(&path, prophoto_icc).unwrap();
+        let manager = ColorManager::with_destination(&OutputColorSpace::Custom {
+            path: path.to_string_lossy().to_string(),
+        })
+        .unwrap();
+        let srgb_icc = Profile::new_srgb().icc().unwrap();
+        let mask = manager
+            .gamut_mask(&img, Some(&srgb_icc), &ColorProfile::Srgb)
+            .unwrap();
+        std::fs::remove_file(&path).ok();
+        assert!(mask.pixels().all(|pixel| pixel[0] == 0));
+    }
+
+    #[test]
+    fn test_png_gamma_chunk_profile() {
+        // gAMA 1.0 = dati lineari: il grigio medio lineare diventa più chiaro in sRGB
+        let chunks = PngColorChunks {
+            srgb: false,
+            gamma: Some(1.0),
+            chromaticities: None,
+        };
+        let icc = ColorManager::icc_from_png_chunks(&chunks).unwrap();
+
+        let manager = ColorManager::new().unwrap();
+        let img = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(1, 1, Rgb([50, 50, 50])));
+        let converted = manager
+            .convert_with_embedded_profile(&img, &icc, RenderingIntent::RelativeColorimetric.into())
+            .unwrap()
+            .to_rgb8();
+        assert!(converted.get_pixel(0, 0)[0] > 110);
+    }
+
+    #[test]
+    fn test_srgb_transfer_lut() {
+        let to_linear = srgb_to_linear_lut();
+        let to_srgb = linear_to_srgb_lut();
+
+        // sRGB 50% corrisponde a circa 21.4% in luce lineare
+        assert!((to_linear.apply(0.5) - 0.214).abs() < 0.002);
+
+        for value in [0.0f32, 0.02, 0.25, 0.5, 0.75, 1.0] {
+            assert!((to_srgb.apply(to_linear.apply(value)) - value).abs() < 0.002);
+        }
+    }
+
+    #[test]
+    fn test_linear_roundtrip_keeps_transparent_pixels() {
+        let mut rgba = image::RgbaImage::new(2, 1);
+        rgba.put_pixel(0, 0, Rgba([200, 100, 50, 255]));
+        rgba.put_pixel(1, 0, Rgba([255, 255, 255, 0]));
+
+        let linear = to_linear_premultiplied(&DynamicImage::ImageRgba8(rgba));
+        let back = DynamicImage::ImageRgba32F(from_linear_premultiplied(&linear)).to_rgba8();
+
+        assert_eq!(back.get_pixel(0, 0), &Rgba([200, 100, 50, 255]));
+        assert_eq!(back.get_pixel(1, 0)[3], 0);
+    }
 }
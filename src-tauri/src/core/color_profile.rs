@@ -30,43 +30,222 @@ impl ColorProfile {
     }
 }
 
+/// Origine dell'informazione sul profilo colore
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ProfileSource {
+    /// Profilo ICC embedded (JPEG APP2 o PNG iCCP)
+    Icc,
+    /// Chunk PNG sRGB, gAMA o cHRM
+    PngChunks,
+    /// Nessuna informazione: si assume sRGB
+    Assumed,
+}
+
+/// Coordinate xy del punto di bianco e delle primarie (chunk cHRM)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Chromaticities {
+    pub white: (f64, f64),
+    pub red: (f64, f64),
+    pub green: (f64, f64),
+    pub blue: (f64, f64),
+}
+
+impl Chromaticities {
+    /// Primarie sRGB/Rec. 709 con bianco D65
+    pub const SRGB: Chromaticities = Chromaticities {
+        white: (0.3127, 0.3290),
+        red: (0.64, 0.33),
+        green: (0.30, 0.60),
+        blue: (0.15, 0.06),
+    };
+
+    fn approx_eq(&self, other: &Chromaticities) -> bool {
+        let close = |a: (f64, f64), b: (f64, f64)| {
+            (a.0 - b.0).abs() < CHROMATICITY_TOLERANCE && (a.1 - b.1).abs() < CHROMATICITY_TOLERANCE
+        };
+        close(self.white, other.white)
+            && close(self.red, other.red)
+            && close(self.green, other.green)
+            && close(self.blue, other.blue)
+    }
+}
+
+/// Tolleranza per considerare equivalenti due coordinate cromatiche
+const CHROMATICITY_TOLERANCE: f64 = 0.001;
+/// Gamma di file (gAMA) considerato equivalente a sRGB: 1/2.2
+const SRGB_FILE_GAMMA: f64 = 0.45455;
+const GAMMA_TOLERANCE: f64 = 0.0005;
+
+/// Descrizione colorimetrica di un PNG senza iCCP
+#[derive(Clone, Debug, PartialEq)]
+pub struct PngColorChunks {
+    /// Presenza del chunk sRGB (ha la precedenza su gAMA/cHRM)
+    pub srgb: bool,
+    /// Gamma di file del chunk gAMA (es. 0.45455)
+    pub gamma: Option<f64>,
+    pub chromaticities: Option<Chromaticities>,
+}
+
+impl PngColorChunks {
+    /// True se i chunk descrivono (approssimativamente) sRGB
+    pub fn is_srgb_equivalent(&self) -> bool {
+        if self.srgb {
+            return true;
+        }
+
+        let gamma_matches = self
+            .gamma
+            .is_none_or(|gamma| (gamma - SRGB_FILE_GAMMA).abs() < GAMMA_TOLERANCE);
+        let primaries_match = self
+            .chromaticities
+            .is_none_or(|chrm| chrm.approx_eq(&Chromaticities::SRGB));
+
+        gamma_matches && primaries_match
+    }
+
+    /// Profilo riconosciuto per i chunk (Unknown se non equivalgono a sRGB)
+    pub fn color_profile(&self) -> ColorProfile {
+        if self.is_srgb_equivalent() {
+            return ColorProfile::Srgb;
+        }
+
+        let mut parts = Vec::new();
+        if let Some(gamma) = self.gamma {
+            parts.push(format!("gAMA {:.5}", gamma));
+        }
+        if self.chromaticities.is_some() {
+            parts.push("cHRM".to_string());
+        }
+        ColorProfile::Unknown(format!("PNG {}", parts.join(" + ")))
+    }
+}
+
 /// Estrae il profilo colore da un'immagine
 pub fn detect_color_profile(path: &Path) -> ColorProfile {
+    detect_color_profile_with_source(path).0
+}
+
+/// Estrae il profilo colore e l'origine dell'informazione.
+/// Precedenza: profilo ICC, poi chunk PNG sRGB/gAMA/cHRM, altrimenti sRGB assunto.
+pub fn detect_color_profile_with_source(path: &Path) -> (ColorProfile, ProfileSource) {
     // Leggi i metadati del file per cercare informazioni sul profilo ICC
-    if let Ok(data) = std::fs::read(path) {
-        // Controlla se c'è un profilo ICC embedded
-        if let Some(profile_name) = extract_icc_profile_name(&data) {
-            return match profile_name.to_lowercase().as_str() {
-                name if name.contains("srgb") || name.contains("srgb") => ColorProfile::Srgb,
-                name if name.contains("adobe") && name.contains("rgb") => ColorProfile::AdobeRgb,
-                name if name.contains("display") && name.contains("p3") => ColorProfile::DisplayP3,
-                name if name.contains("prophoto") => ColorProfile::ProPhotoRgb,
-                _ => ColorProfile::Unknown(profile_name),
-            };
-        }
+    match std::fs::read(path) {
+        Ok(data) => detect_color_profile_in(&data),
+        Err(_) => (ColorProfile::Srgb, ProfileSource::Assumed),
+    }
+}
+
+/// Come `detect_color_profile_with_source`, sui byte del file già letti
+pub fn detect_color_profile_in(data: &[u8]) -> (ColorProfile, ProfileSource) {
+    // Controlla se c'è un profilo ICC embedded
+    if let Some(profile_name) = extract_icc_profile_name(data) {
+        return (profile_from_name(&profile_name), ProfileSource::Icc);
+    }
+
+    if let Some(chunks) = extract_png_color_chunks(data) {
+        return (chunks.color_profile(), ProfileSource::PngChunks);
     }
 
     // Se non troviamo un profilo ICC, assumiamo sRGB (standard de facto)
-    ColorProfile::Srgb
+    (ColorProfile::Srgb, ProfileSource::Assumed)
 }
 
-/// Estrae il nome del profilo ICC dai dati dell'immagine
-fn extract_icc_profile_name(data: &[u8]) -> Option<String> {
-    // Per JPEG, cerca il marker APP2 con ICC
-    if data.len() > 2 && data[0] == 0xFF && data[1] == 0xD8 {
+/// Legge i chunk PNG sRGB/gAMA/cHRM (None se il file non è PNG o non li contiene)
+pub fn read_png_color_chunks(path: &Path) -> Option<PngColorChunks> {
+    let data = std::fs::read(path).ok()?;
+    extract_png_color_chunks(&data)
+}
+
+/// Estrae i chunk colore da un PNG in memoria
+pub fn extract_png_color_chunks(data: &[u8]) -> Option<PngColorChunks> {
+    if !is_png(data) {
+        return None;
+    }
+
+    let decoder = png::Decoder::new(std::io::Cursor::new(data));
+    let reader = decoder.read_info().ok()?;
+    let info = reader.info();
+
+    let point = |(x, y): (png::ScaledFloat, png::ScaledFloat)| {
+        (x.into_value() as f64, y.into_value() as f64)
+    };
+
+    let chunks = PngColorChunks {
+        srgb: info.srgb.is_some(),
+        gamma: info.gama_chunk.map(|gamma| gamma.into_value() as f64),
+        chromaticities: info.chrm_chunk.map(|chrm| Chromaticities {
+            white: point(chrm.white),
+            red: point(chrm.red),
+            green: point(chrm.green),
+            blue: point(chrm.blue),
+        }),
+    };
+
+    if !chunks.srgb && chunks.gamma.is_none() && chunks.chromaticities.is_none() {
+        return None;
+    }
+
+    Some(chunks)
+}
+
+/// Riconosce gli spazi colore noti dal nome (descrizione) del profilo ICC
+pub fn profile_from_name(profile_name: &str) -> ColorProfile {
+    match profile_name.to_lowercase().as_str() {
+        name if name.contains("srgb") => ColorProfile::Srgb,
+        name if name.contains("adobe") && name.contains("rgb") => ColorProfile::AdobeRgb,
+        name if name.contains("display") && name.contains("p3") => ColorProfile::DisplayP3,
+        name if name.contains("prophoto") => ColorProfile::ProPhotoRgb,
+        _ => ColorProfile::Unknown(profile_name.to_string()),
+    }
+}
+
+/// Legge il profilo ICC completo embedded in un file JPEG o PNG
+pub fn read_icc_profile(path: &Path) -> Option<Vec<u8>> {
+    let data = std::fs::read(path).ok()?;
+    extract_icc_profile(&data)
+}
+
+/// Estrae i byte del profilo ICC embedded (JPEG APP2 multi-segmento o PNG iCCP)
+pub fn extract_icc_profile(data: &[u8]) -> Option<Vec<u8>> {
+    if is_jpeg(data) {
         return extract_jpeg_icc_profile(data);
     }
 
-    // Per PNG, cerca il chunk iCCP
-    if data.len() > 8 && &data[1..4] == b"PNG" {
+    if is_png(data) {
         return extract_png_icc_profile(data);
     }
 
     None
 }
 
-fn extract_jpeg_icc_profile(data: &[u8]) -> Option<String> {
+fn is_jpeg(data: &[u8]) -> bool {
+    data.len() > 2 && data[0] == 0xFF && data[1] == 0xD8
+}
+
+fn is_png(data: &[u8]) -> bool {
+    data.len() > 8 && &data[1..4] == b"PNG"
+}
+
+/// Estrae il nome del profilo ICC dai dati dell'immagine
+fn extract_icc_profile_name(data: &[u8]) -> Option<String> {
+    // Usa la descrizione contenuta nel profilo
+    if let Some(name) = extract_icc_profile(data).and_then(|icc| parse_icc_description(&icc)) {
+        return Some(name);
+    }
+
+    // Per PNG, ripiega sul nome del chunk iCCP
+    if is_png(data) {
+        return extract_png_icc_name(data);
+    }
+
+    None
+}
+
+/// Ricompone il profilo ICC dai segmenti APP2 "ICC_PROFILE" (ordinati per numero di sequenza)
+fn extract_jpeg_icc_profile(data: &[u8]) -> Option<Vec<u8>> {
     let mut offset = 2; // Salta il marker SOI (0xFFD8)
+    let mut chunks: Vec<(u8, u8, &[u8])> = Vec::new();
 
     while offset + 4 < data.len() {
         if data[offset] != 0xFF {
@@ -75,38 +254,75 @@ fn extract_jpeg_icc_profile(data: &[u8]) -> Option<String> {
 
         let marker = data[offset + 1];
 
+        // SOS: da qui iniziano i dati compressi, niente più metadati
+        if marker == 0xDA {
+            break;
+        }
+
+        // Marker senza lunghezza (SOI, EOI, RST)
+        if marker == 0xD8 || marker == 0xD9 || (0xD0..=0xD7).contains(&marker) {
+            offset += 2;
+            continue;
+        }
+
+        let length = u16::from_be_bytes([data[offset + 2], data[offset + 3]]) as usize;
+        if length < 2 || offset + 2 + length > data.len() {
+            break;
+        }
+
         // APP2 marker (0xFFE2) contiene il profilo ICC
         if marker == 0xE2 {
-            let length = u16::from_be_bytes([data[offset + 2], data[offset + 3]]) as usize;
-
-            if offset + 2 + length <= data.len() {
-                let segment = &data[offset + 4..offset + 2 + length];
+            let segment = &data[offset + 4..offset + 2 + length];
 
-                // Verifica se è un segmento ICC_PROFILE
-                if segment.len() > 14 && &segment[0..11] == b"ICC_PROFILE" {
-                    // Il profilo ICC inizia dopo "ICC_PROFILE\0" + 2 byte di sequenza
-                    if let Some(name) = parse_icc_description(&segment[14..]) {
-                        return Some(name);
-                    }
-                }
+            // "ICC_PROFILE\0" + numero di sequenza + numero totale di segmenti
+            if segment.len() > 14 && &segment[0..12] == b"ICC_PROFILE\0" {
+                chunks.push((segment[12], segment[13], &segment[14..]));
             }
         }
 
-        // Calcola l'offset del prossimo marker
-        if marker == 0xD8 || marker == 0xD9 || (marker >= 0xD0 && marker <= 0xD7) {
-            offset += 2;
-        } else if offset + 4 < data.len() {
-            let length = u16::from_be_bytes([data[offset + 2], data[offset + 3]]) as usize;
-            offset += 2 + length;
-        } else {
-            break;
-        }
+        offset += 2 + length;
     }
 
-    None
+    if chunks.is_empty() {
+        return None;
+    }
+
+    chunks.sort_by_key(|(sequence, _, _)| *sequence);
+
+    // Verifica che tutti i segmenti 1..=N siano presenti
+    let total = chunks[0].1 as usize;
+    let complete = chunks.len() == total
+        && chunks
+            .iter()
+            .enumerate()
+            .all(|(i, (sequence, _, _))| *sequence as usize == i + 1);
+    if !complete {
+        eprintln!(
+            "⚠ Incomplete ICC profile: found {} of {} APP2 segments",
+            chunks.len(),
+            total
+        );
+        return None;
+    }
+
+    Some(
+        chunks
+            .into_iter()
+            .flat_map(|(_, _, payload)| payload.iter().copied())
+            .collect(),
+    )
+}
+
+/// Estrae il profilo ICC (decompresso) dal chunk iCCP di un PNG
+fn extract_png_icc_profile(data: &[u8]) -> Option<Vec<u8>> {
+    let decoder = png::Decoder::new(std::io::Cursor::new(data));
+    let reader = decoder.read_info().ok()?;
+    let profile = reader.info().icc_profile.as_ref()?;
+    Some(profile.to_vec())
 }
 
-fn extract_png_icc_profile(data: &[u8]) -> Option<String> {
+/// Estrae il nome del profilo dal chunk iCCP (keyword prima del byte nullo)
+fn extract_png_icc_name(data: &[u8]) -> Option<String> {
     let mut offset = 8; // Salta la signature PNG
 
     while offset + 8 < data.len() {
@@ -119,7 +335,7 @@ fn extract_png_icc_profile(data: &[u8]) -> Option<String> {
 
         let chunk_type = &data[offset + 4..offset + 8];
 
-        if chunk_type == b"iCCP" {
+        if chunk_type == b"iCCP" && offset + 8 + chunk_length <= data.len() {
             // iCCP chunk: Profile name (null-terminated) + compression method + compressed profile
             let chunk_data = &data[offset + 8..offset + 8 + chunk_length];
 
@@ -183,23 +399,7 @@ fn parse_icc_description(profile_data: &[u8]) -> Option<String> {
             if tag_offset + tag_size <= profile_data.len() {
                 let desc_data = &profile_data[tag_offset..tag_offset + tag_size];
 
-                // Il tipo "desc" ha un formato specifico
-                if desc_data.len() > 12 {
-                    let text_length = u32::from_be_bytes([
-                        desc_data[8],
-                        desc_data[9],
-                        desc_data[10],
-                        desc_data[11],
-                    ]) as usize;
-
-                    if 12 + text_length <= desc_data.len() {
-                        if let Ok(description) =
-                            std::str::from_utf8(&desc_data[12..12 + text_length - 1])
-                        {
-                            return Some(description.to_string());
-                        }
-                    }
-                }
+                return parse_description_tag(desc_data);
             }
         }
 
@@ -209,6 +409,54 @@ fn parse_icc_description(profile_data: &[u8]) -> Option<String> {
     None
 }
 
+/// Decodifica il contenuto del tag "desc": textDescriptionType (ICC v2) o mluc (ICC v4)
+fn parse_description_tag(desc_data: &[u8]) -> Option<String> {
+    if desc_data.len() <= 12 {
+        return None;
+    }
+
+    match &desc_data[0..4] {
+        b"desc" => {
+            // Lunghezza ASCII (con terminatore) seguita dal testo
+            let text_length =
+                u32::from_be_bytes([desc_data[8], desc_data[9], desc_data[10], desc_data[11]])
+                    as usize;
+
+            if text_length == 0 || 12 + text_length > desc_data.len() {
+                return None;
+            }
+
+            std::str::from_utf8(&desc_data[12..12 + text_length - 1])
+                .ok()
+                .map(|description| description.trim_end_matches('\0').to_string())
+        }
+        b"mluc" => {
+            // Usa il primo record: lingua(2) + paese(2) + lunghezza(4) + offset(4)
+            if desc_data.len() < 28 {
+                return None;
+            }
+
+            let length =
+                u32::from_be_bytes([desc_data[20], desc_data[21], desc_data[22], desc_data[23]])
+                    as usize;
+            let string_offset =
+                u32::from_be_bytes([desc_data[24], desc_data[25], desc_data[26], desc_data[27]])
+                    as usize;
+
+            let utf16_bytes = desc_data.get(string_offset..string_offset + length)?;
+            let utf16: Vec<u16> = utf16_bytes
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+
+            String::from_utf16(&utf16)
+                .ok()
+                .map(|description| description.trim_end_matches('\0').to_string())
+        }
+        _ => None,
+    }
+}
+
 /// Converte un'immagine a sRGB in modo perceptualmente accurato
 /// Nota: questa è una conversione semplificata. Per conversioni ICC accurate,
 /// sarebbe necessaria una libreria come lcms2
@@ -258,4 +506,115 @@ mod tests {
         assert!(!ColorProfile::AdobeRgb.is_web_safe());
         assert!(!ColorProfile::DisplayP3.is_web_safe());
     }
+
+    /// Costruisce un JPEG minimale con i segmenti APP2 (sequenza, totale, dati) indicati
+    fn jpeg_with_icc_segments(segments: &[(u8, u8, &[u8])]) -> Vec<u8> {
+        let mut jpeg = vec![0xFF, 0xD8];
+
+        for (sequence, total, part) in segments {
+            let length = (2 + 14 + part.len()) as u16;
+            jpeg.extend_from_slice(&[0xFF, 0xE2]);
+            jpeg.extend_from_slice(&length.to_be_bytes());
+            jpeg.extend_from_slice(b"ICC_PROFILE\0");
+            jpeg.extend_from_slice(&[*sequence, *total]);
+            jpeg.extend_from_slice(part);
+        }
+
+        jpeg.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02, 0xFF, 0xD9]);
+        jpeg
+    }
+
+    #[test]
+    fn test_extract_multi_segment_jpeg_icc() {
+        let profile = lcms2::Profile::new_srgb().icc().unwrap();
+        let (first, second) = profile.split_at(profile.len() / 3);
+
+        // Segmenti fuori ordine: vengono riordinati per numero di sequenza
+        let jpeg = jpeg_with_icc_segments(&[(2, 2, second), (1, 2, first)]);
+        assert_eq!(extract_icc_profile(&jpeg), Some(profile.clone()));
+
+        // Un segmento mancante invalida il profilo
+        let truncated = jpeg_with_icc_segments(&[(1, 3, first), (2, 3, second)]);
+        assert!(extract_icc_profile(&truncated).is_none());
+    }
+
+    /// PNG 1x1 in scala di grigi con i chunk colore impostati da `configure`
+    fn png_with_color_chunks(configure: impl FnOnce(&mut png::Encoder<&mut Vec<u8>>)) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut buffer, 1, 1);
+            encoder.set_color(png::ColorType::Grayscale);
+            configure(&mut encoder);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&[128]).unwrap();
+        }
+        buffer
+    }
+
+    #[test]
+    fn test_png_color_chunks() {
+        // Nessun chunk colore
+        let plain = png_with_color_chunks(|_| {});
+        assert!(extract_png_color_chunks(&plain).is_none());
+
+        // Chunk sRGB
+        let srgb = png_with_color_chunks(|encoder| {
+            encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual)
+        });
+        let chunks = extract_png_color_chunks(&srgb).unwrap();
+        assert!(chunks.srgb);
+        assert_eq!(chunks.color_profile(), ColorProfile::Srgb);
+
+        // gAMA 1/1.8 (vecchio Mac): non equivalente a sRGB
+        let mac_gamma = png_with_color_chunks(|encoder| {
+            encoder.set_source_gamma(png::ScaledFloat::new(1.0 / 1.8))
+        });
+        let chunks = extract_png_color_chunks(&mac_gamma).unwrap();
+        assert!((chunks.gamma.unwrap() - 0.55556).abs() < 0.0001);
+        assert!(!chunks.color_profile().is_web_safe());
+
+        // gAMA 1/2.2 con primarie sRGB: equivalente a sRGB
+        let web_gamma = png_with_color_chunks(|encoder| {
+            encoder.set_source_gamma(png::ScaledFloat::new(0.45455));
+            encoder.set_source_chromaticities(png::SourceChromaticities::new(
+                (0.3127, 0.3290),
+                (0.64, 0.33),
+                (0.30, 0.60),
+                (0.15, 0.06),
+            ));
+        });
+        let chunks = extract_png_color_chunks(&web_gamma).unwrap();
+        assert!(chunks.chromaticities.is_some());
+        assert!(chunks.is_srgb_equivalent());
+    }
+
+    #[test]
+    fn test_extract_png_iccp_profile() {
+        let profile = lcms2::Profile::new_srgb().icc().unwrap();
+
+        let mut info = png::Info::with_size(1, 1);
+        info.color_type = png::ColorType::Grayscale;
+        info.icc_profile = Some(profile.clone().into());
+
+        let mut buffer = Vec::new();
+        {
+            let encoder = png::Encoder::with_info(&mut buffer, info).unwrap();
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&[0]).unwrap();
+        }
+
+        assert_eq!(extract_icc_profile(&buffer), Some(profile));
+        assert_eq!(
+            extract_icc_profile_name(&buffer).map(|name| profile_from_name(&name)),
+            Some(ColorProfile::Srgb)
+        );
+    }
+
+    #[test]
+    fn test_parse_v4_mluc_description() {
+        // Il profilo sRGB di LCMS2 è v4 con descrizione in formato mluc
+        let profile = lcms2::Profile::new_srgb().icc().unwrap();
+        let description = parse_icc_description(&profile).unwrap();
+        assert!(description.to_lowercase().contains("srgb"));
+    }
 }
@@ -1,10 +1,12 @@
 // src-tauri/src/core/exif_handler.rs
 
+use crate::core::metadata_policy::{MetadataPolicy, TagCategory};
 use exif::{In, Reader, Tag, Value};
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Informazioni EXIF estratte da un'immagine
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,21 +90,148 @@ impl Default for ExifData {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExifOptions {
+    /// Compatibilità con le impostazioni salvate: `false` equivale a `MetadataPolicy::StripAll`
     pub preserve_all: bool,
+    /// Quali metadati del sorgente vengono copiati
+    #[serde(default)]
+    pub policy: MetadataPolicy,
     pub strip_gps: bool,
     pub strip_thumbnail: bool,
     pub update_software: bool,
     pub preserve_copyright: bool,
+    /// Rimuove la MakerNote (dati proprietari del produttore)
+    #[serde(default = "default_strip_maker_note")]
+    pub strip_maker_note: bool,
+    /// Precisione delle coordinate conservate (se il GPS non viene rimosso)
+    #[serde(default)]
+    pub gps_precision: GpsPrecision,
+    /// Nei PNG scrive anche Author/Copyright come chunk testuali (tEXt/iTXt)
+    #[serde(default)]
+    pub png_text_chunks: bool,
+    /// Campi scritti in ogni output, anche se il sorgente non li contiene
+    #[serde(default)]
+    pub template: MetadataTemplate,
+}
+
+fn default_strip_maker_note() -> bool {
+    true
+}
+
+/// Precisione della posizione scritta nei file: le coordinate vengono
+/// arrotondate a una griglia e altitudine/direzione rimosse
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum GpsPrecision {
+    /// Coordinate originali
+    #[default]
+    Exact,
+    /// Griglia di 0.01° (circa 1 km)
+    Approximate,
+    /// Griglia di 0.1° (circa 11 km, livello città)
+    City,
+}
+
+impl GpsPrecision {
+    /// Passo della griglia in gradi (None = nessun arrotondamento)
+    pub fn grid_degrees(self) -> Option<f64> {
+        match self {
+            GpsPrecision::Exact => None,
+            GpsPrecision::Approximate => Some(0.01),
+            GpsPrecision::City => Some(0.1),
+        }
+    }
+}
+
+impl ExifOptions {
+    /// Policy da applicare, tenendo conto di `preserve_all`
+    pub fn effective_policy(&self) -> MetadataPolicy {
+        if self.preserve_all {
+            self.policy.clone()
+        } else {
+            MetadataPolicy::StripAll
+        }
+    }
+}
+
+/// Template di metadati da impostare (o sovrascrivere) su tutto il batch.
+/// I campi vuoti lasciano invariato il valore del sorgente.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MetadataTemplate {
+    pub artist: Option<String>,
+    /// `{year}` viene sostituito con l'anno corrente
+    pub copyright: Option<String>,
+    pub description: Option<String>,
+    pub keywords: Vec<String>,
+    /// URL dei diritti d'uso (xmpRights:WebStatement)
+    pub rights_url: Option<String>,
+}
+
+impl MetadataTemplate {
+    /// Vero se nessun campo ha un valore (le stringhe vuote non contano)
+    pub fn is_empty(&self) -> bool {
+        self.resolved(0) == MetadataTemplate::default()
+    }
+
+    /// Template pronto da scrivere: stringhe vuote ignorate e `{year}` risolto
+    pub fn resolved(&self, year: i32) -> MetadataTemplate {
+        let clean = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(|v| v.replace("{year}", &year.to_string()))
+        };
+        MetadataTemplate {
+            artist: clean(&self.artist),
+            copyright: clean(&self.copyright),
+            description: clean(&self.description),
+            keywords: self
+                .keywords
+                .iter()
+                .map(|k| k.trim().to_string())
+                .filter(|k| !k.is_empty())
+                .collect(),
+            rights_url: clean(&self.rights_url),
+        }
+    }
+}
+
+/// Anno corrente (UTC), usato per il segnaposto `{year}` dei template
+pub fn current_year() -> i32 {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or(0) as i64;
+    year_from_days(days)
+}
+
+/// Anno del calendario gregoriano dai giorni trascorsi dal 1970-01-01
+fn year_from_days(days: i64) -> i32 {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let month_index = (5 * doy + 2) / 153;
+    // L'anno "marzo-febbraio" include gennaio e febbraio dell'anno successivo
+    let year = yoe + era * 400 + i64::from(month_index >= 10);
+    year as i32
 }
 
 impl Default for ExifOptions {
     fn default() -> Self {
         Self {
             preserve_all: true,
+            policy: MetadataPolicy::KeepAll,
             strip_gps: false,
             strip_thumbnail: true,
             update_software: true,
             preserve_copyright: true,
+            strip_maker_note: true,
+            gps_precision: GpsPrecision::Exact,
+            png_text_chunks: false,
+            template: MetadataTemplate::default(),
         }
     }
 }
@@ -251,7 +380,13 @@ impl ExifHandler {
         if let Some(field) = exif.get_field(Tag::GPSAltitude, In::PRIMARY) {
             if let Value::Rational(ref vec) = field.value {
                 if let Some(rational) = vec.first() {
-                    data.gps_altitude = Some(rational.num as f64 / rational.denom as f64);
+                    let altitude = rational.num as f64 / rational.denom as f64;
+                    // GPSAltitudeRef = 1: sotto il livello del mare
+                    let below_sea_level = exif
+                        .get_field(Tag::GPSAltitudeRef, In::PRIMARY)
+                        .and_then(|field| field.value.get_uint(0))
+                        == Some(1);
+                    data.gps_altitude = Some(if below_sea_level { -altitude } else { altitude });
                 }
             }
         }
@@ -313,6 +448,16 @@ impl ExifHandler {
         }
     }
 
+    /// Legge solo il tag Orientation (1-8) senza estrarre tutti i metadati
+    pub fn read_orientation(path: &Path) -> Option<u16> {
+        let file = File::open(path).ok()?;
+        let mut bufreader = BufReader::new(&file);
+        let exif = Reader::new().read_from_container(&mut bufreader).ok()?;
+
+        let field = exif.get_field(Tag::Orientation, In::PRIMARY)?;
+        field.value.get_uint(0).map(|value| value as u16)
+    }
+
     /// Estrae coordinate GPS (latitudine o longitudine)
     fn extract_gps_coordinate(exif: &exif::Exif, coord_tag: Tag, ref_tag: Tag) -> Option<f64> {
         let coord_field = exif.get_field(coord_tag, In::PRIMARY)?;
@@ -389,9 +534,30 @@ impl ExifHandler {
         summary
     }
 
-    /// Verifica se l'immagine ha dati GPS sensibili
-    pub fn has_sensitive_location(data: &ExifData) -> bool {
-        data.gps_latitude.is_some() || data.gps_longitude.is_some()
+    /// Verifica se i dati GPS scritti con queste opzioni sono sensibili:
+    /// le coordinate arrotondate a una griglia non rivelano la posizione esatta
+    pub fn has_sensitive_location(data: &ExifData, options: &ExifOptions) -> bool {
+        let has_location = data.gps_latitude.is_some() || data.gps_longitude.is_some();
+        let keeps_location = options
+            .effective_policy()
+            .keeps("GPSLatitude", Some(TagCategory::Location));
+        has_location
+            && keeps_location
+            && !options.strip_gps
+            && options.gps_precision == GpsPrecision::Exact
+    }
+
+    /// Arrotonda le coordinate alla griglia della precisione scelta ed
+    /// elimina l'altitudine; con `Exact` i dati restano invariati
+    pub fn fuzz_location(data: &ExifData, precision: GpsPrecision) -> ExifData {
+        let mut fuzzed = data.clone();
+        if let Some(grid) = precision.grid_degrees() {
+            let snap = |value: f64| (value / grid).round() * grid;
+            fuzzed.gps_latitude = data.gps_latitude.map(snap);
+            fuzzed.gps_longitude = data.gps_longitude.map(snap);
+            fuzzed.gps_altitude = None;
+        }
+        fuzzed
     }
 }
 
@@ -413,6 +579,72 @@ mod tests {
         assert!(!options.strip_gps);
     }
 
+    #[test]
+    fn test_location_fuzzing() {
+        let data = ExifData {
+            gps_latitude: Some(45.464_211),
+            gps_longitude: Some(-9.190_336),
+            gps_altitude: Some(122.0),
+            ..ExifData::default()
+        };
+
+        let fuzzed = ExifHandler::fuzz_location(&data, GpsPrecision::City);
+        assert!((fuzzed.gps_latitude.unwrap() - 45.5).abs() < 1e-9);
+        assert!((fuzzed.gps_longitude.unwrap() + 9.2).abs() < 1e-9);
+        assert!(fuzzed.gps_altitude.is_none());
+
+        let exact = ExifHandler::fuzz_location(&data, GpsPrecision::Exact);
+        assert_eq!(exact.gps_altitude, Some(122.0));
+
+        let mut options = ExifOptions::default();
+        assert!(ExifHandler::has_sensitive_location(&data, &options));
+        options.gps_precision = GpsPrecision::Approximate;
+        assert!(!ExifHandler::has_sensitive_location(&data, &options));
+        options.gps_precision = GpsPrecision::Exact;
+        options.strip_gps = true;
+        assert!(!ExifHandler::has_sensitive_location(&data, &options));
+        options.strip_gps = false;
+        options.policy = MetadataPolicy::DenyList {
+            categories: vec![TagCategory::Location],
+            tags: vec![],
+        };
+        assert!(!ExifHandler::has_sensitive_location(&data, &options));
+    }
+
+    #[test]
+    fn test_template_resolution() {
+        let template = MetadataTemplate {
+            artist: Some("  Jane Doe ".to_string()),
+            copyright: Some("© {year} Agency".to_string()),
+            description: Some("".to_string()),
+            keywords: vec!["news".to_string(), " ".to_string()],
+            rights_url: None,
+        };
+
+        let resolved = template.resolved(2024);
+        assert_eq!(resolved.artist.as_deref(), Some("Jane Doe"));
+        assert_eq!(resolved.copyright.as_deref(), Some("© 2024 Agency"));
+        assert!(resolved.description.is_none());
+        assert_eq!(resolved.keywords, vec!["news"]);
+        assert!(MetadataTemplate::default().resolved(2024).is_empty());
+
+        // Template assente nelle impostazioni salvate prima dell'opzione
+        let options: ExifOptions = serde_json::from_str(
+            r#"{"preserveAll":true,"stripGps":true,"stripThumbnail":true,"updateSoftware":true,"preserveCopyright":true}"#,
+        )
+        .unwrap();
+        assert!(options.template.is_empty());
+    }
+
+    #[test]
+    fn test_year_from_days() {
+        assert_eq!(year_from_days(0), 1970);
+        assert_eq!(year_from_days(19_722), 2023); // 2023-12-31
+        assert_eq!(year_from_days(19_723), 2024); // 2024-01-01
+        assert_eq!(year_from_days(19_783), 2024); // 2024-03-01
+        assert_eq!(year_from_days(-1), 1969);
+    }
+
     #[test]
     fn test_summary_generation() {
         let mut data = ExifData::default();
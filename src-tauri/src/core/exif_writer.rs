@@ -1,11 +1,33 @@
 // src-tauri/src/core/exif_writer.rs
 
-use crate::core::exif_handler::{ExifData, ExifOptions};
-use exif::{Field, In, Tag, Value};
-use std::fs;
-use std::io::Cursor;
+use crate::core::exif_handler::{
+    current_year, ExifData, ExifHandler, ExifOptions, GpsPrecision, MetadataTemplate,
+};
+use crate::core::icc_writer::{IccWriter, WebpMetadata};
+use crate::core::iptc;
+use crate::core::metadata_policy::{MetadataPolicy, TagCategory};
+use crate::core::tiff::{
+    ByteOrder, ExifTree, Ifd, IfdEntry, TiffValue, TAG_ARTIST, TAG_COPYRIGHT, TAG_GPS_ALTITUDE,
+    TAG_GPS_ALTITUDE_REF, TAG_GPS_LATITUDE, TAG_GPS_LATITUDE_REF, TAG_GPS_LONGITUDE,
+    TAG_GPS_LONGITUDE_REF, TAG_GPS_VERSION_ID, TAG_IMAGE_DESCRIPTION, TAG_ORIENTATION,
+    TAG_PIXEL_X_DIMENSION, TAG_PIXEL_Y_DIMENSION, TAG_SOFTWARE,
+};
+use crate::core::xmp;
+use std::fs::{self, File};
+use std::io::BufReader;
 use std::path::Path;
 
+const SOFTWARE_NAME: &str = "Iron Optimizer v1.0";
+/// Spazio massimo per il TIFF in un segmento APP1 (65535 - lunghezza - "Exif\0\0")
+const MAX_APP1_PAYLOAD: usize = 65535 - 2 - 6;
+/// Dimensione massima di un chunk RIFF (WebP)
+const MAX_WEBP_CHUNK: usize = u32::MAX as usize - 8;
+/// Dimensione massima di un chunk PNG (lunghezza a 31 bit)
+const MAX_PNG_CHUNK: usize = i32::MAX as usize;
+/// Precisione dei secondi d'arco e dell'altitudine nei RATIONAL GPS
+const GPS_SECONDS_DENOMINATOR: u32 = 10_000;
+const GPS_ALTITUDE_DENOMINATOR: u32 = 1_000;
+
 /// Modulo per scrivere/preservare EXIF nei file ottimizzati
 pub struct ExifWriter;
 
@@ -26,8 +48,48 @@ impl ExifWriter {
             return Err(format!("Destination file does not exist: {:?}", dest_path));
         }
 
-        // Estrai EXIF dal file sorgente
-        let source_exif_data = crate::core::exif_handler::ExifHandler::extract_exif(source_path)?;
+        // Template risolto una sola volta per il file ({year}) e policy effettiva
+        let options = &ExifOptions {
+            template: options.template.resolved(current_year()),
+            policy: options.effective_policy(),
+            ..options.clone()
+        };
+        let template = &options.template;
+        let keeps_source = options.policy != MetadataPolicy::StripAll;
+
+        // Estrai EXIF, XMP e IPTC dal file sorgente (basta uno dei tre);
+        // con la policy StripAll viene scritto solo il template
+        let source_exif_data = if keeps_source {
+            ExifHandler::extract_exif(source_path)
+        } else {
+            Err("Metadata preservation disabled".to_string())
+        };
+        let mut xmp_data = xmp::extract_xmp(source_path)
+            .filter(|_| keeps_source)
+            .map(|data| data.filtered(options))
+            .unwrap_or_default();
+        xmp_data.apply_template(template);
+        let xmp_packet = (!xmp_data.is_empty()).then(|| xmp_data.to_packet());
+        // L'IPTC del sorgente si copia solo con il copyright preservato, filtrato
+        // dataset per dataset dalla policy; i campi del template vengono scritti comunque
+        let source_jpeg = if options.preserve_copyright && keeps_source {
+            fs::read(source_path).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let iptc_segment = iptc::iptc_app13_segment(&source_jpeg, options);
+        let template_has_exif = template.artist.is_some()
+            || template.copyright.is_some()
+            || template.description.is_some();
+        let source_exif_data = match source_exif_data {
+            Ok(data) => Some(data),
+            // Sorgente senza EXIF: il template finisce in un EXIF nuovo
+            Err(_) if template_has_exif => Some(ExifData::default()),
+            Err(_) if xmp_packet.is_some() || iptc_segment.is_some() => None,
+            Err(e) => return Err(e),
+        };
+        let source_data = source_exif_data.as_ref();
+        let xmp = xmp_packet.as_deref();
 
         // Determina il formato del file destinazione
         let extension = dest_path
@@ -37,18 +99,20 @@ impl ExifWriter {
 
         match extension.to_lowercase().as_str() {
             "jpg" | "jpeg" => {
-                Self::write_jpeg_exif(dest_path, &source_exif_data, options)?;
+                Self::write_jpeg_exif(
+                    source_path,
+                    dest_path,
+                    source_data,
+                    xmp,
+                    iptc_segment.as_deref(),
+                    options,
+                )?;
             }
             "png" => {
-                // PNG non supporta EXIF nativamente in modo standard
-                // Potremmo usare eXIf chunk (PNG 1.5+) ma è poco supportato
-                println!("ℹ️  PNG format: EXIF preservation skipped (limited support)");
-                return Ok(());
+                Self::write_png_exif(source_path, dest_path, source_data, xmp, options)?;
             }
             "webp" => {
-                // WebP supporta EXIF ma la libreria webp non espone API di scrittura
-                println!("ℹ️  WebP format: EXIF preservation skipped (library limitation)");
-                return Ok(());
+                Self::write_webp_exif(source_path, dest_path, source_data, xmp, options)?;
             }
             _ => {
                 return Err(format!(
@@ -61,10 +125,13 @@ impl ExifWriter {
         Ok(())
     }
 
-    /// Scrive EXIF in un file JPEG usando implementazione nativa
+    /// Scrive EXIF e XMP in un file JPEG come segmenti APP1 e IPTC come APP13
     fn write_jpeg_exif(
+        source_path: &Path,
         dest_path: &Path,
-        source_data: &ExifData,
+        source_data: Option<&ExifData>,
+        xmp: Option<&str>,
+        iptc: Option<&[u8]>,
         options: &ExifOptions,
     ) -> Result<(), String> {
         // Leggi il file JPEG esistente
@@ -75,11 +142,23 @@ impl ExifWriter {
             return Err("Invalid JPEG file".to_string());
         }
 
-        // Crea nuovi dati EXIF filtrati
-        let exif_segment = Self::build_exif_segment(source_data, options)?;
+        let mut segments = Vec::new();
+        if let Some(data) = source_data {
+            let tiff = Self::exif_tiff(source_path, dest_path, data, options, MAX_APP1_PAYLOAD);
+            segments.extend(Self::app1_segment(&tiff)?);
+        }
+        if let Some(xmp) = xmp {
+            match xmp::jpeg_xmp_segment(xmp.as_bytes()) {
+                Ok(segment) => segments.extend(segment),
+                Err(e) => eprintln!("⚠ XMP skipped for {:?}: {}", dest_path, e),
+            }
+        }
+        if let Some(iptc) = iptc {
+            segments.extend_from_slice(iptc);
+        }
 
-        // Inserisci l'EXIF segment nel JPEG
-        let new_jpeg = Self::inject_exif_into_jpeg(&jpeg_data, &exif_segment)?;
+        // Inserisci i segmenti nel JPEG
+        let new_jpeg = Self::inject_exif_into_jpeg(&jpeg_data, &segments)?;
 
         // Scrivi il nuovo file
         fs::write(dest_path, new_jpeg).map_err(|e| format!("Failed to write file: {}", e))?;
@@ -88,156 +167,361 @@ impl ExifWriter {
         Ok(())
     }
 
-    /// Costruisce un segmento APP1 EXIF completo
-    fn build_exif_segment(data: &ExifData, options: &ExifOptions) -> Result<Vec<u8>, String> {
-        let mut segment = Vec::new();
+    /// Scrive EXIF e XMP in un WebP come chunk "EXIF" e "XMP " del formato esteso VP8X
+    /// (il profilo ICC già embedded viene mantenuto)
+    fn write_webp_exif(
+        source_path: &Path,
+        dest_path: &Path,
+        source_data: Option<&ExifData>,
+        xmp: Option<&str>,
+        options: &ExifOptions,
+    ) -> Result<(), String> {
+        let webp_data = fs::read(dest_path).map_err(|e| format!("Failed to read file: {}", e))?;
 
-        // APP1 marker (0xFFE1)
-        segment.extend_from_slice(&[0xFF, 0xE1]);
+        let tiff = source_data
+            .map(|data| Self::exif_tiff(source_path, dest_path, data, options, MAX_WEBP_CHUNK));
+        let new_webp = IccWriter::embed_metadata_in_webp(
+            &webp_data,
+            &WebpMetadata {
+                exif: tiff.as_deref(),
+                xmp: xmp.map(str::as_bytes),
+                ..WebpMetadata::default()
+            },
+        )?;
 
-        // Placeholder per la lunghezza (da riempire dopo)
-        segment.extend_from_slice(&[0x00, 0x00]);
+        fs::write(dest_path, new_webp).map_err(|e| format!("Failed to write file: {}", e))?;
 
-        // EXIF identifier + padding
-        segment.extend_from_slice(b"Exif\0\0");
+        println!("✅ EXIF data preserved for: {:?}", dest_path);
+        Ok(())
+    }
 
-        // TIFF header (little-endian)
-        segment.extend_from_slice(&[0x49, 0x49]); // "II" = little-endian
-        segment.extend_from_slice(&[0x2A, 0x00]); // TIFF magic number
-        segment.extend_from_slice(&[0x08, 0x00, 0x00, 0x00]); // Offset to IFD0
+    /// Scrive EXIF in un PNG come chunk `eXIf` prima di IDAT, con Author/Copyright
+    /// anche testuali per i programmi che non leggono `eXIf`, e l'XMP in `iTXt`
+    fn write_png_exif(
+        source_path: &Path,
+        dest_path: &Path,
+        source_data: Option<&ExifData>,
+        xmp: Option<&str>,
+        options: &ExifOptions,
+    ) -> Result<(), String> {
+        let png_data = fs::read(dest_path).map_err(|e| format!("Failed to read file: {}", e))?;
+
+        let tiff = source_data
+            .map(|data| Self::exif_tiff(source_path, dest_path, data, options, MAX_PNG_CHUNK));
+
+        let mut text = Vec::new();
+        if options.png_text_chunks {
+            // Il template ha la precedenza sui valori del sorgente
+            let source = source_data.filter(|_| options.preserve_copyright);
+            let keeps = |name: &str| options.policy.keeps(name, Some(TagCategory::Owner));
+            let template = &options.template;
+            let artist = template.artist.as_ref().or(source
+                .filter(|_| keeps("Artist"))
+                .and_then(|data| data.artist.as_ref()));
+            let copyright = template.copyright.as_ref().or(source
+                .filter(|_| keeps("Copyright"))
+                .and_then(|data| data.copyright.as_ref()));
+            if let Some(artist) = artist {
+                text.push(("Author", artist.as_str()));
+            }
+            if let Some(copyright) = copyright {
+                text.push(("Copyright", copyright.as_str()));
+            }
+        }
 
-        // Costruisci IFD0 con i tag filtrati
-        let ifd0_entries = Self::build_ifd0_entries(data, options);
+        let new_png = IccWriter::embed_metadata_in_png(
+            &png_data,
+            tiff.as_deref(),
+            &text,
+            xmp.map(str::as_bytes),
+        )?;
+        fs::write(dest_path, new_png).map_err(|e| format!("Failed to write file: {}", e))?;
 
-        // Numero di entry nell'IFD
-        segment.extend_from_slice(&(ifd0_entries.len() as u16).to_le_bytes());
+        println!("✅ EXIF data preserved for: {:?}", dest_path);
+        Ok(())
+    }
 
-        // Aggiungi le entry
-        for entry in ifd0_entries {
-            segment.extend_from_slice(&entry);
+    /// Blocco TIFF da scrivere: la struttura EXIF originale copiata, oppure
+    /// come fallback quella ricostruita da ExifData.
+    /// Con StripAll non si copia nulla del sorgente (thumbnail, Interop IFD):
+    /// l'EXIF è costruito dal solo template
+    fn exif_tiff(
+        source_path: &Path,
+        dest_path: &Path,
+        source_data: &ExifData,
+        options: &ExifOptions,
+        max_len: usize,
+    ) -> Vec<u8> {
+        if options.policy == MetadataPolicy::StripAll {
+            return Self::build_exif_tree(source_data, options).to_bytes();
         }
+        match Self::passthrough_tiff(source_path, dest_path, source_data, options, max_len) {
+            Ok(tiff) => tiff,
+            Err(e) => {
+                eprintln!("⚠ Raw EXIF passthrough failed: {}, rebuilding EXIF", e);
+                Self::build_exif_tree(source_data, options).to_bytes()
+            }
+        }
+    }
 
-        // Next IFD offset (0 = nessun altro IFD)
-        segment.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+    /// Legge il blocco TIFF grezzo dell'EXIF sorgente (JPEG, PNG, WebP, TIFF)
+    fn read_raw_exif(path: &Path) -> Result<Vec<u8>, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+        let mut bufreader = BufReader::new(&file);
+        let exif = exif::Reader::new()
+            .read_from_container(&mut bufreader)
+            .map_err(|e| format!("Failed to read EXIF data: {}", e))?;
+        Ok(exif.buf().to_vec())
+    }
 
-        // Calcola e scrivi la lunghezza del segmento
-        let segment_length = (segment.len() - 2) as u16; // Escludi il marker
-        segment[2..4].copy_from_slice(&segment_length.to_be_bytes());
+    /// Struttura TIFF originale copiata byte per byte, con gli offset ricalcolati
+    /// e i tag rimossi o aggiornati secondo le opzioni
+    fn passthrough_tiff(
+        source_path: &Path,
+        dest_path: &Path,
+        source_data: &ExifData,
+        options: &ExifOptions,
+        max_len: usize,
+    ) -> Result<Vec<u8>, String> {
+        let raw = Self::read_raw_exif(source_path)?;
+        let mut tree = ExifTree::parse(&raw)?;
+
+        let dimensions = image::image_dimensions(dest_path).ok();
+        Self::apply_options(&mut tree, source_data, options, dimensions);
+
+        let mut tiff = tree.to_bytes();
+        if tiff.len() > max_len && tree.thumbnail.is_some() {
+            // La thumbnail è la prima cosa da sacrificare per stare in un solo APP1
+            tree.remove_thumbnail();
+            tiff = tree.to_bytes();
+        }
+        if tiff.len() > max_len {
+            return Err("EXIF data too large for the output container".to_string());
+        }
 
-        Ok(segment)
+        Ok(tiff)
     }
 
-    /// Costruisce le entry IFD0 con i dati filtrati
-    fn build_ifd0_entries(data: &ExifData, options: &ExifOptions) -> Vec<Vec<u8>> {
-        let mut entries = Vec::new();
-
-        // Software tag (se richiesto)
-        if options.update_software {
-            entries.push(Self::create_ascii_entry(
-                0x0131, // Software tag
-                "Iron Optimizer v1.0",
-            ));
+    /// Applica le opzioni di privacy e aggiorna i tag che l'ottimizzazione rende obsoleti
+    fn apply_options(
+        tree: &mut ExifTree,
+        source_data: &ExifData,
+        options: &ExifOptions,
+        dimensions: Option<(u32, u32)>,
+    ) {
+        if options.strip_gps {
+            tree.remove_gps();
+        } else if options.gps_precision != GpsPrecision::Exact && tree.gps.is_some() {
+            // GPS IFD ricostruita con le sole coordinate arrotondate:
+            // altitudine, direzione e timestamp non vengono copiati
+            let fuzzed = ExifHandler::fuzz_location(source_data, options.gps_precision);
+            tree.gps = Self::build_gps_ifd(&fuzzed, tree.byte_order);
         }
+        // Dopo la ricostruzione della GPS IFD, che altrimenti
+        // reintrodurrebbe i tag esclusi dalla policy
+        options.policy.apply(tree);
 
-        // Artist (se presente e richiesto)
-        if options.preserve_copyright {
-            if let Some(ref artist) = data.artist {
-                entries.push(Self::create_ascii_entry(0x013B, artist)); // Artist tag
-            }
-            if let Some(ref copyright) = data.copyright {
-                entries.push(Self::create_ascii_entry(0x8298, copyright)); // Copyright tag
-            }
+        if options.strip_thumbnail {
+            tree.remove_thumbnail();
         }
-
-        // Camera make/model
-        if let Some(ref make) = data.camera_make {
-            entries.push(Self::create_ascii_entry(0x010F, make)); // Make tag
+        if options.strip_maker_note {
+            tree.remove_maker_note();
         }
-        if let Some(ref model) = data.camera_model {
-            entries.push(Self::create_ascii_entry(0x0110, model)); // Model tag
+
+        if !options.preserve_copyright {
+            tree.ifd0.remove(TAG_ARTIST);
+            tree.ifd0.remove(TAG_COPYRIGHT);
         }
 
-        // DateTime
-        if let Some(ref date) = data.date_taken {
-            entries.push(Self::create_ascii_entry(0x0132, date)); // DateTime tag
+        if options.update_software {
+            let software = tree.entry(TAG_SOFTWARE, TiffValue::Ascii(SOFTWARE_NAME.to_string()));
+            tree.ifd0.set(software);
         }
 
-        // Orientation
-        if let Some(orientation) = data.orientation {
-            entries.push(Self::create_short_entry(0x0112, orientation)); // Orientation tag
+        // Orientation: i pixel sono già stati ruotati, quindi il tag torna a 1
+        if tree.ifd0.get(TAG_ORIENTATION).is_some() {
+            let orientation = tree.entry(TAG_ORIENTATION, TiffValue::Short(vec![1]));
+            tree.ifd0.set(orientation);
         }
 
-        // ISO
-        if let Some(iso) = data.iso {
-            entries.push(Self::create_short_entry(0x8827, iso as u16)); // ISO tag
+        // Dimensioni dell'immagine ottimizzata (dopo resize e trim)
+        if let Some((width, height)) = dimensions {
+            let pixel_x = tree.entry(TAG_PIXEL_X_DIMENSION, TiffValue::Long(vec![width]));
+            let pixel_y = tree.entry(TAG_PIXEL_Y_DIMENSION, TiffValue::Long(vec![height]));
+            if let Some(exif) = &mut tree.exif {
+                if exif.get(TAG_PIXEL_X_DIMENSION).is_some() {
+                    exif.set(pixel_x);
+                }
+                if exif.get(TAG_PIXEL_Y_DIMENSION).is_some() {
+                    exif.set(pixel_y);
+                }
+            }
         }
 
-        // GPS data (solo se NON strip_gps)
-        if !options.strip_gps {
-            // Per GPS servirebbero SubIFD dedicati, implementazione complessa
-            // Per ora skippiamo (GPS richiede strutture EXIF avanzate)
+        Self::apply_template(tree, &options.template);
+    }
+
+    /// Scrive in IFD0 i campi del template, sovrascrivendo quelli del sorgente
+    fn apply_template(tree: &mut ExifTree, template: &MetadataTemplate) {
+        let fields = [
+            (TAG_ARTIST, &template.artist),
+            (TAG_COPYRIGHT, &template.copyright),
+            (TAG_IMAGE_DESCRIPTION, &template.description),
+        ];
+        for (tag, value) in fields {
+            if let Some(value) = value {
+                let entry = tree.entry(tag, TiffValue::Ascii(value.clone()));
+                tree.ifd0.set(entry);
+            }
         }
+    }
 
-        // Ordina per tag ID (requirement EXIF)
-        entries.sort_by_key(|entry| u16::from_le_bytes([entry[0], entry[1]]));
+    /// Segmento APP1 con il blocco TIFF indicato (deve stare in un solo segmento)
+    fn app1_segment(tiff: &[u8]) -> Result<Vec<u8>, String> {
+        if tiff.len() > MAX_APP1_PAYLOAD {
+            return Err(format!(
+                "EXIF data too large for an APP1 segment ({} bytes)",
+                tiff.len()
+            ));
+        }
 
-        entries
+        let mut segment = Vec::with_capacity(tiff.len() + 10);
+        segment.extend_from_slice(&[0xFF, 0xE1]);
+        segment.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        segment.extend_from_slice(b"Exif\0\0");
+        segment.extend_from_slice(tiff);
+        Ok(segment)
     }
 
-    /// Crea una entry IFD per valori ASCII
-    fn create_ascii_entry(tag: u16, value: &str) -> Vec<u8> {
-        let mut entry = Vec::new();
+    /// Ricostruisce la struttura EXIF dai dati estratti (IFD0 + Exif SubIFD),
+    /// usata quando il TIFF originale non è leggibile
+    fn build_exif_tree(data: &ExifData, options: &ExifOptions) -> ExifTree {
+        let mut tree = ExifTree::new(ByteOrder::LittleEndian);
+        let mut exif = Ifd::default();
 
-        // Tag ID (2 bytes)
-        entry.extend_from_slice(&tag.to_le_bytes());
+        let order = tree.byte_order;
+        let set_ascii = |ifd: &mut Ifd, tag: u16, value: &Option<String>| {
+            if let Some(value) = value {
+                ifd.set(IfdEntry::new(tag, &TiffValue::Ascii(value.clone()), order));
+            }
+        };
+
+        // IFD0: camera, data e autore
+        set_ascii(&mut tree.ifd0, 0x010F, &data.camera_make); // Make
+        set_ascii(&mut tree.ifd0, 0x0110, &data.camera_model); // Model
+        set_ascii(&mut tree.ifd0, TAG_IMAGE_DESCRIPTION, &data.description);
+        // Ogni data torna nel tag da cui `ExifHandler::extract_exif` l'ha letta
+        set_ascii(&mut tree.ifd0, 0x0132, &data.date_taken); // DateTime
+        if options.preserve_copyright {
+            set_ascii(&mut tree.ifd0, TAG_ARTIST, &data.artist);
+            set_ascii(&mut tree.ifd0, TAG_COPYRIGHT, &data.copyright);
+        }
 
-        // Type (ASCII = 2)
-        entry.extend_from_slice(&2u16.to_le_bytes());
+        // Exif SubIFD: date di scatto e obiettivo
+        set_ascii(&mut exif, 0x9003, &data.date_digitized); // DateTimeOriginal
+        set_ascii(&mut exif, 0x9004, &data.date_modified); // DateTimeDigitized
+        set_ascii(&mut exif, 0xA434, &data.lens_model); // LensModel
 
-        // Count (lunghezza stringa + null terminator)
-        let count = (value.len() + 1) as u32;
-        entry.extend_from_slice(&count.to_le_bytes());
+        if let Some(iso) = data.iso {
+            // ISOSpeedRatings è SHORT: valori oltre 65535 vengono saturati
+            let iso = iso.min(u32::from(u16::MAX)) as u16;
+            exif.set(tree.entry(0x8827, TiffValue::Short(vec![iso])));
+        }
 
-        // Value/Offset
-        if count <= 4 {
-            // Valore inline (padded a 4 bytes)
-            let mut val_bytes = value.as_bytes().to_vec();
-            val_bytes.push(0); // null terminator
-            val_bytes.resize(4, 0); // padding
-            entry.extend_from_slice(&val_bytes);
-        } else {
-            // Offset (per semplicità, usiamo valore inline truncato)
-            // In produzione andrebbe gestito con offset table
-            let mut val_bytes = value.as_bytes().to_vec();
-            val_bytes.truncate(3);
-            val_bytes.push(0);
-            entry.extend_from_slice(&val_bytes);
+        // Orientation: i pixel sono già stati ruotati, quindi il tag torna a 1
+        if data.orientation.is_some() {
+            let orientation = tree.entry(TAG_ORIENTATION, TiffValue::Short(vec![1]));
+            tree.ifd0.set(orientation);
         }
 
-        entry
-    }
+        // GPS data (solo se NON strip_gps)
+        if !options.strip_gps {
+            let fuzzed = ExifHandler::fuzz_location(data, options.gps_precision);
+            tree.gps = Self::build_gps_ifd(&fuzzed, order);
+        }
 
-    /// Crea una entry IFD per valori SHORT (u16)
-    fn create_short_entry(tag: u16, value: u16) -> Vec<u8> {
-        let mut entry = Vec::new();
+        if !exif.entries.is_empty() {
+            tree.exif = Some(exif);
+        }
 
-        // Tag ID
-        entry.extend_from_slice(&tag.to_le_bytes());
+        options.policy.apply(&mut tree);
 
-        // Type (SHORT = 3)
-        entry.extend_from_slice(&3u16.to_le_bytes());
+        if options.update_software {
+            let software = tree.entry(TAG_SOFTWARE, TiffValue::Ascii(SOFTWARE_NAME.to_string()));
+            tree.ifd0.set(software);
+        }
 
-        // Count (1)
-        entry.extend_from_slice(&1u32.to_le_bytes());
+        Self::apply_template(&mut tree, &options.template);
+        tree
+    }
 
-        // Value (inline, 2 bytes + 2 bytes padding)
-        entry.extend_from_slice(&value.to_le_bytes());
-        entry.extend_from_slice(&[0x00, 0x00]); // padding
+    /// GPS IFD dalle coordinate decimali estratte (None se mancano o non sono valide)
+    fn build_gps_ifd(data: &ExifData, order: ByteOrder) -> Option<Ifd> {
+        let latitude = data
+            .gps_latitude
+            .filter(|v| v.is_finite() && v.abs() <= 90.0)?;
+        let longitude = data
+            .gps_longitude
+            .filter(|v| v.is_finite() && v.abs() <= 180.0)?;
+
+        let mut gps = Ifd::default();
+        let mut set = |tag: u16, value: TiffValue| gps.set(IfdEntry::new(tag, &value, order));
+
+        set(TAG_GPS_VERSION_ID, TiffValue::Byte(vec![2, 3, 0, 0]));
+        let latitude_ref = if latitude < 0.0 { "S" } else { "N" };
+        set(
+            TAG_GPS_LATITUDE_REF,
+            TiffValue::Ascii(latitude_ref.to_string()),
+        );
+        set(
+            TAG_GPS_LATITUDE,
+            TiffValue::Rational(Self::dms_rationals(latitude)),
+        );
+        let longitude_ref = if longitude < 0.0 { "W" } else { "E" };
+        set(
+            TAG_GPS_LONGITUDE_REF,
+            TiffValue::Ascii(longitude_ref.to_string()),
+        );
+        set(
+            TAG_GPS_LONGITUDE,
+            TiffValue::Rational(Self::dms_rationals(longitude)),
+        );
+
+        if let Some(altitude) = data.gps_altitude.filter(|v| v.is_finite()) {
+            // 0 = sopra il livello del mare, 1 = sotto
+            let altitude_ref = u8::from(altitude < 0.0);
+            let altitude = (altitude.abs() * f64::from(GPS_ALTITUDE_DENOMINATOR)).round();
+            set(TAG_GPS_ALTITUDE_REF, TiffValue::Byte(vec![altitude_ref]));
+            set(
+                TAG_GPS_ALTITUDE,
+                TiffValue::Rational(vec![(
+                    altitude.min(f64::from(u32::MAX)) as u32,
+                    GPS_ALTITUDE_DENOMINATOR,
+                )]),
+            );
+        }
 
-        entry
+        Some(gps)
     }
 
-    /// Inietta il segmento EXIF in un JPEG rimuovendo eventuali EXIF esistenti
+    /// Gradi decimali → gradi, minuti e secondi come RATIONAL (segno nel tag Ref)
+    fn dms_rationals(value: f64) -> Vec<(u32, u32)> {
+        // Tutto in decimillesimi di secondo per evitare riporti tipo 59.99995 → 60
+        let per_second = u64::from(GPS_SECONDS_DENOMINATOR);
+        let total = (value.abs() * 3600.0 * per_second as f64).round() as u64;
+
+        let degrees = total / (3600 * per_second);
+        let minutes = total % (3600 * per_second) / (60 * per_second);
+        let seconds = total % (60 * per_second);
+
+        vec![
+            (degrees as u32, 1),
+            (minutes as u32, 1),
+            (seconds as u32, GPS_SECONDS_DENOMINATOR),
+        ]
+    }
+
+    /// Inietta i segmenti EXIF/XMP/IPTC in un JPEG rimuovendo quelli esistenti
     fn inject_exif_into_jpeg(jpeg_data: &[u8], exif_segment: &[u8]) -> Result<Vec<u8>, String> {
         let mut result = Vec::new();
 
@@ -255,8 +539,17 @@ impl ExifWriter {
                 return Err("Invalid JPEG structure".to_string());
             }
 
+            if i + 1 >= jpeg_data.len() {
+                return Err("Truncated JPEG".to_string());
+            }
             let marker = jpeg_data[i + 1];
 
+            // SOS: da qui in poi dati compressi, si copiano così come sono
+            if marker == 0xDA {
+                result.extend_from_slice(&jpeg_data[i..]);
+                break;
+            }
+
             // SOI, EOI, RST markers (standalone, no length)
             if marker == 0xD8 || marker == 0xD9 || (marker >= 0xD0 && marker <= 0xD7) {
                 result.push(jpeg_data[i]);
@@ -272,15 +565,26 @@ impl ExifWriter {
 
             let length = u16::from_be_bytes([jpeg_data[i + 2], jpeg_data[i + 3]]) as usize;
 
-            // Se è un vecchio APP1 EXIF, skippalo
+            // Se è un vecchio APP1 EXIF o XMP, skippalo
             if marker == 0xE1 && i + 10 < jpeg_data.len() {
-                if &jpeg_data[i + 4..i + 10] == b"Exif\0\0" {
+                let payload = &jpeg_data[i + 4..(i + 2 + length).min(jpeg_data.len())];
+                if payload.starts_with(b"Exif\0\0") || payload.starts_with(xmp::XMP_JPEG_SIGNATURE)
+                {
                     // Salta questo segmento
                     i += 2 + length;
                     continue;
                 }
             }
 
+            // Se è un vecchio APP13 Photoshop (IPTC), skippalo
+            if marker == 0xED && i + 4 <= jpeg_data.len() {
+                let payload = &jpeg_data[i + 4..(i + 2 + length).min(jpeg_data.len())];
+                if iptc::is_photoshop_app13(payload) {
+                    i += 2 + length;
+                    continue;
+                }
+            }
+
             // Copia questo segmento
             if i + 2 + length > jpeg_data.len() {
                 return Err("Invalid segment length".to_string());
@@ -296,7 +600,10 @@ impl ExifWriter {
 
 /// Funzione helper per verificare se la feature EXIF è supportata
 pub fn is_exif_supported_for_format(extension: &str) -> bool {
-    matches!(extension.to_lowercase().as_str(), "jpg" | "jpeg")
+    matches!(
+        extension.to_lowercase().as_str(),
+        "jpg" | "jpeg" | "png" | "webp"
+    )
 }
 
 #[cfg(test)]
@@ -307,8 +614,9 @@ mod tests {
     fn test_exif_supported_formats() {
         assert!(is_exif_supported_for_format("jpg"));
         assert!(is_exif_supported_for_format("JPEG"));
-        assert!(!is_exif_supported_for_format("png"));
-        assert!(!is_exif_supported_for_format("webp"));
+        assert!(is_exif_supported_for_format("webp"));
+        assert!(is_exif_supported_for_format("png"));
+        assert!(!is_exif_supported_for_format("gif"));
     }
 
     #[test]
@@ -323,16 +631,790 @@ mod tests {
     }
 
     #[test]
-    fn test_create_ascii_entry() {
-        let entry = ExifWriter::create_ascii_entry(0x0131, "Test");
-        assert_eq!(entry.len(), 12); // Standard IFD entry size
-        assert_eq!(u16::from_le_bytes([entry[0], entry[1]]), 0x0131);
+    fn test_oversized_exif_and_truncated_jpeg_rejected() {
+        // Un TIFF oltre il limite non può diventare un APP1 (lunghezza su 16 bit)
+        assert!(ExifWriter::app1_segment(&vec![0; MAX_APP1_PAYLOAD]).is_ok());
+        assert!(ExifWriter::app1_segment(&vec![0; MAX_APP1_PAYLOAD + 1]).is_err());
+
+        // Marker troncato subito dopo 0xFF
+        let jpeg = [0xFF, 0xD8, 0xFF];
+        assert_eq!(
+            ExifWriter::inject_exif_into_jpeg(&jpeg, &[]),
+            Err("Truncated JPEG".to_string())
+        );
+    }
+
+    /// Legge con kamadak-exif il TIFF ricostruito dal fallback
+    fn parse_rebuilt(data: &ExifData, options: &ExifOptions) -> exif::Exif {
+        let tiff = ExifWriter::build_exif_tree(data, options).to_bytes();
+        exif::Reader::new().read_raw(tiff).unwrap()
+    }
+
+    #[test]
+    fn test_rebuilt_exif_keeps_full_strings() {
+        let data = ExifData {
+            camera_make: Some("Canon".to_string()),
+            camera_model: Some("Canon EOS 5D Mark IV".to_string()),
+            lens_model: Some("EF24-70mm f/2.8L II USM".to_string()),
+            date_taken: Some("2024:06:02 08:00:00".to_string()),
+            date_digitized: Some("2024:05:01 10:20:30".to_string()),
+            date_modified: Some("2024:05:01 10:21:00".to_string()),
+            iso: Some(400),
+            ..ExifData::default()
+        };
+
+        let exif = parse_rebuilt(&data, &ExifOptions::default());
+
+        let model = exif.get_field(exif::Tag::Model, exif::In::PRIMARY).unwrap();
+        assert_eq!(
+            model.display_value().to_string(),
+            "\"Canon EOS 5D Mark IV\""
+        );
+        let lens = exif
+            .get_field(exif::Tag::LensModel, exif::In::PRIMARY)
+            .unwrap();
+        assert_eq!(
+            lens.display_value().to_string(),
+            "\"EF24-70mm f/2.8L II USM\""
+        );
+        let date = |tag| {
+            exif.get_field(tag, exif::In::PRIMARY)
+                .unwrap()
+                .display_value()
+                .to_string()
+        };
+        assert_eq!(date(exif::Tag::DateTime), "2024-06-02 08:00:00");
+        assert_eq!(date(exif::Tag::DateTimeOriginal), "2024-05-01 10:20:30");
+        assert_eq!(date(exif::Tag::DateTimeDigitized), "2024-05-01 10:21:00");
+
+        // La rilettura riporta ogni data nello stesso campo
+        let path = std::env::temp_dir().join("iron_test_exif_dates.jpg");
+        let mut jpeg = Vec::new();
+        image::DynamicImage::new_rgb8(4, 4)
+            .write_to(
+                &mut std::io::Cursor::new(&mut jpeg),
+                image::ImageFormat::Jpeg,
+            )
+            .unwrap();
+        let tiff = ExifWriter::build_exif_tree(&data, &ExifOptions::default()).to_bytes();
+        let jpeg =
+            ExifWriter::inject_exif_into_jpeg(&jpeg, &ExifWriter::app1_segment(&tiff).unwrap())
+                .unwrap();
+        fs::write(&path, jpeg).unwrap();
+        let reread = ExifHandler::extract_exif(&path).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(reread.date_taken, data.date_taken);
+        assert_eq!(reread.date_digitized, data.date_digitized);
+        assert_eq!(reread.date_modified, data.date_modified);
+
+        let iso = exif
+            .get_field(exif::Tag::PhotographicSensitivity, exif::In::PRIMARY)
+            .unwrap();
+        assert_eq!(iso.value.get_uint(0), Some(400));
+    }
+
+    #[test]
+    fn test_orientation_reset_to_normal() {
+        let data = ExifData {
+            orientation: Some(6),
+            ..ExifData::default()
+        };
+
+        let exif = parse_rebuilt(&data, &ExifOptions::default());
+        let orientation = exif
+            .get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+            .expect("orientation entry");
+
+        assert_eq!(orientation.value.get_uint(0), Some(1));
+    }
+
+    #[test]
+    fn test_rebuilt_exif_respects_copyright_option() {
+        let data = ExifData {
+            artist: Some("Jane Doe".to_string()),
+            copyright: Some("(c) Jane Doe".to_string()),
+            ..ExifData::default()
+        };
+        let options = ExifOptions {
+            preserve_copyright: false,
+            ..ExifOptions::default()
+        };
+
+        let exif = parse_rebuilt(&data, &options);
+        assert!(exif
+            .get_field(exif::Tag::Artist, exif::In::PRIMARY)
+            .is_none());
+        assert!(exif
+            .get_field(exif::Tag::Copyright, exif::In::PRIMARY)
+            .is_none());
+    }
+
+    #[test]
+    fn test_rebuilt_gps_ifd() {
+        let data = ExifData {
+            gps_latitude: Some(-33.856_785),
+            gps_longitude: Some(151.215_297),
+            gps_altitude: Some(-12.5),
+            ..ExifData::default()
+        };
+        let options = ExifOptions {
+            strip_gps: false,
+            ..ExifOptions::default()
+        };
+
+        let tiff = ExifWriter::build_exif_tree(&data, &options).to_bytes();
+        let exif = exif::Reader::new().read_raw(tiff.clone()).unwrap();
+        let field = |tag| &exif.get_field(tag, exif::In::PRIMARY).unwrap().value;
+
+        assert!(matches!(field(exif::Tag::GPSLatitudeRef), exif::Value::Ascii(v) if v[0] == b"S"));
+        assert!(matches!(field(exif::Tag::GPSLongitudeRef), exif::Value::Ascii(v) if v[0] == b"E"));
+        assert!(matches!(field(exif::Tag::GPSLatitude),
+            exif::Value::Rational(v) if v[0].num == 33 && v[1].num == 51 && v[0].denom == 1));
+        assert_eq!(field(exif::Tag::GPSAltitudeRef).get_uint(0), Some(1));
+        assert!(matches!(field(exif::Tag::GPSAltitude),
+            exif::Value::Rational(v) if v[0].to_f64() == 12.5));
+
+        // La lettura riporta le stesse coordinate decimali
+        let path = std::env::temp_dir().join("iron_test_exif_gps.jpg");
+        let mut jpeg = Vec::new();
+        image::DynamicImage::new_rgb8(4, 4)
+            .write_to(
+                &mut std::io::Cursor::new(&mut jpeg),
+                image::ImageFormat::Jpeg,
+            )
+            .unwrap();
+        let jpeg =
+            ExifWriter::inject_exif_into_jpeg(&jpeg, &ExifWriter::app1_segment(&tiff).unwrap())
+                .unwrap();
+        fs::write(&path, jpeg).unwrap();
+        let reread = crate::core::exif_handler::ExifHandler::extract_exif(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert!((reread.gps_latitude.unwrap() + 33.856_785).abs() < 1e-6);
+        assert!((reread.gps_longitude.unwrap() - 151.215_297).abs() < 1e-6);
+        assert_eq!(reread.gps_altitude, Some(-12.5));
+    }
+
+    #[test]
+    fn test_rebuilt_gps_stripped() {
+        let data = ExifData {
+            gps_latitude: Some(45.0),
+            gps_longitude: Some(9.0),
+            ..ExifData::default()
+        };
+        let options = ExifOptions {
+            strip_gps: true,
+            ..ExifOptions::default()
+        };
+
+        assert!(ExifWriter::build_exif_tree(&data, &options).gps.is_none());
+    }
+
+    #[test]
+    fn test_raw_exif_fuzzed_location() {
+        let source = std::env::temp_dir().join("iron_test_exif_fuzz_source.jpg");
+        let dest = std::env::temp_dir().join("iron_test_exif_fuzz_dest.jpg");
+        write_source_jpeg(&source);
+        image::DynamicImage::new_rgb8(4, 4).save(&dest).unwrap();
+
+        let options = ExifOptions {
+            strip_gps: false,
+            gps_precision: GpsPrecision::City,
+            ..ExifOptions::default()
+        };
+        ExifWriter::copy_exif(&source, &dest, &options).unwrap();
+
+        let reread = ExifHandler::extract_exif(&dest).unwrap();
+        let file = File::open(&dest).unwrap();
+        let exif = exif::Reader::new()
+            .read_from_container(&mut BufReader::new(&file))
+            .unwrap();
+        fs::remove_file(&source).ok();
+        fs::remove_file(&dest).ok();
+
+        // 45°27'51.15" N, 9°11'25.21" E → griglia di 0.1°
+        assert!((reread.gps_latitude.unwrap() - 45.5).abs() < 1e-6);
+        assert!((reread.gps_longitude.unwrap() - 9.2).abs() < 1e-6);
+        assert!(reread.gps_altitude.is_none());
+        assert!(exif
+            .get_field(exif::Tag::GPSImgDirection, exif::In::PRIMARY)
+            .is_none());
+        // Il resto dell'EXIF originale è intatto
+        assert!(exif
+            .get_field(exif::Tag::FNumber, exif::In::PRIMARY)
+            .is_some());
+    }
+
+    #[test]
+    fn test_webp_exif_keeps_icc() {
+        let source = std::env::temp_dir().join("iron_test_exif_webp_source.jpg");
+        let dest = std::env::temp_dir().join("iron_test_exif_webp_dest.webp");
+        write_source_jpeg(&source);
+        let rgb = image::RgbImage::from_pixel(6, 6, image::Rgb([90, 120, 150]));
+        let encoded = webp::Encoder::from_rgb(&rgb, 6, 6).encode(80.0).to_vec();
+        let icc = vec![7u8; 64];
+        fs::write(&dest, IccWriter::embed_in_webp(&encoded, &icc).unwrap()).unwrap();
+
+        ExifWriter::copy_exif(&source, &dest, &ExifOptions::default()).unwrap();
+
+        let written = fs::read(&dest).unwrap();
+        let file = File::open(&dest).unwrap();
+        let exif = exif::Reader::new()
+            .read_from_container(&mut BufReader::new(&file))
+            .unwrap();
+        fs::remove_file(&source).ok();
+        fs::remove_file(&dest).ok();
+
+        let make = exif.get_field(exif::Tag::Make, exif::In::PRIMARY).unwrap();
+        assert_eq!(make.display_value().to_string(), "\"Canon\"");
+
+        // Il profilo ICC embedded prima dell'EXIF è ancora presente
+        assert!(written
+            .windows(icc.len())
+            .any(|window| window == icc.as_slice()));
+        assert!(image::load_from_memory(&written).is_ok());
+    }
+
+    #[test]
+    fn test_png_exif_and_text_chunks() {
+        let source = std::env::temp_dir().join("iron_test_exif_png_source.jpg");
+        let dest = std::env::temp_dir().join("iron_test_exif_png_dest.png");
+        write_source_jpeg(&source);
+        image::DynamicImage::new_rgb8(5, 5).save(&dest).unwrap();
+
+        let source_data = ExifData {
+            artist: Some("Jane Doe".to_string()),
+            copyright: Some("© Jane Doe".to_string()),
+            ..ExifData::default()
+        };
+        let options = ExifOptions {
+            png_text_chunks: true,
+            ..ExifOptions::default()
+        };
+        ExifWriter::write_png_exif(&source, &dest, Some(&source_data), None, &options).unwrap();
+
+        let file = File::open(&dest).unwrap();
+        let exif = exif::Reader::new()
+            .read_from_container(&mut BufReader::new(&file))
+            .unwrap();
+        let decoder = png::Decoder::new(BufReader::new(File::open(&dest).unwrap()));
+        let reader = decoder.read_info().unwrap();
+        let info = reader.info().clone();
+        fs::remove_file(&source).ok();
+        fs::remove_file(&dest).ok();
+
+        let make = exif.get_field(exif::Tag::Make, exif::In::PRIMARY).unwrap();
+        assert_eq!(make.display_value().to_string(), "\"Canon\"");
+        assert_eq!(info.uncompressed_latin1_text[0].text, "Jane Doe");
+        assert_eq!(info.utf8_text[0].get_text().unwrap(), "© Jane Doe");
+    }
+
+    #[test]
+    fn test_xmp_copied_and_filtered() {
+        use crate::core::xmp::{extract_xmp, jpeg_xmp_segment, XmpData};
+
+        // Sorgente con EXIF e XMP (autore, diritti, rating)
+        let source = std::env::temp_dir().join("iron_test_xmp_source.jpg");
+        write_source_jpeg(&source);
+        let source_xmp = XmpData {
+            creator: vec!["Jane Doe".to_string()],
+            rights: Some("All rights reserved".to_string()),
+            rating: Some(5),
+            ..XmpData::default()
+        };
+        let jpeg = fs::read(&source).unwrap();
+        let segment = jpeg_xmp_segment(source_xmp.to_packet().as_bytes()).unwrap();
+        fs::write(
+            &source,
+            ExifWriter::inject_exif_into_jpeg(&jpeg, &segment).unwrap(),
+        )
+        .unwrap();
+
+        let mut results = Vec::new();
+        for (extension, preserve_copyright) in [("jpg", true), ("webp", true), ("png", false)] {
+            let dest = std::env::temp_dir().join(format!("iron_test_xmp_dest.{}", extension));
+            image::DynamicImage::new_rgb8(4, 4).save(&dest).unwrap();
+            let options = ExifOptions {
+                preserve_copyright,
+                ..ExifOptions::default()
+            };
+            ExifWriter::copy_exif(&source, &dest, &options).unwrap();
+            results.push(extract_xmp(&dest));
+            fs::remove_file(&dest).ok();
+        }
+        fs::remove_file(&source).ok();
+
+        assert_eq!(results[0].as_ref(), Some(&source_xmp));
+        assert_eq!(results[1].as_ref(), Some(&source_xmp));
+        // Senza preserve_copyright resta solo il rating
+        let filtered = results[2].as_ref().unwrap();
+        assert!(filtered.creator.is_empty());
+        assert!(filtered.rights.is_none());
+        assert_eq!(filtered.rating, Some(5));
+    }
+
+    #[test]
+    fn test_template_written_to_every_format() {
+        use crate::core::xmp::extract_xmp;
+
+        // Sorgente senza alcun metadato
+        let source = std::env::temp_dir().join("iron_test_template_source.jpg");
+        image::DynamicImage::new_rgb8(4, 4).save(&source).unwrap();
+        let options = ExifOptions {
+            preserve_copyright: false,
+            template: MetadataTemplate {
+                artist: Some("Staff Photographer".to_string()),
+                copyright: Some("© {year} Agency".to_string()),
+                keywords: vec!["news".to_string()],
+                rights_url: Some("https://example.com/license".to_string()),
+                ..MetadataTemplate::default()
+            },
+            ..ExifOptions::default()
+        };
+        let copyright = format!("© {} Agency", current_year());
+
+        for extension in ["jpg", "png", "webp"] {
+            let dest = std::env::temp_dir().join(format!("iron_test_template_dest.{}", extension));
+            image::DynamicImage::new_rgb8(4, 4).save(&dest).unwrap();
+            ExifWriter::copy_exif(&source, &dest, &options).unwrap();
+
+            let file = File::open(&dest).unwrap();
+            let exif = exif::Reader::new()
+                .read_from_container(&mut BufReader::new(&file))
+                .unwrap();
+            let artist = exif
+                .get_field(exif::Tag::Artist, exif::In::PRIMARY)
+                .unwrap();
+            assert_eq!(artist.display_value().to_string(), "\"Staff Photographer\"");
+            let field = exif
+                .get_field(exif::Tag::Copyright, exif::In::PRIMARY)
+                .unwrap();
+            match &field.value {
+                exif::Value::Ascii(values) => assert_eq!(values[0], copyright.as_bytes()),
+                value => panic!("unexpected Copyright value: {:?}", value),
+            }
+
+            let xmp = extract_xmp(&dest).unwrap();
+            assert_eq!(xmp.creator, vec!["Staff Photographer"]);
+            assert_eq!(xmp.rights.as_deref(), Some(copyright.as_str()));
+            assert_eq!(xmp.keywords, vec!["news"]);
+            assert_eq!(
+                xmp.web_statement.as_deref(),
+                Some("https://example.com/license")
+            );
+            if extension == "jpg" {
+                // IPTC nuovo anche se il sorgente non ne ha
+                let iptc = crate::core::iptc::extract_iptc(&dest).unwrap();
+                assert_eq!(iptc.by_line, vec!["Staff Photographer"]);
+                assert_eq!(iptc.keywords, vec!["news"]);
+            }
+            fs::remove_file(&dest).ok();
+        }
+        fs::remove_file(&source).ok();
+    }
+
+    #[test]
+    fn test_template_overrides_source_copyright() {
+        let source = std::env::temp_dir().join("iron_test_template_override.jpg");
+        let dest = std::env::temp_dir().join("iron_test_template_override_dest.jpg");
+        write_source_jpeg(&source);
+        image::DynamicImage::new_rgb8(4, 4).save(&dest).unwrap();
+
+        let options = ExifOptions {
+            template: MetadataTemplate {
+                description: Some("Harbor at dusk".to_string()),
+                ..MetadataTemplate::default()
+            },
+            ..ExifOptions::default()
+        };
+        ExifWriter::copy_exif(&source, &dest, &options).unwrap();
+
+        let file = File::open(&dest).unwrap();
+        let exif = exif::Reader::new()
+            .read_from_container(&mut BufReader::new(&file))
+            .unwrap();
+        fs::remove_file(&source).ok();
+        fs::remove_file(&dest).ok();
+
+        let description = exif
+            .get_field(exif::Tag::ImageDescription, exif::In::PRIMARY)
+            .unwrap();
+        assert_eq!(
+            description.display_value().to_string(),
+            "\"Harbor at dusk\""
+        );
+        // Gli altri tag del sorgente restano quelli originali
+        let make = exif.get_field(exif::Tag::Make, exif::In::PRIMARY).unwrap();
+        assert_eq!(make.display_value().to_string(), "\"Canon\"");
+    }
+
+    #[test]
+    fn test_strip_all_template_ignores_source_structure() {
+        let source = std::env::temp_dir().join("iron_test_strip_template_source.jpg");
+        let dest = std::env::temp_dir().join("iron_test_strip_template_dest.jpg");
+
+        // Sorgente con thumbnail (IFD1) e Interop IFD
+        let mut tree = ExifTree::new(ByteOrder::LittleEndian);
+        let make = tree.entry(0x010F, TiffValue::Ascii("Canon".to_string()));
+        tree.ifd0.set(make);
+        let mut exif = Ifd::default();
+        exif.set(tree.entry(0x829D, TiffValue::Rational(vec![(28, 10)]))); // FNumber
+        tree.exif = Some(exif);
+        let mut interop = Ifd::default();
+        interop.set(tree.entry(0x0001, TiffValue::Ascii("R98".to_string()))); // InteroperabilityIndex
+        tree.interop = Some(interop);
+        tree.set_thumbnail(vec![0xFF, 0xD8, 0xFF, 0xD9]);
+        let mut jpeg = Vec::new();
+        image::DynamicImage::new_rgb8(8, 8)
+            .write_to(
+                &mut std::io::Cursor::new(&mut jpeg),
+                image::ImageFormat::Jpeg,
+            )
+            .unwrap();
+        let jpeg = ExifWriter::inject_exif_into_jpeg(
+            &jpeg,
+            &ExifWriter::app1_segment(&tree.to_bytes()).unwrap(),
+        )
+        .unwrap();
+        fs::write(&source, jpeg).unwrap();
+        image::DynamicImage::new_rgb8(4, 4).save(&dest).unwrap();
+
+        let options = ExifOptions {
+            policy: MetadataPolicy::StripAll,
+            strip_thumbnail: false,
+            template: MetadataTemplate {
+                artist: Some("Staff Photographer".to_string()),
+                ..MetadataTemplate::default()
+            },
+            ..ExifOptions::default()
+        };
+        ExifWriter::copy_exif(&source, &dest, &options).unwrap();
+
+        let written = ExifTree::parse(&ExifWriter::read_raw_exif(&dest).unwrap()).unwrap();
+        fs::remove_file(&source).ok();
+        fs::remove_file(&dest).ok();
+
+        assert!(written.ifd1.is_none());
+        assert!(written.thumbnail.is_none());
+        assert!(written.interop.is_none());
+        assert!(written.ifd0.get(0x010F).is_none());
+        assert!(written.ifd0.get(TAG_ARTIST).is_some());
+    }
+
+    #[test]
+    fn test_iptc_preserved_with_copyright() {
+        use crate::core::iptc::extract_iptc;
+
+        // Sorgente con solo APP13: byline 2:80 e credit 2:110
+        let mut iim = vec![0x1C, 2, 80, 0, 8];
+        iim.extend_from_slice(b"Jane Doe");
+        iim.extend_from_slice(&[0x1C, 2, 110, 0, 6]);
+        iim.extend_from_slice(b"Agency");
+        let mut app13 = b"Photoshop 3.0\08BIM\x04\x04\0\0".to_vec();
+        app13.extend_from_slice(&(iim.len() as u32).to_be_bytes());
+        app13.extend_from_slice(&iim);
+
+        let source = std::env::temp_dir().join("iron_test_iptc_source.jpg");
+        image::DynamicImage::new_rgb8(4, 4).save(&source).unwrap();
+        let jpeg = fs::read(&source).unwrap();
+        let mut segment = vec![0xFF, 0xED];
+        segment.extend_from_slice(&((app13.len() + 2) as u16).to_be_bytes());
+        segment.extend_from_slice(&app13);
+        fs::write(
+            &source,
+            ExifWriter::inject_exif_into_jpeg(&jpeg, &segment).unwrap(),
+        )
+        .unwrap();
+
+        let dest = std::env::temp_dir().join("iron_test_iptc_dest.jpg");
+        image::DynamicImage::new_rgb8(4, 4).save(&dest).unwrap();
+        ExifWriter::copy_exif(&source, &dest, &ExifOptions::default()).unwrap();
+        let data = extract_iptc(&dest).unwrap();
+        assert_eq!(data.by_line, vec!["Jane Doe"]);
+        assert_eq!(data.credit.as_deref(), Some("Agency"));
+
+        // Senza preserve_copyright l'IPTC non viene copiato
+        image::DynamicImage::new_rgb8(4, 4).save(&dest).unwrap();
+        let options = ExifOptions {
+            preserve_copyright: false,
+            ..ExifOptions::default()
+        };
+        assert!(ExifWriter::copy_exif(&source, &dest, &options).is_err());
+        assert!(extract_iptc(&dest).is_none());
+
+        fs::remove_file(&source).ok();
+        fs::remove_file(&dest).ok();
+    }
+
+    #[test]
+    fn test_dms_rationals_carry() {
+        // 59.99999 secondi arrotondati non devono diventare "60"
+        assert_eq!(
+            ExifWriter::dms_rationals(10.999_999_99),
+            vec![(11, 1), (0, 1), (0, GPS_SECONDS_DENOMINATOR)]
+        );
+        assert_eq!(
+            ExifWriter::dms_rationals(-45.5),
+            vec![(45, 1), (30, 1), (0, GPS_SECONDS_DENOMINATOR)]
+        );
+    }
+
+    /// JPEG sorgente con EXIF completo: Make lungo, Orientation, FNumber,
+    /// MakerNote, GPS e thumbnail
+    fn write_source_jpeg(path: &Path) {
+        use crate::core::tiff::TAG_MAKER_NOTE;
+
+        let mut tree = ExifTree::new(ByteOrder::LittleEndian);
+        let make = tree.entry(0x010F, TiffValue::Ascii("Canon".to_string()));
+        let orientation = tree.entry(TAG_ORIENTATION, TiffValue::Short(vec![6]));
+        tree.ifd0.set(make);
+        tree.ifd0.set(orientation);
+
+        let mut exif = Ifd::default();
+        exif.set(tree.entry(0x829D, TiffValue::Rational(vec![(28, 10)]))); // FNumber
+        exif.set(tree.entry(TAG_MAKER_NOTE, TiffValue::Undefined(b"MAKERNOT".to_vec())));
+        let mut gps = Ifd::default();
+        gps.set(tree.entry(0x0001, TiffValue::Ascii("N".to_string()))); // GPSLatitudeRef
+        gps.set(tree.entry(
+            0x0002,
+            TiffValue::Rational(vec![(45, 1), (27, 1), (5115, 100)]),
+        )); // GPSLatitude
+        gps.set(tree.entry(0x0003, TiffValue::Ascii("E".to_string()))); // GPSLongitudeRef
+        gps.set(tree.entry(
+            0x0004,
+            TiffValue::Rational(vec![(9, 1), (11, 1), (2521, 100)]),
+        )); // GPSLongitude
+        gps.set(tree.entry(0x0006, TiffValue::Rational(vec![(122, 1)]))); // GPSAltitude
+        gps.set(tree.entry(0x0011, TiffValue::Rational(vec![(270, 1)]))); // GPSImgDirection
+        tree.exif = Some(exif);
+        tree.gps = Some(gps);
+        tree.set_thumbnail(vec![0xFF, 0xD8, 0xFF, 0xD9]);
+
+        let mut jpeg = Vec::new();
+        image::DynamicImage::new_rgb8(8, 8)
+            .write_to(
+                &mut std::io::Cursor::new(&mut jpeg),
+                image::ImageFormat::Jpeg,
+            )
+            .unwrap();
+        let jpeg = ExifWriter::inject_exif_into_jpeg(
+            &jpeg,
+            &ExifWriter::app1_segment(&tree.to_bytes()).unwrap(),
+        )
+        .unwrap();
+        fs::write(path, jpeg).unwrap();
+    }
+
+    #[test]
+    fn test_policy_applied_to_every_format() {
+        use crate::core::iptc::extract_iptc;
+        use crate::core::xmp::{extract_xmp, jpeg_xmp_segment, XmpData};
+
+        let source = std::env::temp_dir().join("iron_test_policy_source.jpg");
+        write_source_jpeg(&source);
+
+        // Aggiunge al sorgente XMP e IPTC (byline, credit, keyword e città)
+        let packet = XmpData {
+            creator: vec!["Jane Doe".to_string()],
+            credit: Some("Agency".to_string()),
+            keywords: vec!["harbor".to_string()],
+            rating: Some(4),
+            ..XmpData::default()
+        }
+        .to_packet();
+        let mut iim = Vec::new();
+        for (dataset, value) in [
+            (80, "Jane Doe"),
+            (110, "Agency"),
+            (25, "harbor"),
+            (90, "Genova"),
+        ] {
+            iim.extend_from_slice(&[0x1C, 2, dataset, 0, value.len() as u8]);
+            iim.extend_from_slice(value.as_bytes());
+        }
+        let mut app13 = b"Photoshop 3.0\08BIM\x04\x04\0\0".to_vec();
+        app13.extend_from_slice(&(iim.len() as u32).to_be_bytes());
+        app13.extend_from_slice(&iim);
+        let jpeg = fs::read(&source).unwrap();
+        let mut with_metadata = jpeg[..2].to_vec();
+        with_metadata.extend(jpeg_xmp_segment(packet.as_bytes()).unwrap());
+        with_metadata.extend_from_slice(&[0xFF, 0xED]);
+        with_metadata.extend_from_slice(&((app13.len() + 2) as u16).to_be_bytes());
+        with_metadata.extend_from_slice(&app13);
+        with_metadata.extend_from_slice(&jpeg[2..]);
+        fs::write(&source, with_metadata).unwrap();
+
+        let options = ExifOptions {
+            strip_gps: false,
+            policy: MetadataPolicy::AllowList {
+                categories: vec![TagCategory::Capture],
+                tags: vec![
+                    "GPSLatitudeRef".to_string(),
+                    "GPSLatitude".to_string(),
+                    "Credit".to_string(),
+                ],
+            },
+            ..ExifOptions::default()
+        };
+
+        for extension in ["jpg", "png", "webp"] {
+            let dest = std::env::temp_dir().join(format!("iron_test_policy_dest.{}", extension));
+            image::DynamicImage::new_rgb8(4, 4).save(&dest).unwrap();
+            ExifWriter::copy_exif(&source, &dest, &options).unwrap();
+
+            let file = File::open(&dest).unwrap();
+            let exif = exif::Reader::new()
+                .read_from_container(&mut BufReader::new(&file))
+                .unwrap();
+            let has = |tag: exif::Tag| exif.get_field(tag, exif::In::PRIMARY).is_some();
+            assert!(!has(exif::Tag::Make), "{}", extension);
+            assert!(has(exif::Tag::FNumber), "{}", extension);
+            assert!(has(exif::Tag::GPSLatitude), "{}", extension);
+            assert!(!has(exif::Tag::GPSLongitude), "{}", extension);
+            // Tag strutturale: sempre presente
+            assert!(has(exif::Tag::Orientation), "{}", extension);
+
+            // XMP e IPTC filtrati con la stessa policy
+            let xmp = extract_xmp(&dest).unwrap();
+            assert_eq!(xmp.credit.as_deref(), Some("Agency"), "{}", extension);
+            assert!(xmp.creator.is_empty(), "{}", extension);
+            assert!(xmp.keywords.is_empty(), "{}", extension);
+            assert!(xmp.rating.is_none(), "{}", extension);
+            if extension == "jpg" {
+                let iptc = extract_iptc(&dest).unwrap();
+                assert_eq!(iptc.credit.as_deref(), Some("Agency"));
+                assert!(iptc.by_line.is_empty());
+                assert!(iptc.keywords.is_empty());
+                assert!(iptc.city.is_none());
+            }
+            fs::remove_file(&dest).ok();
+        }
+
+        // StripAll senza template: nessun metadato da scrivere
+        let dest = std::env::temp_dir().join("iron_test_policy_strip.jpg");
+        image::DynamicImage::new_rgb8(4, 4).save(&dest).unwrap();
+        let options = ExifOptions {
+            policy: MetadataPolicy::StripAll,
+            ..ExifOptions::default()
+        };
+        assert!(ExifWriter::copy_exif(&source, &dest, &options).is_err());
+        fs::remove_file(&dest).ok();
+        fs::remove_file(&source).ok();
+    }
+
+    #[test]
+    fn test_policy_applied_after_gps_fuzzing() {
+        let source = std::env::temp_dir().join("iron_test_policy_fuzz_source.jpg");
+        let dest = std::env::temp_dir().join("iron_test_policy_fuzz_dest.jpg");
+        write_source_jpeg(&source);
+        image::DynamicImage::new_rgb8(4, 4).save(&dest).unwrap();
+
+        let options = ExifOptions {
+            strip_gps: false,
+            gps_precision: GpsPrecision::City,
+            policy: MetadataPolicy::DenyList {
+                categories: vec![],
+                tags: vec!["GPSLongitude".to_string()],
+            },
+            ..ExifOptions::default()
+        };
+        ExifWriter::copy_exif(&source, &dest, &options).unwrap();
+
+        let file = File::open(&dest).unwrap();
+        let exif = exif::Reader::new()
+            .read_from_container(&mut BufReader::new(&file))
+            .unwrap();
+        fs::remove_file(&source).ok();
+        fs::remove_file(&dest).ok();
+
+        let has = |tag: exif::Tag| exif.get_field(tag, exif::In::PRIMARY).is_some();
+        assert!(has(exif::Tag::GPSLatitude));
+        assert!(!has(exif::Tag::GPSLongitude));
+    }
+
+    #[test]
+    fn test_raw_exif_passthrough() {
+        let source = std::env::temp_dir().join("iron_test_exif_source.jpg");
+        let dest = std::env::temp_dir().join("iron_test_exif_dest.jpg");
+        write_source_jpeg(&source);
+        image::DynamicImage::new_rgb8(4, 4).save(&dest).unwrap();
+
+        let options = ExifOptions {
+            strip_gps: true,
+            ..ExifOptions::default()
+        };
+        ExifWriter::copy_exif(&source, &dest, &options).unwrap();
+
+        let file = File::open(&dest).unwrap();
+        let exif = exif::Reader::new()
+            .read_from_container(&mut BufReader::new(&file))
+            .unwrap();
+        fs::remove_file(&source).ok();
+        fs::remove_file(&dest).ok();
+
+        // Stringhe intere (niente "Can") e Exif SubIFD preservata
+        let make = exif.get_field(exif::Tag::Make, exif::In::PRIMARY).unwrap();
+        assert_eq!(make.display_value().to_string(), "\"Canon\"");
+        let f_number = exif
+            .get_field(exif::Tag::FNumber, exif::In::PRIMARY)
+            .unwrap();
+        assert!(matches!(f_number.value, exif::Value::Rational(ref v) if v[0].num == 28));
+
+        let orientation = exif
+            .get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+            .unwrap();
+        assert_eq!(orientation.value.get_uint(0), Some(1));
+        let software = exif
+            .get_field(exif::Tag::Software, exif::In::PRIMARY)
+            .unwrap();
+        assert!(software
+            .display_value()
+            .to_string()
+            .contains("Iron Optimizer"));
+
+        // Rimozioni chirurgiche secondo le opzioni
+        assert!(exif
+            .get_field(exif::Tag::GPSLatitudeRef, exif::In::PRIMARY)
+            .is_none());
+        assert!(exif
+            .get_field(exif::Tag::MakerNote, exif::In::PRIMARY)
+            .is_none());
+        assert!(exif
+            .get_field(exif::Tag::JPEGInterchangeFormat, exif::In::THUMBNAIL)
+            .is_none());
     }
 
     #[test]
-    fn test_create_short_entry() {
-        let entry = ExifWriter::create_short_entry(0x0112, 1);
-        assert_eq!(entry.len(), 12);
-        assert_eq!(u16::from_le_bytes([entry[0], entry[1]]), 0x0112);
+    fn test_raw_exif_keeps_selected_blocks() {
+        let source = std::env::temp_dir().join("iron_test_exif_keep_source.jpg");
+        let dest = std::env::temp_dir().join("iron_test_exif_keep_dest.jpg");
+        write_source_jpeg(&source);
+        image::DynamicImage::new_rgb8(4, 4).save(&dest).unwrap();
+
+        let options = ExifOptions {
+            strip_gps: false,
+            strip_thumbnail: false,
+            strip_maker_note: false,
+            ..ExifOptions::default()
+        };
+        ExifWriter::copy_exif(&source, &dest, &options).unwrap();
+
+        let file = File::open(&dest).unwrap();
+        let exif = exif::Reader::new()
+            .read_from_container(&mut BufReader::new(&file))
+            .unwrap();
+        fs::remove_file(&source).ok();
+        fs::remove_file(&dest).ok();
+
+        assert!(exif
+            .get_field(exif::Tag::GPSLatitudeRef, exif::In::PRIMARY)
+            .is_some());
+        let maker_note = exif
+            .get_field(exif::Tag::MakerNote, exif::In::PRIMARY)
+            .unwrap();
+        assert!(matches!(maker_note.value, exif::Value::Undefined(ref v, _) if v == b"MAKERNOT"));
+        assert!(exif
+            .get_field(exif::Tag::JPEGInterchangeFormatLength, exif::In::THUMBNAIL)
+            .is_some());
     }
 }
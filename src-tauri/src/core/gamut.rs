@@ -0,0 +1,264 @@
+// src-tauri/src/core/gamut.rs
+
+use crate::core::color_management::{icc_color_space, ColorManager};
+use crate::core::color_profile::{self, ColorProfile};
+use crate::core::exif_handler::ExifHandler;
+use crate::core::image_decoder;
+use crate::core::models::GamutReport;
+use crate::core::settings::OutputColorSpace;
+use image::{imageops::FilterType, DynamicImage, GrayImage, Rgb, RgbImage};
+use rayon::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+const MASK_DIR_NAME: &str = "iron-gamut-masks";
+const MAX_MASK_DIMENSION: u32 = 2048; // Lato massimo della maschera di overlay
+const MAX_FILE_SIZE: u64 = 1_000_000_000; // 1GB
+const OVERLAY_COLOR: Rgb<u8> = Rgb([255, 0, 255]); // Magenta per i pixel fuori gamut
+
+/// Analizza quanta parte di ogni immagine cade fuori dal gamut di destinazione,
+/// così da scegliere l'intento di conversione immagine per immagine.
+#[tauri::command]
+pub async fn analyze_gamut(
+    paths: Vec<String>,
+    output_color_space: Option<OutputColorSpace>,
+    generate_mask: bool,
+) -> Result<Vec<GamutReport>, String> {
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if paths.len() > 10000 {
+        return Err("Too many files (max 10000)".to_string());
+    }
+
+    let handle = tauri::async_runtime::spawn_blocking(move || {
+        let destination = output_color_space.unwrap_or_default();
+        let color_manager = ColorManager::with_destination(&destination)?;
+
+        let mask_dir = if generate_mask {
+            let dir = std::env::temp_dir().join(MASK_DIR_NAME);
+            fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create mask directory: {}", e))?;
+            Some(dir)
+        } else {
+            None
+        };
+
+        Ok(paths
+            .par_iter()
+            .map(|path| analyze_image(Path::new(path), &color_manager, mask_dir.as_deref()))
+            .collect())
+    });
+
+    handle
+        .await
+        .map_err(|e| format!("Gamut analysis failed: {}", e))?
+}
+
+/// Analizza una singola immagine; gli errori finiscono nel report
+fn analyze_image(
+    path: &Path,
+    color_manager: &ColorManager,
+    mask_dir: Option<&Path>,
+) -> GamutReport {
+    let color_profile = color_profile::detect_color_profile(path);
+
+    let mut report = GamutReport {
+        path: path.to_string_lossy().to_string(),
+        color_profile: color_profile.clone(),
+        total_pixels: 0,
+        out_of_gamut_pixels: 0,
+        out_of_gamut_percentage: 0.0,
+        mask_path: None,
+        error: None,
+    };
+
+    if let Err(e) = fill_report(&mut report, path, &color_profile, color_manager, mask_dir) {
+        eprintln!("⚠ Gamut analysis failed for {}: {}", path.display(), e);
+        report.error = Some(e);
+    }
+
+    report
+}
+
+fn fill_report(
+    report: &mut GamutReport,
+    path: &Path,
+    color_profile: &ColorProfile,
+    color_manager: &ColorManager,
+    mask_dir: Option<&Path>,
+) -> Result<(), String> {
+    let file_size = fs::metadata(path)
+        .map_err(|e| format!("Cannot read file metadata: {}", e))?
+        .len();
+    if file_size > MAX_FILE_SIZE {
+        return Err("File too large".to_string());
+    }
+
+    let img = image_decoder::decode_image(path, file_size)?;
+
+    // Stesso profilo sorgente usato dalla conversione: ICC embedded o chunk PNG
+    let source_icc = color_profile::read_icc_profile(path).or_else(|| {
+        let chunks = color_profile::read_png_color_chunks(path)?;
+        ColorManager::icc_from_png_chunks(&chunks).ok()
+    });
+
+    let (source_icc, source_profile) = mask_source(source_icc, color_profile)?;
+    let mask = color_manager.gamut_mask(&img, source_icc.as_deref(), source_profile)?;
+
+    let total_pixels = u64::from(mask.width()) * u64::from(mask.height());
+    let out_of_gamut_pixels = mask.pixels().filter(|pixel| pixel[0] > 0).count() as u64;
+
+    report.total_pixels = total_pixels;
+    report.out_of_gamut_pixels = out_of_gamut_pixels;
+    if total_pixels > 0 {
+        report.out_of_gamut_percentage = out_of_gamut_pixels as f64 / total_pixels as f64 * 100.0;
+    }
+
+    if let Some(mask_dir) = mask_dir {
+        let overlay = create_overlay(&img, &mask);
+
+        // Orienta l'overlay come viene mostrata la foto
+        let overlay = match ExifHandler::read_orientation(path) {
+            Some(orientation) => image_decoder::apply_exif_orientation(overlay, orientation),
+            None => overlay,
+        };
+
+        let mask_path = mask_path(mask_dir, path);
+        overlay
+            .save(&mask_path)
+            .map_err(|e| format!("Failed to save gamut mask: {}", e))?;
+        report.mask_path = Some(mask_path.to_string_lossy().to_string());
+    }
+
+    Ok(())
+}
+
+/// Profilo sorgente per il gamut check dei pixel decodificati.
+/// Il decoder converte già il CMYK in sRGB e i grigi sono neutri (sempre in gamut):
+/// in entrambi i casi i pixel si analizzano come sRGB.
+fn mask_source(
+    source_icc: Option<Vec<u8>>,
+    color_profile: &ColorProfile,
+) -> Result<(Option<Vec<u8>>, &ColorProfile), String> {
+    let Some(icc_data) = source_icc else {
+        return Ok((None, color_profile));
+    };
+
+    match icc_color_space(&icc_data) {
+        Some(b"RGB ") => Ok((Some(icc_data), color_profile)),
+        Some(b"CMYK") | Some(b"GRAY") => Ok((None, &ColorProfile::Srgb)),
+        color_space => Err(format!(
+            "Gamut check not applicable to {} profiles",
+            color_space
+                .map(|signature| String::from_utf8_lossy(signature).trim().to_string())
+                .unwrap_or_else(|| "invalid".to_string())
+        )),
+    }
+}
+
+/// Overlay: immagine in scala di grigi con i pixel fuori gamut in magenta
+fn create_overlay(img: &DynamicImage, mask: &GrayImage) -> DynamicImage {
+    let luma = img.to_luma8();
+    let overlay = RgbImage::from_fn(mask.width(), mask.height(), |x, y| {
+        if mask.get_pixel(x, y)[0] > 0 {
+            OVERLAY_COLOR
+        } else {
+            let value = luma.get_pixel(x, y)[0];
+            Rgb([value, value, value])
+        }
+    });
+
+    let overlay = DynamicImage::ImageRgb8(overlay);
+    if overlay.width() > MAX_MASK_DIMENSION || overlay.height() > MAX_MASK_DIMENSION {
+        // Nearest mantiene visibili anche i singoli pixel fuori gamut
+        overlay.resize(MAX_MASK_DIMENSION, MAX_MASK_DIMENSION, FilterType::Nearest)
+    } else {
+        overlay
+    }
+}
+
+/// Percorso della maschera, univoco per file sorgente
+fn mask_path(mask_dir: &Path, path: &Path) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    let canonical_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    canonical_path.to_string_lossy().hash(&mut hasher);
+
+    mask_dir.join(format!("{:x}_gamut.png", hasher.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay_marks_out_of_gamut_pixels() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(2, 1, Rgb([100, 100, 100])));
+        let mut mask = GrayImage::new(2, 1);
+        mask.put_pixel(1, 0, image::Luma([255]));
+
+        let overlay = create_overlay(&img, &mask).to_rgb8();
+        assert_eq!(overlay.get_pixel(0, 0), &Rgb([100, 100, 100]));
+        assert_eq!(overlay.get_pixel(1, 0), &OVERLAY_COLOR);
+    }
+
+    #[test]
+    fn test_analyze_untagged_png() {
+        // PNG senza profilo: viene trattato come sRGB, quindi tutto dentro il gamut sRGB
+        let path = std::env::temp_dir().join("iron_test_gamut_source.png");
+        RgbImage::from_pixel(4, 4, Rgb([0, 255, 0]))
+            .save(&path)
+            .unwrap();
+
+        let color_manager = ColorManager::new().unwrap();
+        let report = analyze_image(&path, &color_manager, None);
+        fs::remove_file(&path).ok();
+
+        assert!(report.error.is_none());
+        assert_eq!(report.total_pixels, 16);
+        assert_eq!(report.out_of_gamut_pixels, 0);
+        assert!(report.mask_path.is_none());
+    }
+
+    #[test]
+    fn test_cmyk_source_checked_as_decoded_srgb() {
+        use crate::core::cmyk_profile::default_cmyk_icc;
+        use crate::core::color_management::RenderingIntent;
+        use crate::core::image_decoder::CmykImage;
+
+        // Come fa il decoder: pixel CMYK convertiti in sRGB con il profilo embedded
+        let color_manager = ColorManager::new().unwrap();
+        let cmyk = CmykImage {
+            width: 2,
+            height: 1,
+            pixels: vec![255, 0, 0, 0, 0, 0, 0, 255],
+        };
+        let img = color_manager
+            .convert_cmyk(
+                &cmyk,
+                Some(default_cmyk_icc()),
+                RenderingIntent::Perceptual.into(),
+            )
+            .unwrap();
+
+        let detected = ColorProfile::Unknown("CMYK".to_string());
+        let (source_icc, source_profile) =
+            mask_source(Some(default_cmyk_icc().to_vec()), &detected).unwrap();
+        assert!(source_icc.is_none());
+        assert_eq!(source_profile, &ColorProfile::Srgb);
+
+        let mask = color_manager
+            .gamut_mask(&img, source_icc.as_deref(), source_profile)
+            .unwrap();
+        assert_eq!(mask.dimensions(), (2, 1));
+        assert!(mask.pixels().all(|pixel| pixel[0] == 0));
+
+        // Profili di altri spazi colore (es. Lab) non sono analizzabili
+        let mut lab_icc = default_cmyk_icc().to_vec();
+        lab_icc[16..20].copy_from_slice(b"Lab ");
+        assert!(mask_source(Some(lab_icc), &detected).is_err());
+    }
+}
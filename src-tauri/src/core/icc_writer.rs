@@ -0,0 +1,673 @@
+// src-tauri/src/core/icc_writer.rs
+
+use crate::core::color_management::RenderingIntent;
+use crate::core::xmp::XMP_PNG_KEYWORD;
+
+/// Firma dei segmenti APP2 che contengono il profilo ICC
+const JPEG_ICC_SIGNATURE: &[u8; 12] = b"ICC_PROFILE\0";
+/// Spazio utile per segmento APP2: 65535 - lunghezza (2) - firma (12) - sequenza (2)
+const JPEG_ICC_CHUNK_SIZE: usize = 65519;
+
+/// Flag VP8X (WebP extended format)
+const VP8X_FLAG_ICC: u8 = 0x20;
+const VP8X_FLAG_ALPHA: u8 = 0x10;
+const VP8X_FLAG_EXIF: u8 = 0x08;
+const VP8X_FLAG_XMP: u8 = 0x04;
+
+/// Firma dei file PNG
+const PNG_SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+/// Chunk di un container PNG o RIFF/WebP: nome (FourCC) e payload
+pub(crate) type Chunk<'a> = ([u8; 4], &'a [u8]);
+
+/// Metadati da scrivere in un WebP esteso (None = chunk esistente invariato)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WebpMetadata<'a> {
+    pub icc: Option<&'a [u8]>,
+    /// Blocco TIFF dell'EXIF, senza il prefisso "Exif\0\0" dei JPEG
+    pub exif: Option<&'a [u8]>,
+    /// Pacchetto XMP (XML)
+    pub xmp: Option<&'a [u8]>,
+}
+
+/// Modulo per embeddare i profili ICC nei file già codificati
+pub struct IccWriter;
+
+impl IccWriter {
+    /// Inserisce il profilo ICC in un JPEG come segmenti APP2 "ICC_PROFILE".
+    /// I profili oltre i 64 KB vengono divisi su più segmenti numerati.
+    pub fn embed_in_jpeg(jpeg_data: &[u8], icc_profile: &[u8]) -> Result<Vec<u8>, String> {
+        if jpeg_data.len() < 4 || jpeg_data[0] != 0xFF || jpeg_data[1] != 0xD8 {
+            return Err("Invalid JPEG structure".to_string());
+        }
+
+        let segments = Self::jpeg_icc_segments(icc_profile)?;
+
+        let mut result = Vec::with_capacity(jpeg_data.len() + icc_profile.len() + 64);
+        result.extend_from_slice(&jpeg_data[0..2]);
+
+        // Il segmento APP0 (JFIF), se presente, deve restare il primo dopo SOI
+        let mut i = 2;
+        if jpeg_data.len() > 5 && jpeg_data[2] == 0xFF && jpeg_data[3] == 0xE0 {
+            let length = u16::from_be_bytes([jpeg_data[4], jpeg_data[5]]) as usize;
+            if length < 2 || 4 + length > jpeg_data.len() {
+                return Err("Invalid segment length".to_string());
+            }
+            result.extend_from_slice(&jpeg_data[2..4 + length]);
+            i = 4 + length;
+        }
+
+        for segment in &segments {
+            result.extend_from_slice(segment);
+        }
+
+        // Copia il resto dell'header saltando eventuali vecchi profili ICC
+        while i + 4 <= jpeg_data.len() {
+            if jpeg_data[i] != 0xFF {
+                return Err("Invalid JPEG structure".to_string());
+            }
+
+            let marker = jpeg_data[i + 1];
+
+            // Dall'inizio della scansione in poi copia tutto così com'è
+            if marker == 0xDA {
+                break;
+            }
+
+            let length = u16::from_be_bytes([jpeg_data[i + 2], jpeg_data[i + 3]]) as usize;
+            // La lunghezza include i suoi 2 byte
+            if length < 2 || i + 2 + length > jpeg_data.len() {
+                return Err("Invalid segment length".to_string());
+            }
+
+            let is_icc =
+                marker == 0xE2 && jpeg_data[i + 4..i + 2 + length].starts_with(JPEG_ICC_SIGNATURE);
+            if !is_icc {
+                result.extend_from_slice(&jpeg_data[i..i + 2 + length]);
+            }
+            i += 2 + length;
+        }
+
+        result.extend_from_slice(&jpeg_data[i..]);
+        Ok(result)
+    }
+
+    /// Costruisce i segmenti APP2 (marker incluso) per il profilo ICC
+    fn jpeg_icc_segments(icc_profile: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+        if icc_profile.is_empty() {
+            return Err("Empty ICC profile".to_string());
+        }
+
+        let chunks: Vec<&[u8]> = icc_profile.chunks(JPEG_ICC_CHUNK_SIZE).collect();
+        if chunks.len() > u8::MAX as usize {
+            return Err(format!(
+                "ICC profile too large for JPEG: {} bytes",
+                icc_profile.len()
+            ));
+        }
+
+        let total = chunks.len() as u8;
+        Ok(chunks
+            .iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                let length = (2 + JPEG_ICC_SIGNATURE.len() + 2 + chunk.len()) as u16;
+                let mut segment = Vec::with_capacity(length as usize + 2);
+                segment.extend_from_slice(&[0xFF, 0xE2]);
+                segment.extend_from_slice(&length.to_be_bytes());
+                segment.extend_from_slice(JPEG_ICC_SIGNATURE);
+                segment.push(index as u8 + 1);
+                segment.push(total);
+                segment.extend_from_slice(chunk);
+                segment
+            })
+            .collect())
+    }
+
+    /// Inserisce il profilo ICC in un WebP come chunk "ICCP".
+    /// I file VP8/VP8L semplici vengono convertiti nel formato esteso VP8X.
+    pub fn embed_in_webp(webp_data: &[u8], icc_profile: &[u8]) -> Result<Vec<u8>, String> {
+        if icc_profile.is_empty() {
+            return Err("Empty ICC profile".to_string());
+        }
+
+        Self::embed_metadata_in_webp(
+            webp_data,
+            &WebpMetadata {
+                icc: Some(icc_profile),
+                ..WebpMetadata::default()
+            },
+        )
+    }
+
+    /// Scrive i chunk ICCP, EXIF e "XMP " convertendo il file nel formato esteso VP8X.
+    /// I chunk di metadati già presenti e non sostituiti vengono mantenuti.
+    pub fn embed_metadata_in_webp(
+        webp_data: &[u8],
+        metadata: &WebpMetadata,
+    ) -> Result<Vec<u8>, String> {
+        let chunks = parse_webp_chunks(webp_data)?;
+
+        let mut vp8x = match chunks.iter().find(|(fourcc, _)| fourcc == b"VP8X") {
+            Some((_, data)) if data.len() >= 10 => data[..10].to_vec(),
+            Some(_) => return Err("Invalid VP8X chunk".to_string()),
+            None => Self::vp8x_from_bitstream(&chunks)?,
+        };
+
+        let existing = |name: &[u8; 4]| {
+            chunks
+                .iter()
+                .find(|(fourcc, _)| fourcc == name)
+                .map(|(_, data)| *data)
+        };
+        let icc = metadata.icc.or_else(|| existing(b"ICCP"));
+        let exif = metadata.exif.or_else(|| existing(b"EXIF"));
+        let xmp = metadata.xmp.or_else(|| existing(b"XMP "));
+
+        // I flag devono corrispondere esattamente ai chunk presenti
+        vp8x[0] &= !(VP8X_FLAG_ICC | VP8X_FLAG_EXIF | VP8X_FLAG_XMP);
+        let mut output: Vec<Chunk> = vec![(*b"VP8X", &[])];
+        if let Some(icc) = icc {
+            vp8x[0] |= VP8X_FLAG_ICC;
+            output.push((*b"ICCP", icc));
+        }
+
+        // Ordine richiesto dalla specifica: VP8X, ICCP, dati immagine, EXIF, XMP
+        output.extend(
+            chunks
+                .iter()
+                .copied()
+                .filter(|(fourcc, _)| !matches!(fourcc, b"VP8X" | b"ICCP" | b"EXIF" | b"XMP ")),
+        );
+        if let Some(exif) = exif {
+            vp8x[0] |= VP8X_FLAG_EXIF;
+            output.push((*b"EXIF", exif));
+        }
+        if let Some(xmp) = xmp {
+            vp8x[0] |= VP8X_FLAG_XMP;
+            output.push((*b"XMP ", xmp));
+        }
+        output[0].1 = &vp8x;
+
+        Ok(write_webp_chunks(&output))
+    }
+
+    /// Crea l'header VP8X leggendo le dimensioni dal bitstream VP8 o VP8L
+    fn vp8x_from_bitstream(chunks: &[Chunk]) -> Result<Vec<u8>, String> {
+        let (width, height, has_alpha) = chunks
+            .iter()
+            .find_map(|(fourcc, data)| match fourcc {
+                b"VP8 " => vp8_dimensions(data).map(|(w, h)| (w, h, false)),
+                b"VP8L" => vp8l_dimensions(data),
+                _ => None,
+            })
+            .ok_or_else(|| "WebP bitstream not found".to_string())?;
+        // Il VP8X memorizza dimensione - 1: un header a zero non è valido
+        if width == 0 || height == 0 {
+            return Err(format!("Invalid WebP dimensions {}x{}", width, height));
+        }
+
+        let mut vp8x = vec![0u8; 10];
+        if has_alpha {
+            vp8x[0] |= VP8X_FLAG_ALPHA;
+        }
+        vp8x[4..7].copy_from_slice(&(width - 1).to_le_bytes()[..3]);
+        vp8x[7..10].copy_from_slice(&(height - 1).to_le_bytes()[..3]);
+
+        Ok(vp8x)
+    }
+
+    /// Marca un PNG come sRGB con il chunk compatto `sRGB` (1 byte) al posto del profilo completo.
+    /// Eventuali chunk `iCCP`/`sRGB` esistenti vengono rimossi.
+    pub fn embed_srgb_in_png(png_data: &[u8], intent: RenderingIntent) -> Result<Vec<u8>, String> {
+        let intent_byte = match intent {
+            RenderingIntent::Perceptual => 0,
+            RenderingIntent::RelativeColorimetric => 1,
+            RenderingIntent::Saturation => 2,
+            RenderingIntent::AbsoluteColorimetric => 3,
+        };
+
+        let chunks = parse_png_chunks(png_data)?;
+        if chunks.first().map(|(name, _)| name) != Some(b"IHDR") {
+            return Err("PNG is missing IHDR".to_string());
+        }
+
+        let mut result = Vec::with_capacity(png_data.len() + 13);
+        result.extend_from_slice(PNG_SIGNATURE);
+        for (index, (name, data)) in chunks.iter().enumerate() {
+            if name == b"iCCP" || name == b"sRGB" {
+                continue;
+            }
+            write_png_chunk(&mut result, name, data);
+            // Il chunk sRGB va prima di PLTE e IDAT: subito dopo IHDR
+            if index == 0 {
+                write_png_chunk(&mut result, b"sRGB", &[intent_byte]);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Inserisce l'EXIF (chunk `eXIf`, blocco TIFF), chunk testuali e XMP prima del primo IDAT.
+    /// Il testo ASCII va in `tEXt`, quello con altri caratteri in `iTXt` (UTF-8);
+    /// l'XMP va nell'`iTXt` "XML:com.adobe.xmp". Chunk esistenti con la stessa
+    /// keyword vengono sostituiti.
+    pub fn embed_metadata_in_png(
+        png_data: &[u8],
+        exif: Option<&[u8]>,
+        text: &[(&str, &str)],
+        xmp: Option<&[u8]>,
+    ) -> Result<Vec<u8>, String> {
+        let chunks = parse_png_chunks(png_data)?;
+        if chunks.first().map(|(name, _)| name) != Some(b"IHDR") {
+            return Err("PNG is missing IHDR".to_string());
+        }
+        if !chunks.iter().any(|(name, _)| name == b"IDAT") {
+            return Err("PNG is missing IDAT".to_string());
+        }
+
+        let replaced = |name: &[u8; 4], data: &[u8]| match name {
+            b"eXIf" => exif.is_some(),
+            b"tEXt" | b"iTXt" => {
+                let keyword = data.split(|&byte| byte == 0).next().unwrap_or_default();
+                (xmp.is_some() && keyword == XMP_PNG_KEYWORD.as_bytes())
+                    || text.iter().any(|(key, _)| key.as_bytes() == keyword)
+            }
+            _ => false,
+        };
+
+        let extra: usize = exif.map_or(0, <[u8]>::len)
+            + xmp.map_or(0, |xmp| xmp.len() + 29)
+            + text
+                .iter()
+                .map(|(key, value)| key.len() + value.len() + 17)
+                .sum::<usize>();
+        let mut result = Vec::with_capacity(png_data.len() + extra + 12);
+        result.extend_from_slice(PNG_SIGNATURE);
+
+        let mut inserted = false;
+        for (name, data) in &chunks {
+            if replaced(name, data) {
+                continue;
+            }
+            if name == b"IDAT" && !inserted {
+                if let Some(exif) = exif {
+                    write_png_chunk(&mut result, b"eXIf", exif);
+                }
+                for (key, value) in text {
+                    let (chunk_name, chunk_data) = png_text_chunk(key, value);
+                    write_png_chunk(&mut result, chunk_name, &chunk_data);
+                }
+                if let Some(xmp) = xmp {
+                    let mut chunk_data = XMP_PNG_KEYWORD.as_bytes().to_vec();
+                    chunk_data.extend_from_slice(&[0, 0, 0, 0, 0]);
+                    chunk_data.extend_from_slice(xmp);
+                    write_png_chunk(&mut result, b"iTXt", &chunk_data);
+                }
+                inserted = true;
+            }
+            write_png_chunk(&mut result, name, data);
+        }
+
+        Ok(result)
+    }
+}
+
+/// Chunk testuale PNG: `tEXt` per l'ASCII, `iTXt` non compresso per il resto
+fn png_text_chunk(keyword: &str, value: &str) -> (&'static [u8; 4], Vec<u8>) {
+    let mut data = keyword.as_bytes().to_vec();
+    data.push(0);
+    if value.is_ascii() {
+        data.extend_from_slice(value.as_bytes());
+        (b"tEXt", data)
+    } else {
+        // Flag e metodo di compressione a 0, lingua e keyword tradotta vuote
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        data.extend_from_slice(value.as_bytes());
+        (b"iTXt", data)
+    }
+}
+
+/// Divide un file PNG nei suoi chunk (nome, dati), verificando la struttura
+pub(crate) fn parse_png_chunks(data: &[u8]) -> Result<Vec<Chunk<'_>>, String> {
+    if !data.starts_with(PNG_SIGNATURE) {
+        return Err("Invalid PNG structure".to_string());
+    }
+
+    let mut chunks = Vec::new();
+    let mut offset = PNG_SIGNATURE.len();
+    while offset + 12 <= data.len() {
+        let length = u32::from_be_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ]) as usize;
+        let name = [
+            data[offset + 4],
+            data[offset + 5],
+            data[offset + 6],
+            data[offset + 7],
+        ];
+
+        let start = offset + 8;
+        if start + length + 4 > data.len() {
+            return Err("Truncated PNG chunk".to_string());
+        }
+
+        chunks.push((name, &data[start..start + length]));
+        offset = start + length + 4; // Dati + CRC
+
+        if &name == b"IEND" {
+            break;
+        }
+    }
+
+    Ok(chunks)
+}
+
+/// Scrive un chunk PNG (lunghezza, nome, dati, CRC)
+fn write_png_chunk(output: &mut Vec<u8>, name: &[u8; 4], data: &[u8]) {
+    output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    output.extend_from_slice(name);
+    output.extend_from_slice(data);
+    let crc = crc32(name.iter().chain(data.iter()));
+    output.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// CRC-32 (ISO 3309) usato dai chunk PNG
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Divide un file RIFF/WebP nei suoi chunk (FourCC, payload)
+pub(crate) fn parse_webp_chunks(data: &[u8]) -> Result<Vec<Chunk<'_>>, String> {
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+        return Err("Invalid WebP structure".to_string());
+    }
+
+    let riff_end =
+        (u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize + 8).min(data.len());
+
+    let mut chunks = Vec::new();
+    let mut offset = 12;
+    while offset + 8 <= riff_end {
+        let fourcc = [
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ];
+        let size = u32::from_le_bytes([
+            data[offset + 4],
+            data[offset + 5],
+            data[offset + 6],
+            data[offset + 7],
+        ]) as usize;
+
+        let start = offset + 8;
+        if start + size > riff_end {
+            return Err("Truncated WebP chunk".to_string());
+        }
+
+        chunks.push((fourcc, &data[start..start + size]));
+        // I chunk di lunghezza dispari hanno un byte di padding
+        offset = start + size + (size & 1);
+    }
+
+    Ok(chunks)
+}
+
+/// Riassembla i chunk in un file RIFF/WebP
+fn write_webp_chunks(chunks: &[Chunk]) -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(b"WEBP");
+
+    for (fourcc, data) in chunks {
+        body.extend_from_slice(fourcc);
+        body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        body.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            body.push(0);
+        }
+    }
+
+    let mut result = Vec::with_capacity(body.len() + 8);
+    result.extend_from_slice(b"RIFF");
+    result.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    result.extend_from_slice(&body);
+    result
+}
+
+/// Dimensioni dal frame header VP8 (lossy)
+fn vp8_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if data.len() < 10 || data[3..6] != [0x9D, 0x01, 0x2A] {
+        return None;
+    }
+
+    let width = u16::from_le_bytes([data[6], data[7]]) & 0x3FFF;
+    let height = u16::from_le_bytes([data[8], data[9]]) & 0x3FFF;
+    Some((width as u32, height as u32))
+}
+
+/// Dimensioni e flag alpha dall'header VP8L (lossless)
+fn vp8l_dimensions(data: &[u8]) -> Option<(u32, u32, bool)> {
+    if data.len() < 5 || data[0] != 0x2F {
+        return None;
+    }
+
+    let bits = u32::from_le_bytes([data[1], data[2], data[3], data[4]]);
+    let width = (bits & 0x3FFF) + 1;
+    let height = ((bits >> 14) & 0x3FFF) + 1;
+    let has_alpha = (bits >> 28) & 1 == 1;
+    Some((width, height, has_alpha))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::color_profile;
+
+    fn sample_icc(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn sample_jpeg() -> Vec<u8> {
+        let img = image::RgbImage::from_pixel(8, 8, image::Rgb([10, 120, 200]));
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        img.write_to(&mut buffer, image::ImageFormat::Jpeg).unwrap();
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn test_embed_small_profile_in_jpeg() {
+        let icc = sample_icc(3_000);
+        let jpeg = IccWriter::embed_in_jpeg(&sample_jpeg(), &icc).unwrap();
+
+        assert_eq!(color_profile::extract_icc_profile(&jpeg), Some(icc));
+        assert!(image::load_from_memory(&jpeg).is_ok());
+    }
+
+    #[test]
+    fn test_embed_large_profile_splits_segments() {
+        let icc = sample_icc(150_000);
+        let jpeg = IccWriter::embed_in_jpeg(&sample_jpeg(), &icc).unwrap();
+
+        let segments = jpeg
+            .windows(JPEG_ICC_SIGNATURE.len())
+            .filter(|w| w == JPEG_ICC_SIGNATURE)
+            .count();
+        assert_eq!(segments, 3);
+        assert_eq!(color_profile::extract_icc_profile(&jpeg), Some(icc.clone()));
+
+        // Reinserire un profilo sostituisce quello precedente
+        let smaller = sample_icc(500);
+        let jpeg = IccWriter::embed_in_jpeg(&jpeg, &smaller).unwrap();
+        assert_eq!(color_profile::extract_icc_profile(&jpeg), Some(smaller));
+    }
+
+    #[test]
+    fn test_malformed_input_rejected() {
+        let icc = sample_icc(100);
+
+        // Segmenti con lunghezza < 2 (la lunghezza include i suoi 2 byte)
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xE1, 0x00, 0x01, 0xFF, 0xDA, 0x00, 0x02];
+        assert!(IccWriter::embed_in_jpeg(&jpeg, &icc).is_err());
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x00, 0xFF, 0xDA];
+        assert!(IccWriter::embed_in_jpeg(&jpeg, &icc).is_err());
+
+        // Header VP8 con larghezza zero
+        let vp8 = [0, 0, 0, 0x9D, 0x01, 0x2A, 0, 0, 3, 0];
+        assert!(IccWriter::vp8x_from_bitstream(&[(*b"VP8 ", &vp8[..])]).is_err());
+    }
+
+    #[test]
+    fn test_srgb_chunk_replaces_iccp() {
+        // PNG con profilo completo
+        let mut png_data = Vec::new();
+        let mut info = png::Info::with_size(4, 4);
+        info.icc_profile = Some(sample_icc(400).into());
+        info.color_type = png::ColorType::Rgb;
+        let mut writer = png::Encoder::with_info(&mut png_data, info)
+            .unwrap()
+            .write_header()
+            .unwrap();
+        writer.write_image_data(&[90u8; 4 * 4 * 3]).unwrap();
+        writer.finish().unwrap();
+
+        let tagged = IccWriter::embed_srgb_in_png(&png_data, RenderingIntent::Perceptual).unwrap();
+        let names: Vec<[u8; 4]> = parse_png_chunks(&tagged)
+            .unwrap()
+            .iter()
+            .map(|(name, _)| *name)
+            .collect();
+        assert_eq!(&names[..2], &[*b"IHDR", *b"sRGB"]);
+        assert!(!names.contains(b"iCCP"));
+
+        // Il decoder verifica i CRC e legge l'intento
+        let decoder = png::Decoder::new(std::io::Cursor::new(tagged));
+        let reader = decoder.read_info().unwrap();
+        assert_eq!(
+            reader.info().srgb,
+            Some(png::SrgbRenderingIntent::Perceptual)
+        );
+        assert!(reader.info().icc_profile.is_none());
+    }
+
+    #[test]
+    fn test_embed_profile_in_webp() {
+        let rgba = image::RgbaImage::from_pixel(5, 3, image::Rgba([200, 100, 50, 128]));
+        let encoded = webp::Encoder::from_rgba(&rgba, 5, 3)
+            .encode_lossless()
+            .to_vec();
+        let icc = sample_icc(301);
+
+        let webp = IccWriter::embed_in_webp(&encoded, &icc).unwrap();
+        let chunks = parse_webp_chunks(&webp).unwrap();
+
+        assert_eq!(&chunks[0].0, b"VP8X");
+        let vp8x = chunks[0].1;
+        assert_eq!(vp8x[0] & VP8X_FLAG_ICC, VP8X_FLAG_ICC);
+        assert_eq!(vp8x[0] & VP8X_FLAG_ALPHA, VP8X_FLAG_ALPHA);
+        assert_eq!(u32::from_le_bytes([vp8x[4], vp8x[5], vp8x[6], 0]) + 1, 5);
+        assert_eq!(u32::from_le_bytes([vp8x[7], vp8x[8], vp8x[9], 0]) + 1, 3);
+        assert_eq!(chunks[1], (*b"ICCP", icc.as_slice()));
+
+        // Il file resta decodificabile
+        let decoded = image::load_from_memory(&webp).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (5, 3));
+    }
+
+    #[test]
+    fn test_embed_metadata_in_webp() {
+        let rgb = image::RgbImage::from_pixel(7, 4, image::Rgb([10, 20, 30]));
+        let encoded = webp::Encoder::from_rgb(&rgb, 7, 4).encode(80.0).to_vec();
+        let icc = sample_icc(128);
+        let with_icc = IccWriter::embed_in_webp(&encoded, &icc).unwrap();
+
+        // EXIF e XMP di lunghezza dispari: servono i byte di padding
+        let exif = b"II*\0\x08\0\0\0\0\0\0\0\0".to_vec();
+        let xmp = b"<x:xmpmeta/>\n".to_vec();
+        let webp = IccWriter::embed_metadata_in_webp(
+            &with_icc,
+            &WebpMetadata {
+                exif: Some(&exif),
+                xmp: Some(&xmp),
+                ..WebpMetadata::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            u32::from_le_bytes([webp[4], webp[5], webp[6], webp[7]]) as usize + 8,
+            webp.len()
+        );
+        let chunks = parse_webp_chunks(&webp).unwrap();
+        let names: Vec<&[u8; 4]> = chunks.iter().map(|(fourcc, _)| fourcc).collect();
+        assert_eq!(names, vec![b"VP8X", b"ICCP", b"VP8 ", b"EXIF", b"XMP "]);
+
+        let flags = chunks[0].1[0];
+        assert_eq!(flags & VP8X_FLAG_ICC, VP8X_FLAG_ICC);
+        assert_eq!(flags & VP8X_FLAG_EXIF, VP8X_FLAG_EXIF);
+        assert_eq!(flags & VP8X_FLAG_XMP, VP8X_FLAG_XMP);
+        assert_eq!(flags & VP8X_FLAG_ALPHA, 0);
+        assert_eq!(chunks[1].1, icc.as_slice());
+        assert_eq!(chunks[3].1, exif.as_slice());
+        assert_eq!(chunks[4].1, xmp.as_slice());
+
+        let decoded = image::load_from_memory(&webp).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (7, 4));
+    }
+
+    #[test]
+    fn test_embed_metadata_in_png() {
+        let mut encoded = Vec::new();
+        image::DynamicImage::new_rgb8(3, 2)
+            .write_to(
+                &mut std::io::Cursor::new(&mut encoded),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+        let exif = b"MM\0*\0\0\0\x08\0\0\0\0\0\0".to_vec();
+
+        let png = IccWriter::embed_metadata_in_png(
+            &encoded,
+            Some(&exif),
+            &[("Author", "Jane Doe"), ("Copyright", "© 2024 Jane Doe")],
+            None,
+        )
+        .unwrap();
+
+        let chunks = parse_png_chunks(&png).unwrap();
+        let names: Vec<&[u8; 4]> = chunks.iter().map(|(name, _)| name).collect();
+        let idat = names.iter().position(|name| *name == b"IDAT").unwrap();
+        let exif_index = names.iter().position(|name| *name == b"eXIf").unwrap();
+        assert!(exif_index < idat);
+        assert_eq!(chunks[exif_index].1, exif.as_slice());
+        assert!(chunks.contains(&(*b"tEXt", b"Author\0Jane Doe".as_slice())));
+
+        // Il decoder png verifica i CRC e legge i testi
+        let decoder = png::Decoder::new(std::io::Cursor::new(&png));
+        let reader = decoder.read_info().unwrap();
+        let info = reader.info();
+        assert_eq!(info.uncompressed_latin1_text[0].keyword, "Author");
+        assert_eq!(info.utf8_text[0].keyword, "Copyright");
+        assert_eq!(info.utf8_text[0].get_text().unwrap(), "© 2024 Jane Doe");
+
+        // Una seconda scrittura sostituisce i chunk invece di duplicarli
+        let again =
+            IccWriter::embed_metadata_in_png(&png, Some(&exif), &[("Author", "John")], None)
+                .unwrap();
+        let chunks = parse_png_chunks(&again).unwrap();
+        assert_eq!(chunks.iter().filter(|(name, _)| name == b"eXIf").count(), 1);
+        assert_eq!(chunks.iter().filter(|(name, _)| name == b"tEXt").count(), 1);
+    }
+}
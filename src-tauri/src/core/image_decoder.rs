@@ -1,5 +1,8 @@
 // src-tauri/src/core/image_decoder.rs
 
+use crate::core::color_management::{ColorManager, RenderingIntent};
+use crate::core::color_profile;
+use image::metadata::Orientation;
 use image::{DynamicImage, ImageFormat};
 use std::fs;
 use std::path::Path;
@@ -37,16 +40,39 @@ pub fn decode_image(path: &Path, file_size: u64) -> Result<DynamicImage, String>
     }
 }
 
-/// Decodifica JPEG usando TurboJPEG
-fn decode_jpeg_turbojpeg(path: &Path) -> Result<DynamicImage, String> {
-    let jpeg_data = fs::read(path).map_err(|e| format!("Failed to read JPEG: {}", e))?;
+/// Immagine CMYK a 8 bit per canale (0 = nessun inchiostro, 255 = copertura piena)
+pub struct CmykImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// Pixel di un JPEG decodificati nello spazio colore nativo
+pub enum JpegPixels {
+    /// YCbCr/RGB (RGB8) o scala di grigi (Luma8)
+    Image(DynamicImage),
+    /// CMYK o YCCK, da convertire con un profilo CMYK
+    Cmyk(CmykImage),
+}
+
+/// Decodifica un JPEG con TurboJPEG mantenendo lo spazio colore nativo:
+/// RGB per YCbCr/RGB, Luma per la scala di grigi, CMYK per CMYK/YCCK
+pub fn decode_jpeg_native(jpeg_data: &[u8]) -> Result<JpegPixels, String> {
+    let header = turbojpeg::read_header(jpeg_data)
+        .map_err(|e| format!("TurboJPEG header read failed: {}", e))?;
 
-    let tj_image = turbojpeg::decompress(&jpeg_data, turbojpeg::PixelFormat::RGB)
+    let format = match header.colorspace {
+        turbojpeg::Colorspace::Gray => turbojpeg::PixelFormat::GRAY,
+        turbojpeg::Colorspace::CMYK | turbojpeg::Colorspace::YCCK => turbojpeg::PixelFormat::CMYK,
+        _ => turbojpeg::PixelFormat::RGB,
+    };
+
+    let tj_image = turbojpeg::decompress(jpeg_data, format)
         .map_err(|e| format!("TurboJPEG decode failed: {}", e))?;
 
     let width = tj_image.width as u32;
     let height = tj_image.height as u32;
-    let expected_len = (width * height * 3) as usize;
+    let expected_len = width as usize * height as usize * format.size();
 
     if tj_image.pixels.len() != expected_len {
         return Err(format!(
@@ -56,10 +82,69 @@ fn decode_jpeg_turbojpeg(path: &Path) -> Result<DynamicImage, String> {
         ));
     }
 
-    let image_buffer = image::RgbImage::from_raw(width, height, tj_image.pixels)
-        .ok_or_else(|| "Failed to create image buffer".to_string())?;
+    let buffer_error = || "Failed to create image buffer".to_string();
+    match format {
+        turbojpeg::PixelFormat::GRAY => {
+            let image_buffer = image::GrayImage::from_raw(width, height, tj_image.pixels)
+                .ok_or_else(buffer_error)?;
+            Ok(JpegPixels::Image(DynamicImage::ImageLuma8(image_buffer)))
+        }
+        turbojpeg::PixelFormat::CMYK => {
+            let mut pixels = tj_image.pixels;
+
+            // Photoshop salva il CMYK invertito (0 = inchiostro pieno) e lo segnala con APP14 "Adobe"
+            if has_adobe_marker(jpeg_data) {
+                pixels.iter_mut().for_each(|value| *value = 255 - *value);
+            }
 
-    Ok(DynamicImage::ImageRgb8(image_buffer))
+            Ok(JpegPixels::Cmyk(CmykImage {
+                width,
+                height,
+                pixels,
+            }))
+        }
+        _ => {
+            let image_buffer = image::RgbImage::from_raw(width, height, tj_image.pixels)
+                .ok_or_else(buffer_error)?;
+            Ok(JpegPixels::Image(DynamicImage::ImageRgb8(image_buffer)))
+        }
+    }
+}
+
+/// Cerca il segmento APP14 "Adobe" negli header del JPEG
+fn has_adobe_marker(jpeg_data: &[u8]) -> bool {
+    let mut offset = 2; // Salta SOI
+    while offset + 4 <= jpeg_data.len() && jpeg_data[offset] == 0xFF {
+        let marker = jpeg_data[offset + 1];
+        if marker == 0xDA {
+            break;
+        }
+
+        let length = u16::from_be_bytes([jpeg_data[offset + 2], jpeg_data[offset + 3]]) as usize;
+        if marker == 0xEE && jpeg_data[offset + 4..].starts_with(b"Adobe") {
+            return true;
+        }
+        offset += 2 + length;
+    }
+
+    false
+}
+
+/// Decodifica JPEG usando TurboJPEG (CMYK convertito in sRGB)
+fn decode_jpeg_turbojpeg(path: &Path) -> Result<DynamicImage, String> {
+    let jpeg_data = fs::read(path).map_err(|e| format!("Failed to read JPEG: {}", e))?;
+
+    match decode_jpeg_native(&jpeg_data)? {
+        JpegPixels::Image(img) => Ok(img),
+        JpegPixels::Cmyk(cmyk) => {
+            let icc_data = color_profile::extract_icc_profile(&jpeg_data);
+            ColorManager::new()?.convert_cmyk(
+                &cmyk,
+                icc_data.as_deref(),
+                RenderingIntent::Perceptual.into(),
+            )
+        }
+    }
 }
 
 /// Decodifica standard per PNG
@@ -67,6 +152,19 @@ fn decode_standard(path: &Path) -> Result<DynamicImage, String> {
     image::open(path).map_err(|e| format!("Failed to decode image: {}", e))
 }
 
+/// Applica ai pixel l'orientamento EXIF (1-8): rotazioni e specchiature.
+/// Valori fuori range lasciano l'immagine invariata.
+pub fn apply_exif_orientation(mut img: DynamicImage, orientation: u16) -> DynamicImage {
+    if let Some(orientation) = u8::try_from(orientation)
+        .ok()
+        .and_then(Orientation::from_exif)
+    {
+        img.apply_orientation(orientation);
+    }
+
+    img
+}
+
 /// Verifica se un file è supportato per l'elaborazione
 pub fn is_supported_format(path: &Path) -> bool {
     if !path.is_file() {
@@ -108,4 +206,74 @@ mod tests {
         assert!(!is_supported_format(Path::new("test.tif")));
         assert!(!is_supported_format(Path::new("test.bmp")));
     }
+
+    /// JPEG minimale con i soli segmenti indicati (nessun dato immagine)
+    fn jpeg_with_segments(segments: &[(u8, &[u8])]) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        for (marker, payload) in segments {
+            data.extend_from_slice(&[0xFF, *marker]);
+            data.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+            data.extend_from_slice(payload);
+        }
+        data.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02]);
+        data
+    }
+
+    #[test]
+    fn test_adobe_marker_detection() {
+        let adobe = jpeg_with_segments(&[
+            (0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0"),
+            (0xEE, b"Adobe\0\x64\0\0\0\0\x02"),
+        ]);
+        assert!(has_adobe_marker(&adobe));
+
+        let plain = jpeg_with_segments(&[(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0")]);
+        assert!(!has_adobe_marker(&plain));
+    }
+
+    #[test]
+    fn test_decode_grayscale_jpeg_natively() {
+        let gray = image::GrayImage::from_pixel(8, 8, image::Luma([128]));
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        DynamicImage::ImageLuma8(gray)
+            .write_to(&mut buffer, ImageFormat::Jpeg)
+            .unwrap();
+
+        match decode_jpeg_native(buffer.get_ref()).unwrap() {
+            JpegPixels::Image(img) => assert_eq!(img.color(), image::ColorType::L8),
+            JpegPixels::Cmyk(_) => panic!("grayscale decoded as CMYK"),
+        }
+    }
+
+    #[test]
+    fn test_exif_orientation() {
+        // Pixel rosso in alto a sinistra su un'immagine 3x2
+        let mut rgb = image::RgbImage::new(3, 2);
+        rgb.put_pixel(0, 0, image::Rgb([255, 0, 0]));
+        let img = DynamicImage::ImageRgb8(rgb);
+
+        // 6 = ruota di 90° in senso orario: il pixel finisce in alto a destra
+        let rotated = apply_exif_orientation(img.clone(), 6).to_rgb8();
+        assert_eq!(rotated.dimensions(), (2, 3));
+        assert_eq!(rotated.get_pixel(1, 0), &image::Rgb([255, 0, 0]));
+
+        // 3 = ruota di 180°
+        let flipped = apply_exif_orientation(img.clone(), 3).to_rgb8();
+        assert_eq!(flipped.get_pixel(2, 1), &image::Rgb([255, 0, 0]));
+
+        // 5 = trasposizione: scambia righe e colonne
+        let transposed = apply_exif_orientation(img.clone(), 5).to_rgb8();
+        assert_eq!(transposed.dimensions(), (2, 3));
+        assert_eq!(transposed.get_pixel(0, 0), &image::Rgb([255, 0, 0]));
+
+        // Valori normali o non validi non modificano nulla
+        assert_eq!(
+            apply_exif_orientation(img.clone(), 1).to_rgb8(),
+            img.to_rgb8()
+        );
+        assert_eq!(
+            apply_exif_orientation(img.clone(), 9).to_rgb8(),
+            img.to_rgb8()
+        );
+    }
 }
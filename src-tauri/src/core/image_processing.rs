@@ -1,17 +1,24 @@
 // src-tauri/src/core/image_processing.rs
 
-use crate::core::color_management::{ColorManager, RenderingIntent};
+use crate::core::adjustments;
+use crate::core::color_management::{ColorManager, ConversionSettings, RenderingIntent};
 use crate::core::color_profile::{self};
 use crate::core::exif_handler::ExifHandler;
-use crate::core::image_decoder;
+use crate::core::icc_writer::IccWriter;
+use crate::core::image_decoder::{self, CmykImage, JpegPixels};
+use crate::core::metadata_policy::MetadataPolicy;
 use crate::core::models::{
     ImageInfo, MetadataProgressPayload, OptimizationResult, ProgressPayload,
 };
-use crate::core::settings::{self, OptimizationOptions};
+use crate::core::settings::{self, IccEmbedding, OptimizationOptions, OptionOverrides};
 use crate::core::task::ImageTask;
 use crate::core::thumbnail::ThumbnailCache;
+use crate::core::trim;
+use crate::core::watermark::Watermark;
 use image::{DynamicImage, ImageFormat};
 use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fs;
 use std::panic::{self, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
@@ -24,6 +31,7 @@ use walkdir::WalkDir;
 const MAX_FILE_SIZE: u64 = 1_000_000_000; // 1GB
 const MAX_IMAGE_DIMENSION: u32 = 16384; // 16K max per lato
 const MIN_IMAGE_DIMENSION: u32 = 1; // Minimo 1px
+const FLATTEN_WARNING_THRESHOLD: f64 = 0.01; // Avvisa oltre l'1% di pixel trasparenti
 
 // --- Comandi Tauri ---
 
@@ -226,8 +234,9 @@ fn extract_image_info(
         .map_err(|e| e.to_string())?
         .as_secs();
 
-    // Rileva il profilo colore
-    let color_profile = color_profile::detect_color_profile(path);
+    // Rileva il profilo colore e da dove proviene l'informazione
+    let (color_profile, color_profile_source) =
+        color_profile::detect_color_profile_with_source(path);
     let needs_conversion = !color_profile.is_web_safe();
 
     // Genera o recupera thumbnail dalla cache
@@ -257,26 +266,36 @@ fn extract_image_info(
         None
     };
 
+    // Metadati XMP (autore, diritti, rating, keyword)
+    let xmp_data = crate::core::xmp::extract_xmp(path).filter(|data| !data.is_empty());
+    // Dataset IPTC-IIM (didascalie, byline, credit) dei JPEG
+    let iptc_data = crate::core::iptc::extract_iptc(path);
+
     Ok(ImageInfo {
         path: p_str,
         size_kb: file_size as f64 / 1024.0,
         mimetype,
         last_modified,
         color_profile,
+        color_profile_source,
         needs_conversion,
         preview_path: None,
         thumbnail_path,
         exif_data,
         has_exif,
+        xmp_data,
+        iptc_data,
     })
 }
 
 /// Comando asincrono che orchestra l'ottimizzazione delle immagini.
+/// `overrides` permette di cambiare alcune opzioni per singoli file del batch.
 #[tauri::command]
 pub async fn optimize_images(
     app_handle: tauri::AppHandle,
     paths: Vec<String>,
     options: OptimizationOptions,
+    overrides: Option<HashMap<String, OptionOverrides>>,
 ) -> Result<(), String> {
     // Validazione input
     if paths.is_empty() {
@@ -288,28 +307,47 @@ pub async fn optimize_images(
     }
 
     let handle = tauri::async_runtime::spawn_blocking(move || {
-        let processor = ImageProcessor::new(paths, app_handle, options);
+        let processor =
+            ImageProcessor::new(paths, app_handle, options, overrides.unwrap_or_default())?;
         processor.run_parallel();
+        Ok(())
     });
 
     handle
         .await
-        .map_err(|e| format!("Processing task failed: {}", e))?;
-
-    Ok(())
+        .map_err(|e| format!("Processing task failed: {}", e))?
 }
 
 // --- Struttura Principale per la Logica di Elaborazione ---
 
+/// Immagine decodificata: pixel ancora nel profilo sorgente o già convertiti (CMYK)
+enum DecodedImage {
+    Source(DynamicImage),
+    Converted(DynamicImage, Option<Vec<u8>>),
+}
+
+fn is_valid_dimension(width: u32, height: u32) -> bool {
+    (MIN_IMAGE_DIMENSION..=MAX_IMAGE_DIMENSION).contains(&width)
+        && (MIN_IMAGE_DIMENSION..=MAX_IMAGE_DIMENSION).contains(&height)
+}
+
 struct ImageProcessor {
     tasks: Vec<ImageTask>,
     app_handle: AppHandle,
     total_valid_tasks: usize,
     options: OptimizationOptions,
+    watermark: Option<Watermark>,
+    color_manager: Option<ColorManager>,
+    overrides: HashMap<PathBuf, OptionOverrides>,
 }
 
 impl ImageProcessor {
-    fn new(paths: Vec<String>, app_handle: AppHandle, options: OptimizationOptions) -> Self {
+    fn new(
+        paths: Vec<String>,
+        app_handle: AppHandle,
+        options: OptimizationOptions,
+        overrides: HashMap<String, OptionOverrides>,
+    ) -> Result<Self, String> {
         let tasks: Vec<ImageTask> = paths
             .into_iter()
             .map(PathBuf::from)
@@ -321,12 +359,35 @@ impl ImageProcessor {
             .filter(|t| matches!(t, ImageTask::Valid { .. }))
             .count();
 
-        Self {
+        // Il watermark viene preparato una sola volta per tutto il batch:
+        // se non è utilizzabile il batch non parte, invece di uscire senza watermark
+        let watermark = options
+            .watermark
+            .as_ref()
+            .map(Watermark::prepare)
+            .transpose()?;
+
+        // Anche il ColorManager è condiviso: le trasformazioni LCMS2 restano in cache
+        let color_manager = match ColorManager::with_destination(&options.output_color_space) {
+            Ok(color_manager) => Some(color_manager),
+            Err(e) => {
+                eprintln!("⚠ Failed to create ColorManager: {}", e);
+                None
+            }
+        };
+
+        Ok(Self {
             tasks,
             app_handle,
             total_valid_tasks,
             options,
-        }
+            watermark,
+            color_manager,
+            overrides: overrides
+                .into_iter()
+                .map(|(path, overrides)| (PathBuf::from(path), overrides))
+                .collect(),
+        })
     }
 
     fn run_parallel(self) {
@@ -379,7 +440,197 @@ impl ImageProcessor {
         println!("Parallel processing finished.");
     }
 
+    /// Intento di rendering e BPC scelti nelle opzioni
+    fn conversion_settings(options: &OptimizationOptions) -> ConversionSettings {
+        let intent = match &options.color_intent {
+            settings::ColorConversionIntent::Perceptual => RenderingIntent::Perceptual,
+            settings::ColorConversionIntent::RelativeColorimetric => {
+                RenderingIntent::RelativeColorimetric
+            }
+            settings::ColorConversionIntent::Saturation => RenderingIntent::Saturation,
+            settings::ColorConversionIntent::AbsoluteColorimetric => {
+                RenderingIntent::AbsoluteColorimetric
+            }
+        };
+        ConversionSettings::new(intent, options.black_point_compensation)
+    }
+
+    /// Opzioni effettive per un file: quelle del batch con gli eventuali override
+    fn options_for(&self, path: &Path) -> Cow<'_, OptimizationOptions> {
+        match self.overrides.get(path) {
+            Some(overrides) => Cow::Owned(self.options.with_overrides(overrides)),
+            None => Cow::Borrowed(&self.options),
+        }
+    }
+
+    /// Converte un JPEG CMYK/YCCK nello spazio colore di output, usando il profilo
+    /// embedded o quello CMYK di default configurato nelle opzioni.
+    fn convert_cmyk(
+        &self,
+        cmyk: &CmykImage,
+        jpeg_data: &[u8],
+        options: &OptimizationOptions,
+    ) -> Option<(DynamicImage, Option<Vec<u8>>)> {
+        // Senza destinazione valida converte comunque verso sRGB
+        let fallback_manager;
+        let color_manager = match &self.color_manager {
+            Some(color_manager) => color_manager,
+            None => {
+                fallback_manager = ColorManager::new().ok()?;
+                &fallback_manager
+            }
+        };
+
+        let icc_data = color_profile::extract_icc_profile(jpeg_data).or_else(|| {
+            let profile_path = options.default_cmyk_profile.as_ref()?;
+            fs::read(profile_path)
+                .map_err(|e| eprintln!("⚠ Failed to read CMYK profile {}: {}", profile_path, e))
+                .ok()
+        });
+
+        let converted = match color_manager.convert_cmyk(
+            cmyk,
+            icc_data.as_deref(),
+            Self::conversion_settings(options),
+        ) {
+            Ok(converted) => converted,
+            Err(e) => {
+                eprintln!(
+                    "⚠ CMYK conversion failed: {}, using default CMYK profile",
+                    e
+                );
+                color_manager
+                    .convert_cmyk(cmyk, None, Self::conversion_settings(options))
+                    .ok()?
+            }
+        };
+
+        if color_manager.is_srgb_destination() {
+            return Some((converted, None));
+        }
+        Some((converted, color_manager.destination_icc().ok()))
+    }
+
+    /// Converte i pixel nello spazio colore di output.
+    /// Restituisce anche il profilo ICC dei pixel risultanti (None se sono sRGB).
+    /// Se la conversione non riesce, i pixel restano nel profilo sorgente.
+    fn convert_color_space(
+        &self,
+        img: DynamicImage,
+        path: &Path,
+        options: &OptimizationOptions,
+    ) -> (DynamicImage, Option<Vec<u8>>) {
+        // Il file viene letto una sola volta per profilo ICC, chunk PNG e nome
+        let data = std::fs::read(path).unwrap_or_default();
+
+        // Profilo ICC embedded o, per i PNG, equivalente ai chunk gAMA/cHRM
+        // (None se i chunk descrivono sRGB)
+        let source_icc = color_profile::extract_icc_profile(&data).or_else(|| {
+            let chunks = color_profile::extract_png_color_chunks(&data)
+                .filter(|chunks| !chunks.is_srgb_equivalent())?;
+            ColorManager::icc_from_png_chunks(&chunks)
+                .map_err(|e| eprintln!("⚠ {}", e))
+                .ok()
+        });
+
+        let Some(color_manager) = &self.color_manager else {
+            eprintln!("⚠ ColorManager unavailable, skipping conversion");
+            return (img, source_icc);
+        };
+
+        // Senza profilo i pixel sono sRGB; con un profilo identico a quello
+        // di destinazione sono già nello spazio colore di output
+        let in_destination = match &source_icc {
+            Some(icc_data) => color_manager.is_destination_icc(icc_data),
+            None => color_manager.is_srgb_destination(),
+        };
+        if in_destination {
+            return (
+                img,
+                source_icc.filter(|_| !color_manager.is_srgb_destination()),
+            );
+        }
+
+        let (color_profile, _) = color_profile::detect_color_profile_in(&data);
+
+        println!(
+            "Converting color profile from {:?} to {:?} for {}",
+            color_profile,
+            options.output_color_space,
+            path.display()
+        );
+
+        let conversion = Self::conversion_settings(options);
+
+        // Usa il profilo ICC reale; il riconoscimento per nome è solo un fallback
+        let converted = match &source_icc {
+            Some(icc_data) => color_manager
+                .convert_with_embedded_profile(&img, icc_data, conversion)
+                .or_else(|e| {
+                    eprintln!(
+                        "⚠ Embedded ICC conversion failed: {}, falling back to {:?}",
+                        e, color_profile
+                    );
+                    color_manager.convert_to_destination(&img, &color_profile, conversion)
+                }),
+            None => color_manager.convert_to_destination(&img, &color_profile, conversion),
+        };
+
+        match converted {
+            Ok(converted_img) => {
+                println!("✓ Color conversion successful with {:?}", conversion);
+
+                if color_manager.is_srgb_destination() {
+                    return (converted_img, None);
+                }
+
+                match color_manager.destination_icc() {
+                    Ok(icc) => (converted_img, Some(icc)),
+                    Err(e) => {
+                        eprintln!("⚠ Failed to serialize output ICC profile: {}", e);
+                        (converted_img, None)
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("⚠ Color conversion failed: {}, using original", e);
+                (img, source_icc)
+            }
+        }
+    }
+
+    /// Sceglie il profilo ICC da scrivere nel file secondo le opzioni di embedding.
+    /// `pixel_icc` è il profilo dei pixel codificati (None = sRGB).
+    fn icc_to_embed(
+        &self,
+        pixel_icc: Option<Vec<u8>>,
+        options: &OptimizationOptions,
+    ) -> Option<Vec<u8>> {
+        match (&options.icc_embedding, pixel_icc) {
+            (IccEmbedding::Never, _) => None,
+            (_, Some(icc)) => Some(icc),
+            (IccEmbedding::Auto, None) => None,
+            // Per il PNG il tag compatto sostituisce il profilo completo
+            (IccEmbedding::SrgbTag, None)
+                if matches!(options.format, settings::OutputFormat::Png) =>
+            {
+                None
+            }
+            (IccEmbedding::Always | IccEmbedding::SrgbTag, None) => {
+                match ColorManager::new().and_then(|manager| manager.destination_icc()) {
+                    Ok(icc) => Some(icc),
+                    Err(e) => {
+                        eprintln!("⚠ Failed to serialize sRGB profile: {}", e);
+                        None
+                    }
+                }
+            }
+        }
+    }
+
     fn process_single_image(&self, path: &Path, original_size: u64) -> Option<OptimizationResult> {
+        let options = self.options_for(path);
+
         // Validazione path
         if !path.exists() {
             eprintln!("File does not exist: {}", path.display());
@@ -387,9 +638,10 @@ impl ImageProcessor {
         }
 
         let format = ImageFormat::from_path(path).ok()?;
+        let mut warnings: Vec<String> = Vec::new();
 
         // Carica e decodifica immagine
-        let img: DynamicImage = match format {
+        let decoded = match format {
             ImageFormat::Jpeg => {
                 let jpeg_data = fs::read(path).ok()?;
 
@@ -399,35 +651,22 @@ impl ImageProcessor {
                     return None;
                 }
 
-                match turbojpeg::decompress(&jpeg_data, turbojpeg::PixelFormat::RGB) {
-                    Ok(tj_image) => {
-                        let width = tj_image.width as u32;
-                        let height = tj_image.height as u32;
-
+                match image_decoder::decode_jpeg_native(&jpeg_data) {
+                    Ok(JpegPixels::Image(img)) => {
                         // Validazione dimensioni
-                        if width == 0
-                            || height == 0
-                            || width > MAX_IMAGE_DIMENSION
-                            || height > MAX_IMAGE_DIMENSION
-                        {
-                            eprintln!("Invalid image dimensions: {}x{}", width, height);
+                        if !is_valid_dimension(img.width(), img.height()) {
+                            eprintln!("Invalid image dimensions: {}x{}", img.width(), img.height());
                             return None;
                         }
-
-                        let expected_len = (width * height * 3) as usize;
-                        if tj_image.pixels.len() != expected_len {
-                            eprintln!(
-                                "Invalid pixel data for {}: expected {} bytes, got {}",
-                                path.display(),
-                                expected_len,
-                                tj_image.pixels.len()
-                            );
+                        DecodedImage::Source(img)
+                    }
+                    Ok(JpegPixels::Cmyk(cmyk)) => {
+                        if !is_valid_dimension(cmyk.width, cmyk.height) {
+                            eprintln!("Invalid image dimensions: {}x{}", cmyk.width, cmyk.height);
                             return None;
                         }
-
-                        let image_buffer =
-                            image::RgbImage::from_raw(width, height, tj_image.pixels)?;
-                        DynamicImage::ImageRgb8(image_buffer)
+                        let (img, pixel_icc) = self.convert_cmyk(&cmyk, &jpeg_data, &options)?;
+                        DecodedImage::Converted(img, pixel_icc)
                     }
                     Err(e) => {
                         eprintln!(
@@ -443,15 +682,11 @@ impl ImageProcessor {
                 match image::open(path) {
                     Ok(img) => {
                         // Validazione dimensioni
-                        if img.width() == 0
-                            || img.height() == 0
-                            || img.width() > MAX_IMAGE_DIMENSION
-                            || img.height() > MAX_IMAGE_DIMENSION
-                        {
+                        if !is_valid_dimension(img.width(), img.height()) {
                             eprintln!("Invalid PNG dimensions: {}x{}", img.width(), img.height());
                             return None;
                         }
-                        img
+                        DecodedImage::Source(img)
                     }
                     Err(e) => {
                         eprintln!("Failed to open PNG {}: {}", path.display(), e);
@@ -465,53 +700,44 @@ impl ImageProcessor {
             }
         };
 
-        // Conversione profilo colore se necessario
-        let color_profile = color_profile::detect_color_profile(path);
-        let img = if !color_profile.is_web_safe() {
-            println!(
-                "Converting color profile from {:?} to sRGB for {}",
-                color_profile,
-                path.display()
-            );
-
-            let intent = match &self.options.color_intent {
-                settings::ColorConversionIntent::Perceptual => RenderingIntent::Perceptual,
-                settings::ColorConversionIntent::RelativeColorimetric => {
-                    RenderingIntent::RelativeColorimetric
-                }
-                settings::ColorConversionIntent::Saturation => RenderingIntent::Saturation,
-                settings::ColorConversionIntent::AbsoluteColorimetric => {
-                    RenderingIntent::AbsoluteColorimetric
-                }
-            };
+        // Conversione nello spazio colore di output (il CMYK è già convertito in decodifica)
+        let (img, pixel_icc) = match decoded {
+            DecodedImage::Source(img) => self.convert_color_space(img, path, &options),
+            DecodedImage::Converted(img, pixel_icc) => (img, pixel_icc),
+        };
 
-            match ColorManager::new() {
-                Ok(color_manager) => {
-                    match color_manager.convert_to_srgb(&img, &color_profile, intent) {
-                        Ok(converted_img) => {
-                            println!("✓ Color conversion successful with intent: {:?}", intent);
-                            converted_img
-                        }
-                        Err(e) => {
-                            eprintln!("⚠ Color conversion failed: {}, using original", e);
-                            img
-                        }
-                    }
-                }
-                Err(e) => {
-                    eprintln!(
-                        "⚠ Failed to create ColorManager: {}, skipping conversion",
-                        e
-                    );
-                    img
-                }
+        // Applica l'orientamento EXIF ai pixel (il tag in output viene riportato a 1)
+        let img = match ExifHandler::read_orientation(path) {
+            Some(orientation) if orientation != 1 => {
+                println!(
+                    "Applying EXIF orientation {} for {}",
+                    orientation,
+                    path.display()
+                );
+                image_decoder::apply_exif_orientation(img, orientation)
             }
-        } else {
-            img
+            _ => img,
+        };
+
+        // Ritaglia i bordi uniformi prima del resize
+        let (img, crop_rect) = match &options.trim {
+            Some(trim_options) => trim::apply_trim(&img, trim_options),
+            None => (img, None),
+        };
+
+        // Regolazioni tonali (dopo il trim, così l'auto-levels ignora i bordi)
+        let img = match &options.adjustments {
+            Some(adjustment_options) => adjustments::apply_adjustments(&img, adjustment_options),
+            None => img,
         };
 
         // Applica resize
-        let img = settings::apply_resize(&img, &self.options.resize);
+        let img = settings::apply_resize(
+            &img,
+            &options.resize,
+            options.linear_resize,
+            options.allow_upscale,
+        );
 
         // Validazione dimensioni finali
         if img.width() == 0 || img.height() == 0 {
@@ -519,8 +745,14 @@ impl ImageProcessor {
             return None;
         }
 
+        // Applica il watermark prima dell'encoding
+        let img = match &self.watermark {
+            Some(watermark) => watermark.apply(&img),
+            None => img,
+        };
+
         // Genera percorso output
-        let new_extension = match self.options.format {
+        let new_extension = match options.format {
             settings::OutputFormat::Jpeg => "jpg",
             settings::OutputFormat::Png => "png",
             settings::OutputFormat::Webp => "webp",
@@ -529,7 +761,7 @@ impl ImageProcessor {
         let file_stem = path.file_stem()?.to_str()?;
         let new_filename = format!("{}-optimized.{}", file_stem, new_extension);
 
-        let output_path = match &self.options.destination {
+        let output_path = match &options.destination {
             settings::OutputDestination::SameFolder => path.with_file_name(new_filename),
             settings::OutputDestination::CustomFolder { path: custom_path } => {
                 let custom_dir = PathBuf::from(custom_path);
@@ -554,14 +786,60 @@ impl ImageProcessor {
             }
         };
 
+        // Il JPEG non supporta la trasparenza: componi sopra il colore di sfondo
+        let img = if matches!(options.format, settings::OutputFormat::Jpeg)
+            && img.color().has_alpha()
+        {
+            let (flattened, transparent) = settings::flatten_alpha(&img, options.matte_color);
+            if transparent > FLATTEN_WARNING_THRESHOLD {
+                let [r, g, b] = options.matte_color;
+                warnings.push(format!(
+                    "{:.1}% of pixels were transparent and have been flattened onto #{:02X}{:02X}{:02X}",
+                    transparent * 100.0,
+                    r,
+                    g,
+                    b
+                ));
+            }
+            flattened
+        } else {
+            img
+        };
+
         // Encoding
-        let encoded_bytes = match self.options.format {
-            settings::OutputFormat::Jpeg => encode_jpeg_fast(&img, &self.options)?,
+        let write_srgb_tag = pixel_icc.is_none()
+            && matches!(options.icc_embedding, IccEmbedding::SrgbTag)
+            && matches!(options.format, settings::OutputFormat::Png);
+        let output_icc = self.icc_to_embed(pixel_icc, &options);
+        let output_icc = output_icc.as_deref();
+        let encoded_bytes = match options.format {
+            settings::OutputFormat::Jpeg => {
+                let jpeg_bytes = encode_jpeg_fast(&img, &options)?;
+                match output_icc {
+                    Some(icc) => IccWriter::embed_in_jpeg(&jpeg_bytes, icc).ok()?,
+                    None => jpeg_bytes,
+                }
+            }
             settings::OutputFormat::Webp => {
                 let is_large = original_size > 20_000_000;
-                encode_webp_fast(&img, &self.options, is_large)?
+                let webp_bytes = encode_webp_fast(&img, &options, is_large)?;
+                match output_icc {
+                    Some(icc) => IccWriter::embed_in_webp(&webp_bytes, icc).ok()?,
+                    None => webp_bytes,
+                }
+            }
+            settings::OutputFormat::Png => {
+                let png_bytes = settings::encode_image(&img, &options, output_icc)?;
+                if write_srgb_tag {
+                    IccWriter::embed_srgb_in_png(
+                        &png_bytes,
+                        Self::conversion_settings(&options).intent,
+                    )
+                    .ok()?
+                } else {
+                    png_bytes
+                }
             }
-            settings::OutputFormat::Png => settings::encode_image(&img, &self.options)?,
         };
 
         // Salva file
@@ -574,29 +852,35 @@ impl ImageProcessor {
             return None;
         }
 
-        let optimized_size = fs::metadata(&output_path).ok()?.len();
-        let reduction_percentage = if original_size > 0 {
-            (original_size.saturating_sub(optimized_size) as f64 / original_size as f64) * 100.0
-        } else {
-            0.0
-        };
-
-        // NUOVO: Preserva EXIF se richiesto
-        if self.options.exif_options.preserve_all {
+        // NUOVO: Preserva EXIF se richiesto (o scrive il template di metadati)
+        let keeps_source_metadata = options.exif_options.preserve_all
+            && options.exif_options.policy != MetadataPolicy::StripAll;
+        let mut location_exposed = false;
+        if keeps_source_metadata || !options.exif_options.template.is_empty() {
             use crate::core::exif_writer::ExifWriter;
 
             // Converti le opzioni da settings::ExifOptions a exif_handler::ExifOptions
             let exif_opts = crate::core::exif_handler::ExifOptions {
-                preserve_all: self.options.exif_options.preserve_all,
-                strip_gps: self.options.exif_options.strip_gps,
-                strip_thumbnail: self.options.exif_options.strip_thumbnail,
-                update_software: self.options.exif_options.update_software,
-                preserve_copyright: self.options.exif_options.preserve_copyright,
+                preserve_all: options.exif_options.preserve_all,
+                policy: options.exif_options.policy.clone(),
+                strip_gps: options.exif_options.strip_gps,
+                strip_thumbnail: options.exif_options.strip_thumbnail,
+                update_software: options.exif_options.update_software,
+                preserve_copyright: options.exif_options.preserve_copyright,
+                strip_maker_note: options.exif_options.strip_maker_note,
+                gps_precision: options.exif_options.gps_precision,
+                png_text_chunks: options.exif_options.png_text_chunks,
+                template: options.exif_options.template.clone(),
             };
 
             match ExifWriter::copy_exif(path, &output_path, &exif_opts) {
                 Ok(_) => {
                     println!("✓ EXIF preserved for: {}", output_path.display());
+                    // Verifica sui metadati effettivamente scritti (policy comprese)
+                    location_exposed =
+                        ExifHandler::extract_exif(&output_path).is_ok_and(|written| {
+                            ExifHandler::has_sensitive_location(&written, &exif_opts)
+                        });
                 }
                 Err(e) => {
                     eprintln!(
@@ -605,16 +889,28 @@ impl ImageProcessor {
                         e
                     );
                     // Non fallire l'ottimizzazione per questo
+                    warnings.push(format!("Metadata not written: {}", e));
                 }
             }
         }
 
+        // Dimensione finale, metadati compresi
+        let optimized_size = fs::metadata(&output_path).ok()?.len();
+        let reduction_percentage = if original_size > 0 {
+            (original_size.saturating_sub(optimized_size) as f64 / original_size as f64) * 100.0
+        } else {
+            0.0
+        };
+
         Some(OptimizationResult {
             original_path: path.to_str()?.to_string(),
             optimized_path: output_path.to_str()?.to_string(),
             original_size_kb: original_size as f64 / 1024.0,
             optimized_size_kb: optimized_size as f64 / 1024.0,
             reduction_percentage,
+            warnings,
+            crop_rect,
+            location_exposed,
         })
     }
 }
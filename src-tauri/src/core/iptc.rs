@@ -0,0 +1,621 @@
+// src-tauri/src/core/iptc.rs
+
+use crate::core::exif_handler::{ExifOptions, GpsPrecision, MetadataTemplate};
+use crate::core::metadata_policy::TagCategory;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Firma dei segmenti APP13 con le risorse Photoshop (8BIM)
+const PHOTOSHOP_SIGNATURE: &[u8] = b"Photoshop 3.0\0";
+/// ID della risorsa 8BIM che contiene i dataset IPTC-IIM
+const RESOURCE_IPTC: u16 = 0x0404;
+/// Marcatore di inizio di ogni dataset IIM
+const IIM_TAG_MARKER: u8 = 0x1C;
+/// Valore di 1:90 Coded Character Set per UTF-8 (ESC % G)
+const UTF8_CHARSET: &[u8] = b"\x1B%G";
+/// Spazio massimo per le risorse in un segmento APP13 (65535 - lunghezza - firma)
+const MAX_APP13_PAYLOAD: usize = 65535 - 2 - 14;
+
+/// Dataset IPTC-IIM (record 2) usati nel fotogiornalismo
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IptcData {
+    /// 2:05 Object Name
+    pub object_name: Option<String>,
+    /// 2:105 Headline
+    pub headline: Option<String>,
+    /// 2:120 Caption/Abstract
+    pub caption: Option<String>,
+    /// 2:25 Keywords
+    pub keywords: Vec<String>,
+    /// 2:80 By-line (autori)
+    pub by_line: Vec<String>,
+    /// 2:85 By-line Title
+    pub by_line_title: Option<String>,
+    /// 2:110 Credit
+    pub credit: Option<String>,
+    /// 2:115 Source
+    pub source: Option<String>,
+    /// 2:116 Copyright Notice
+    pub copyright_notice: Option<String>,
+    /// 2:55 Date Created (CCYYMMDD)
+    pub date_created: Option<String>,
+    /// 2:90 City
+    pub city: Option<String>,
+    /// 2:95 Province/State
+    pub province_state: Option<String>,
+    /// 2:101 Country/Primary Location Name
+    pub country: Option<String>,
+}
+
+impl IptcData {
+    /// Interpreta i dataset IIM di una risorsa 8BIM 0x0404
+    pub fn parse(iim: &[u8]) -> IptcData {
+        let mut data = IptcData::default();
+
+        for (record, dataset, value) in iim_datasets(iim) {
+            if record != 2 {
+                continue;
+            }
+            let text = decode_text(value);
+            match dataset {
+                5 => data.object_name = Some(text),
+                25 => data.keywords.push(text),
+                55 => data.date_created = Some(text),
+                80 => data.by_line.push(text),
+                85 => data.by_line_title = Some(text),
+                90 => data.city = Some(text),
+                95 => data.province_state = Some(text),
+                101 => data.country = Some(text),
+                105 => data.headline = Some(text),
+                110 => data.credit = Some(text),
+                115 => data.source = Some(text),
+                116 => data.copyright_notice = Some(text),
+                120 => data.caption = Some(text),
+                _ => {}
+            }
+        }
+
+        data
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == IptcData::default()
+    }
+}
+
+/// Legge i dati IPTC da un JPEG (None se assenti)
+pub fn extract_iptc(path: &Path) -> Option<IptcData> {
+    let jpeg = fs::read(path).ok()?;
+    let data = IptcData::parse(&read_iim(&jpeg));
+    (!data.is_empty()).then_some(data)
+}
+
+/// Segmento APP13 con la sola risorsa IPTC del JPEG sorgente, con i campi
+/// del template di `options` al posto di quelli originali. Se il sorgente non
+/// ha IPTC (o `jpeg` è vuoto) il record contiene solo i campi del template.
+/// Le altre risorse Photoshop (thumbnail, tracciati, slice) non valgono
+/// per l'immagine ottimizzata e non vengono copiate.
+pub fn iptc_app13_segment(jpeg: &[u8], options: &ExifOptions) -> Option<Vec<u8>> {
+    let template = &options.template;
+    let source = filter_datasets(&read_iim(jpeg), options);
+    let iim = if source.is_empty() {
+        // Record nuovo: testo UTF-8 (1:90) e versione del record 2 (2:00)
+        let mut header = Vec::new();
+        write_dataset(&mut header, 1, 90, UTF8_CHARSET);
+        write_dataset(&mut header, 2, 0, &4u16.to_be_bytes());
+        let iim = apply_template(&header, template);
+        if iim.len() == header.len() {
+            return None;
+        }
+        iim
+    } else {
+        apply_template(&source, template)
+    };
+
+    // Risorsa: "8BIM", ID, nome Pascal vuoto (2 byte), dimensione, dati (padding pari)
+    let mut resource = Vec::with_capacity(iim.len() + 13);
+    resource.extend_from_slice(b"8BIM");
+    resource.extend_from_slice(&RESOURCE_IPTC.to_be_bytes());
+    resource.extend_from_slice(&[0, 0]);
+    resource.extend_from_slice(&(iim.len() as u32).to_be_bytes());
+    resource.extend_from_slice(&iim);
+    if iim.len() % 2 == 1 {
+        resource.push(0);
+    }
+    if resource.len() > MAX_APP13_PAYLOAD {
+        return None;
+    }
+
+    let mut segment = Vec::with_capacity(resource.len() + 18);
+    segment.extend_from_slice(&[0xFF, 0xED]);
+    segment.extend_from_slice(
+        &((resource.len() + PHOTOSHOP_SIGNATURE.len() + 2) as u16).to_be_bytes(),
+    );
+    segment.extend_from_slice(PHOTOSHOP_SIGNATURE);
+    segment.extend_from_slice(&resource);
+    Some(segment)
+}
+
+/// Nome (per le policy) e categoria dei dataset del record 2 (None = non classificato)
+fn dataset_tag(dataset: u8) -> (&'static str, Option<TagCategory>) {
+    use TagCategory::{Datetime, Location, Owner, Software};
+    match dataset {
+        5 => ("ObjectName", None),
+        25 => ("Keywords", None),
+        55 => ("DateCreated", Some(Datetime)),
+        60 => ("TimeCreated", Some(Datetime)),
+        62 => ("DigitalCreationDate", Some(Datetime)),
+        63 => ("DigitalCreationTime", Some(Datetime)),
+        65 => ("OriginatingProgram", Some(Software)),
+        70 => ("ProgramVersion", Some(Software)),
+        80 => ("By-line", Some(Owner)),
+        85 => ("By-lineTitle", Some(Owner)),
+        90 => ("City", Some(Location)),
+        92 => ("Sub-location", Some(Location)),
+        95 => ("Province-State", Some(Location)),
+        100 => ("Country-PrimaryLocationCode", Some(Location)),
+        101 => ("Country-PrimaryLocationName", Some(Location)),
+        105 => ("Headline", None),
+        110 => ("Credit", Some(Owner)),
+        115 => ("Source", Some(Owner)),
+        116 => ("CopyrightNotice", Some(Owner)),
+        120 => ("Caption-Abstract", None),
+        _ => ("", None),
+    }
+}
+
+/// Rimuove i dataset del record 2 esclusi dalla policy, come i tag EXIF.
+/// La posizione segue anche le opzioni GPS: con coordinate arrotondate si
+/// toglie la Sub-location (più precisa della città).
+fn filter_datasets(iim: &[u8], options: &ExifOptions) -> Vec<u8> {
+    let policy = options.effective_policy();
+    let keeps = |dataset: u8| {
+        // 2:00 Record Version è strutturale
+        if dataset == 0 {
+            return true;
+        }
+        let (name, category) = dataset_tag(dataset);
+        if category == Some(TagCategory::Location)
+            && (options.strip_gps
+                || (dataset == 92 && options.gps_precision != GpsPrecision::Exact))
+        {
+            return false;
+        }
+        policy.keeps(name, category)
+    };
+
+    let mut result = Vec::with_capacity(iim.len());
+    for (record, dataset, value) in iim_datasets(iim) {
+        if record != 2 || keeps(dataset) {
+            write_dataset(&mut result, record, dataset, value);
+        }
+    }
+    result
+}
+
+/// Dataset IIM delle risorse 0x0404 di tutti i segmenti APP13
+fn read_iim(jpeg: &[u8]) -> Vec<u8> {
+    let mut iim = Vec::new();
+    for resources in photoshop_resources(jpeg) {
+        for (id, data) in parse_8bim_resources(resources) {
+            if id == RESOURCE_IPTC {
+                iim.extend_from_slice(data);
+            }
+        }
+    }
+    iim
+}
+
+/// Sostituisce by-line (2:80), copyright (2:116), caption (2:120) e keyword (2:25)
+/// con i valori del template, lasciando invariati gli altri dataset.
+/// I valori del template sono UTF-8: se il sorgente non dichiara UTF-8 (1:90)
+/// il dataset viene aggiunto e i testi Latin-1 del record 2 convertiti.
+fn apply_template(iim: &[u8], template: &MetadataTemplate) -> Vec<u8> {
+    let single = |value: &Option<String>| value.iter().cloned().collect::<Vec<_>>();
+    let overrides = [
+        (80, single(&template.artist)),
+        (116, single(&template.copyright)),
+        (120, single(&template.description)),
+        (25, template.keywords.clone()),
+    ];
+    let overridden = |dataset: u8| {
+        overrides
+            .iter()
+            .any(|(id, values)| *id == dataset && !values.is_empty())
+    };
+
+    let datasets = iim_datasets(iim);
+    let is_utf8 = datasets
+        .iter()
+        .any(|&(record, dataset, value)| record == 1 && dataset == 90 && value == UTF8_CHARSET);
+
+    let mut result = Vec::with_capacity(iim.len());
+    let mut charset_written = is_utf8;
+    for (record, dataset, value) in datasets {
+        if !is_utf8 {
+            // 1:90 chiude il record 1, prima dei dataset applicativi
+            if record == 1 && dataset == 90 {
+                continue;
+            }
+            if record > 1 && !charset_written {
+                write_dataset(&mut result, 1, 90, UTF8_CHARSET);
+                charset_written = true;
+            }
+        }
+        if record == 2 && overridden(dataset) {
+            continue;
+        }
+
+        // 2:00 Record Version è binario
+        let latin1 = !is_utf8 && record == 2 && dataset != 0 && std::str::from_utf8(value).is_err();
+        if latin1 {
+            let text: String = value.iter().map(|&byte| byte as char).collect();
+            write_dataset(&mut result, record, dataset, text.as_bytes());
+        } else {
+            write_dataset(&mut result, record, dataset, value);
+        }
+    }
+    if !charset_written {
+        write_dataset(&mut result, 1, 90, UTF8_CHARSET);
+    }
+    for (dataset, values) in &overrides {
+        for value in values {
+            write_dataset(&mut result, 2, *dataset, value.as_bytes());
+        }
+    }
+    result
+}
+
+/// Scrive un dataset IIM (lunghezza estesa oltre 32767 byte)
+fn write_dataset(iim: &mut Vec<u8>, record: u8, dataset: u8, value: &[u8]) {
+    iim.extend_from_slice(&[IIM_TAG_MARKER, record, dataset]);
+    if value.len() < 0x8000 {
+        iim.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    } else {
+        iim.extend_from_slice(&0x8004u16.to_be_bytes());
+        iim.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    }
+    iim.extend_from_slice(value);
+}
+
+/// Verifica se un payload APP13 contiene risorse Photoshop
+pub fn is_photoshop_app13(payload: &[u8]) -> bool {
+    payload.starts_with(PHOTOSHOP_SIGNATURE)
+}
+
+/// Blocchi di risorse 8BIM dei segmenti APP13 "Photoshop 3.0"
+fn photoshop_resources(jpeg: &[u8]) -> Vec<&[u8]> {
+    let mut blocks = Vec::new();
+    if !jpeg.starts_with(&[0xFF, 0xD8]) {
+        return blocks;
+    }
+
+    let mut i = 2;
+    while i + 4 <= jpeg.len() && jpeg[i] == 0xFF {
+        let marker = jpeg[i + 1];
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+        let length = u16::from_be_bytes([jpeg[i + 2], jpeg[i + 3]]) as usize;
+        let end = (i + 2 + length).min(jpeg.len());
+        let payload = &jpeg[(i + 4).min(end)..end];
+        if marker == 0xED && is_photoshop_app13(payload) {
+            blocks.push(&payload[PHOTOSHOP_SIGNATURE.len()..]);
+        }
+        i += 2 + length;
+    }
+    blocks
+}
+
+/// Risorse 8BIM (ID, dati) di un blocco Photoshop
+fn parse_8bim_resources(block: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut resources = Vec::new();
+    let mut offset = 0;
+
+    while offset + 12 <= block.len() && &block[offset..offset + 4] == b"8BIM" {
+        let id = u16::from_be_bytes([block[offset + 4], block[offset + 5]]);
+        // Nome Pascal: byte di lunghezza + nome, padding a lunghezza pari
+        let name_length = block[offset + 6] as usize;
+        let name_size = (name_length + 1 + 1) & !1;
+        let size_offset = offset + 6 + name_size;
+        if size_offset + 4 > block.len() {
+            break;
+        }
+
+        let size = u32::from_be_bytes([
+            block[size_offset],
+            block[size_offset + 1],
+            block[size_offset + 2],
+            block[size_offset + 3],
+        ]) as usize;
+        let start = size_offset + 4;
+        if start + size > block.len() {
+            break;
+        }
+
+        resources.push((id, &block[start..start + size]));
+        offset = start + size + (size & 1);
+    }
+
+    resources
+}
+
+/// Dataset IIM (record, dataset, valore)
+fn iim_datasets(iim: &[u8]) -> Vec<(u8, u8, &[u8])> {
+    let mut datasets = Vec::new();
+    let mut offset = 0;
+
+    while offset + 5 <= iim.len() && iim[offset] == IIM_TAG_MARKER {
+        let record = iim[offset + 1];
+        let dataset = iim[offset + 2];
+        let mut size = u16::from_be_bytes([iim[offset + 3], iim[offset + 4]]) as usize;
+        let mut start = offset + 5;
+
+        // Extended dataset: il bit alto indica quanti byte seguono per la lunghezza
+        if size & 0x8000 != 0 {
+            let length_bytes = size & 0x7FFF;
+            if length_bytes > 4 || start + length_bytes > iim.len() {
+                break;
+            }
+            size = iim[start..start + length_bytes]
+                .iter()
+                .fold(0usize, |acc, &byte| (acc << 8) | byte as usize);
+            start += length_bytes;
+        }
+        if start + size > iim.len() {
+            break;
+        }
+
+        datasets.push((record, dataset, &iim[start..start + size]));
+        offset = start + size;
+    }
+
+    datasets
+}
+
+/// Testo IIM: UTF-8 se valido (1:90 = ESC % G), altrimenti Latin-1
+fn decode_text(value: &[u8]) -> String {
+    let text = match std::str::from_utf8(value) {
+        Ok(text) => text.to_string(),
+        Err(_) => value.iter().map(|&byte| byte as char).collect(),
+    };
+    text.trim_end_matches('\0').trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::metadata_policy::MetadataPolicy;
+
+    fn dataset(record: u8, dataset: u8, value: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![IIM_TAG_MARKER, record, dataset];
+        bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(value);
+        bytes
+    }
+
+    /// JPEG con un APP13: risorsa thumbnail (0x040C) e risorsa IPTC con nome
+    fn sample_jpeg() -> Vec<u8> {
+        let mut iim = dataset(1, 90, b"\x1B%G");
+        iim.extend(dataset(2, 80, b"Jane Doe"));
+        iim.extend(dataset(2, 25, b"harbor"));
+        iim.extend(dataset(2, 25, b"sunset"));
+        iim.extend(dataset(2, 110, b"Agency"));
+        iim.extend(dataset(2, 116, "© 2024 Jane Doe".as_bytes()));
+        iim.extend(dataset(2, 120, b"Boats at dusk"));
+        iim.extend(dataset(2, 90, b"Genova"));
+        // Latin-1 non UTF-8
+        iim.extend(dataset(2, 101, b"Espa\xF1a"));
+
+        let mut block = Vec::new();
+        block.extend_from_slice(b"8BIM\x04\x0C\x00\x00\x00\x00\x00\x03abc\x00");
+        block.extend_from_slice(b"8BIM\x04\x04\x04IPTC\x00");
+        block.extend_from_slice(&(iim.len() as u32).to_be_bytes());
+        block.extend_from_slice(&iim);
+        if iim.len() % 2 == 1 {
+            block.push(0);
+        }
+
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xED];
+        jpeg.extend_from_slice(&((block.len() + 16) as u16).to_be_bytes());
+        jpeg.extend_from_slice(PHOTOSHOP_SIGNATURE);
+        jpeg.extend_from_slice(&block);
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+        jpeg
+    }
+
+    #[test]
+    fn test_parse_iptc() {
+        let path = std::env::temp_dir().join("iron_test_iptc.jpg");
+        fs::write(&path, sample_jpeg()).unwrap();
+        let data = extract_iptc(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(data.by_line, vec!["Jane Doe"]);
+        assert_eq!(data.keywords, vec!["harbor", "sunset"]);
+        assert_eq!(data.credit.as_deref(), Some("Agency"));
+        assert_eq!(data.copyright_notice.as_deref(), Some("© 2024 Jane Doe"));
+        assert_eq!(data.caption.as_deref(), Some("Boats at dusk"));
+        assert_eq!(data.city.as_deref(), Some("Genova"));
+        assert_eq!(data.country.as_deref(), Some("España"));
+    }
+
+    #[test]
+    fn test_app13_keeps_only_iptc() {
+        let jpeg = sample_jpeg();
+        let segment = iptc_app13_segment(&jpeg, &ExifOptions::default()).unwrap();
+
+        let mut rebuilt = vec![0xFF, 0xD8];
+        rebuilt.extend_from_slice(&segment);
+        let blocks = photoshop_resources(&rebuilt);
+        assert_eq!(blocks.len(), 1);
+        let resources = parse_8bim_resources(blocks[0]);
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].0, RESOURCE_IPTC);
+
+        assert_eq!(
+            IptcData::parse(resources[0].1),
+            IptcData::parse(parse_8bim_resources(photoshop_resources(&jpeg)[0])[1].1)
+        );
+    }
+
+    #[test]
+    fn test_location_follows_gps_options() {
+        let parsed = |options: &ExifOptions| {
+            let mut rebuilt = vec![0xFF, 0xD8];
+            rebuilt.extend_from_slice(&iptc_app13_segment(&sample_jpeg(), options).unwrap());
+            IptcData::parse(&read_iim(&rebuilt))
+        };
+
+        let stripped = parsed(&ExifOptions {
+            strip_gps: true,
+            ..ExifOptions::default()
+        });
+        assert!(stripped.city.is_none());
+        assert!(stripped.country.is_none());
+        // Gli altri dataset restano
+        assert_eq!(stripped.by_line, vec!["Jane Doe"]);
+
+        let denied = parsed(&ExifOptions {
+            policy: MetadataPolicy::DenyList {
+                categories: vec![TagCategory::Location],
+                tags: vec![],
+            },
+            ..ExifOptions::default()
+        });
+        assert!(denied.city.is_none());
+
+        let city = parsed(&ExifOptions {
+            gps_precision: GpsPrecision::City,
+            ..ExifOptions::default()
+        });
+        assert_eq!(city.city.as_deref(), Some("Genova"));
+    }
+
+    #[test]
+    fn test_policy_filters_datasets() {
+        let options = ExifOptions {
+            policy: MetadataPolicy::AllowList {
+                categories: vec![TagCategory::Owner],
+                tags: vec!["keywords".to_string()],
+            },
+            ..ExifOptions::default()
+        };
+        let mut rebuilt = vec![0xFF, 0xD8];
+        rebuilt.extend_from_slice(&iptc_app13_segment(&sample_jpeg(), &options).unwrap());
+        let data = IptcData::parse(&read_iim(&rebuilt));
+
+        assert_eq!(data.by_line, vec!["Jane Doe"]);
+        assert_eq!(data.credit.as_deref(), Some("Agency"));
+        assert_eq!(data.keywords, vec!["harbor", "sunset"]);
+        assert!(data.caption.is_none());
+        assert!(data.city.is_none());
+    }
+
+    #[test]
+    fn test_template_overrides_datasets() {
+        let template = MetadataTemplate {
+            artist: Some("Staff".to_string()),
+            keywords: vec!["news".to_string()],
+            ..MetadataTemplate::default()
+        };
+        let options = ExifOptions {
+            template,
+            ..ExifOptions::default()
+        };
+        let segment = iptc_app13_segment(&sample_jpeg(), &options).unwrap();
+        let mut rebuilt = vec![0xFF, 0xD8];
+        rebuilt.extend_from_slice(&segment);
+        let data = IptcData::parse(&read_iim(&rebuilt));
+
+        assert_eq!(data.by_line, vec!["Staff"]);
+        assert_eq!(data.keywords, vec!["news"]);
+        // I dataset non presenti nel template restano quelli del sorgente
+        assert_eq!(data.credit.as_deref(), Some("Agency"));
+        assert_eq!(data.caption.as_deref(), Some("Boats at dusk"));
+    }
+
+    #[test]
+    fn test_template_without_source_iptc() {
+        let jpeg = vec![0xFF, 0xD8, 0xFF, 0xD9];
+        assert!(iptc_app13_segment(&jpeg, &ExifOptions::default()).is_none());
+
+        let template = MetadataTemplate {
+            artist: Some("Staff".to_string()),
+            copyright: Some("© 2024 Agency".to_string()),
+            keywords: vec!["news".to_string(), "port".to_string()],
+            ..MetadataTemplate::default()
+        };
+        let options = ExifOptions {
+            template,
+            ..ExifOptions::default()
+        };
+        let segment = iptc_app13_segment(&jpeg, &options).unwrap();
+        let mut rebuilt = vec![0xFF, 0xD8];
+        rebuilt.extend_from_slice(&segment);
+        let iim = read_iim(&rebuilt);
+        let datasets = iim_datasets(&iim);
+        assert_eq!(datasets[0], (1, 90, &b"\x1B%G"[..]));
+        assert_eq!(datasets[1], (2, 0, &[0, 4][..]));
+
+        let data = IptcData::parse(&iim);
+        assert_eq!(data.by_line, vec!["Staff"]);
+        assert_eq!(data.copyright_notice.as_deref(), Some("© 2024 Agency"));
+        assert_eq!(data.keywords, vec!["news", "port"]);
+        assert!(data.caption.is_none());
+    }
+
+    #[test]
+    fn test_template_declares_utf8_for_latin1_source() {
+        // Sorgente senza 1:90: testi Latin-1
+        let mut iim = dataset(1, 0, &[0, 4]);
+        iim.extend(dataset(2, 0, &[0, 4]));
+        iim.extend(dataset(2, 90, b"K\xF6ln"));
+        iim.extend(dataset(2, 110, b"Agency"));
+        let template = MetadataTemplate {
+            copyright: Some("© 2024 Agency".to_string()),
+            ..MetadataTemplate::default()
+        };
+
+        let rebuilt = apply_template(&iim, &template);
+        let datasets = iim_datasets(&rebuilt);
+        assert_eq!(datasets[0], (1, 0, &[0, 4][..]));
+        assert_eq!(datasets[1], (1, 90, UTF8_CHARSET));
+        assert_eq!(datasets[2], (2, 0, &[0, 4][..]));
+        assert_eq!(datasets[3], (2, 90, "Köln".as_bytes()));
+        assert_eq!(datasets[4], (2, 110, &b"Agency"[..]));
+        assert_eq!(datasets[5], (2, 116, "© 2024 Agency".as_bytes()));
+
+        // Con 1:90 UTF-8 già presente i valori restano invariati
+        let sample = read_iim(&sample_jpeg());
+        let rebuilt = apply_template(&sample, &template);
+        let datasets = iim_datasets(&rebuilt);
+        assert_eq!(
+            datasets
+                .iter()
+                .filter(|(record, dataset, _)| (*record, *dataset) == (1, 90))
+                .count(),
+            1
+        );
+        assert!(datasets.contains(&(2, 101, &b"Espa\xF1a"[..])));
+    }
+
+    #[test]
+    fn test_extended_dataset_length() {
+        // Lunghezza su 4 byte (bit alto del campo size)
+        let mut iim = vec![IIM_TAG_MARKER, 2, 120, 0x80, 0x04, 0, 0, 0, 5];
+        iim.extend_from_slice(b"Hello");
+        iim.extend(dataset(2, 105, b"Title"));
+
+        let data = IptcData::parse(&iim);
+        assert_eq!(data.caption.as_deref(), Some("Hello"));
+        assert_eq!(data.headline.as_deref(), Some("Title"));
+
+        let long_caption = "a".repeat(40_000);
+        let mut iim = Vec::new();
+        write_dataset(&mut iim, 2, 120, long_caption.as_bytes());
+        assert_eq!(IptcData::parse(&iim).caption, Some(long_caption));
+    }
+}
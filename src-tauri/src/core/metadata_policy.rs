@@ -0,0 +1,254 @@
+// src-tauri/src/core/metadata_policy.rs
+
+use crate::core::tiff::{
+    ExifTree, Ifd, TAG_EXIF_IFD, TAG_GPS_IFD, TAG_GPS_VERSION_ID, TAG_INTEROP_IFD, TAG_ORIENTATION,
+    TAG_PIXEL_X_DIMENSION, TAG_PIXEL_Y_DIMENSION,
+};
+use exif::{Context, Tag};
+use serde::{Deserialize, Serialize};
+
+/// Categorie di tag EXIF usate nelle policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TagCategory {
+    /// Make, Model, obiettivo e numeri di serie
+    Camera,
+    /// Esposizione, ISO, flash, focale, bilanciamento del bianco
+    Capture,
+    /// Date e ore di scatto e modifica
+    Datetime,
+    /// Tutta la GPS IFD
+    Location,
+    /// Artist, Copyright, proprietario della fotocamera
+    Owner,
+    /// Software e computer usati per l'elaborazione
+    Software,
+    /// MakerNote proprietaria del produttore
+    MakerNotes,
+}
+
+/// Quali metadati del sorgente vengono copiati negli output.
+/// Le policy filtrano solo i tag del sorgente: Software aggiornato e
+/// template di metadati vengono scritti dopo.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum MetadataPolicy {
+    /// Nessun metadato del sorgente
+    StripAll,
+    /// Tutti i metadati (salvo le opzioni di privacy)
+    #[default]
+    KeepAll,
+    /// Solo le categorie e i tag elencati
+    AllowList {
+        #[serde(default)]
+        categories: Vec<TagCategory>,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+    /// Tutto tranne le categorie e i tag elencati
+    DenyList {
+        #[serde(default)]
+        categories: Vec<TagCategory>,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+}
+
+/// Tag che descrivono la struttura dell'immagine: sempre conservati,
+/// altrimenti l'immagine potrebbe essere interpretata male (es. ColorSpace)
+const STRUCTURAL_TAGS: &[u16] = &[
+    0x0100, // ImageWidth
+    0x0101, // ImageLength
+    0x0102, // BitsPerSample
+    0x0103, // Compression
+    0x0106, // PhotometricInterpretation
+    TAG_ORIENTATION,
+    0x0115, // SamplesPerPixel
+    0x011A, // XResolution
+    0x011B, // YResolution
+    0x0128, // ResolutionUnit
+    0x0213, // YCbCrPositioning
+    TAG_EXIF_IFD,
+    TAG_GPS_IFD,
+    0x9000, // ExifVersion
+    0x9101, // ComponentsConfiguration
+    0xA000, // FlashpixVersion
+    0xA001, // ColorSpace
+    TAG_PIXEL_X_DIMENSION,
+    TAG_PIXEL_Y_DIMENSION,
+    TAG_INTEROP_IFD,
+    0xA500, // Gamma
+];
+
+/// Categoria dei tag di IFD0 ed Exif IFD (None = non classificato)
+fn category_of(tag: u16) -> Option<TagCategory> {
+    match tag {
+        0x010F | 0x0110 | 0xA431..=0xA435 => Some(TagCategory::Camera),
+        0x829A
+        | 0x829D
+        | 0x8822
+        | 0x8824
+        | 0x8827
+        | 0x8830..=0x8835
+        | 0x9201..=0x920A
+        | 0x9214
+        | 0xA20E..=0xA210
+        | 0xA215
+        | 0xA217
+        | 0xA300..=0xA302
+        | 0xA401..=0xA40C => Some(TagCategory::Capture),
+        0x0132 | 0x9003 | 0x9004 | 0x9010..=0x9012 | 0x9290..=0x9292 => Some(TagCategory::Datetime),
+        0x013B | 0x8298 | 0xA430 => Some(TagCategory::Owner),
+        0x000B | 0x0131 | 0x013C => Some(TagCategory::Software),
+        0x927C => Some(TagCategory::MakerNotes),
+        _ => None,
+    }
+}
+
+impl MetadataPolicy {
+    /// Verifica se un tag (nome EXIF e categoria) va conservato
+    pub fn keeps(&self, name: &str, category: Option<TagCategory>) -> bool {
+        let listed = |categories: &[TagCategory], tags: &[String]| {
+            category.is_some_and(|category| categories.contains(&category))
+                || tags.iter().any(|tag| tag.trim().eq_ignore_ascii_case(name))
+        };
+        match self {
+            MetadataPolicy::StripAll => false,
+            MetadataPolicy::KeepAll => true,
+            MetadataPolicy::AllowList { categories, tags } => listed(categories, tags),
+            MetadataPolicy::DenyList { categories, tags } => !listed(categories, tags),
+        }
+    }
+
+    /// Rimuove dalla struttura EXIF i tag esclusi dalla policy.
+    /// La Interop IFD e la thumbnail (IFD1) non sono classificate e restano;
+    /// la thumbnail è gestita da `strip_thumbnail`.
+    pub fn apply(&self, tree: &mut ExifTree) {
+        if *self == MetadataPolicy::KeepAll {
+            return;
+        }
+
+        self.filter_ifd(&mut tree.ifd0, Context::Tiff);
+        if let Some(exif) = &mut tree.exif {
+            self.filter_ifd(exif, Context::Exif);
+        }
+
+        if let Some(gps) = &mut tree.gps {
+            gps.entries.retain(|entry| {
+                let name = Tag(Context::Gps, entry.tag).to_string();
+                self.keeps(&name, Some(TagCategory::Location))
+            });
+            // Senza coordinate la sola GPSVersionID non ha senso
+            if gps
+                .entries
+                .iter()
+                .all(|entry| entry.tag == TAG_GPS_VERSION_ID)
+            {
+                tree.gps = None;
+            }
+        }
+    }
+
+    fn filter_ifd(&self, ifd: &mut Ifd, context: Context) {
+        ifd.entries.retain(|entry| {
+            STRUCTURAL_TAGS.contains(&entry.tag)
+                || self.keeps(&Tag(context, entry.tag).to_string(), category_of(entry.tag))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::tiff::{ByteOrder, TiffValue, TAG_GPS_LATITUDE};
+
+    fn sample_tree() -> ExifTree {
+        let mut tree = ExifTree::new(ByteOrder::LittleEndian);
+        let ascii = |text: &str| TiffValue::Ascii(text.to_string());
+
+        for (tag, value) in [
+            (0x010F, ascii("Canon")),      // Make
+            (0x0131, ascii("Lightroom")),  // Software
+            (0x013B, ascii("Jane Doe")),   // Artist
+            (0x8298, ascii("© Jane Doe")), // Copyright
+            (TAG_ORIENTATION, TiffValue::Short(vec![1])),
+        ] {
+            let entry = tree.entry(tag, value);
+            tree.ifd0.set(entry);
+        }
+
+        let mut exif = Ifd::default();
+        for (tag, value) in [
+            (0x9003, ascii("2024:05:01 10:20:30")), // DateTimeOriginal
+            (0x829A, TiffValue::Rational(vec![(1, 250)])), // ExposureTime
+            (0xA434, ascii("EF24-70mm")),           // LensModel
+            (0xA001, TiffValue::Short(vec![1])),    // ColorSpace
+            (0x927C, TiffValue::Undefined(vec![0; 8])), // MakerNote
+        ] {
+            exif.set(tree.entry(tag, value));
+        }
+        tree.exif = Some(exif);
+
+        let mut gps = Ifd::default();
+        gps.set(tree.entry(TAG_GPS_VERSION_ID, TiffValue::Byte(vec![2, 3, 0, 0])));
+        gps.set(tree.entry(TAG_GPS_LATITUDE, TiffValue::Rational(vec![(45, 1); 3])));
+        tree.gps = Some(gps);
+
+        tree
+    }
+
+    fn tags(ifd: &Ifd) -> Vec<u16> {
+        ifd.entries.iter().map(|entry| entry.tag).collect()
+    }
+
+    #[test]
+    fn test_allow_list() {
+        let mut tree = sample_tree();
+        let policy = MetadataPolicy::AllowList {
+            categories: vec![TagCategory::Camera],
+            tags: vec!["datetimeoriginal".to_string()],
+        };
+        policy.apply(&mut tree);
+
+        // Make + Orientation (strutturale)
+        assert_eq!(tags(&tree.ifd0), vec![0x010F, TAG_ORIENTATION]);
+        // DateTimeOriginal, LensModel e ColorSpace (strutturale)
+        assert_eq!(
+            tags(tree.exif.as_ref().unwrap()),
+            vec![0x9003, 0xA001, 0xA434]
+        );
+        assert!(tree.gps.is_none());
+    }
+
+    #[test]
+    fn test_deny_list() {
+        let mut tree = sample_tree();
+        let policy = MetadataPolicy::DenyList {
+            categories: vec![TagCategory::Owner, TagCategory::MakerNotes],
+            tags: vec!["GPSLatitude".to_string(), "Software".to_string()],
+        };
+        policy.apply(&mut tree);
+
+        assert_eq!(tags(&tree.ifd0), vec![0x010F, TAG_ORIENTATION]);
+        assert_eq!(
+            tags(tree.exif.as_ref().unwrap()),
+            vec![0x829A, 0x9003, 0xA001, 0xA434]
+        );
+        // Rimasta solo GPSVersionID: la GPS IFD viene eliminata
+        assert!(tree.gps.is_none());
+    }
+
+    #[test]
+    fn test_policy_deserialization() {
+        let policy: MetadataPolicy = serde_json::from_str(
+            r#"{"type":"denyList","categories":["location","makerNotes"],"tags":["Artist"]}"#,
+        )
+        .unwrap();
+        assert!(!policy.keeps("GPSLatitude", Some(TagCategory::Location)));
+        assert!(!policy.keeps("Artist", Some(TagCategory::Owner)));
+        assert!(policy.keeps("Copyright", Some(TagCategory::Owner)));
+
+        let policy: MetadataPolicy = serde_json::from_str(r#"{"type":"stripAll"}"#).unwrap();
+        assert_eq!(policy, MetadataPolicy::StripAll);
+    }
+}
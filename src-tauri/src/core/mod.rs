@@ -1,13 +1,24 @@
 // src-tauri/src/core/mod.rs
+pub mod adjustments;
+pub mod cmyk_profile;
 pub mod color_management;
 pub mod color_profile;
 pub mod error;
 pub mod exif_handler;
 pub mod exif_writer;
+pub mod gamut;
+pub mod icc_writer;
 pub mod image_decoder;
 pub mod image_processing;
+pub mod iptc;
+pub mod metadata_policy;
 pub mod models;
 pub mod settings;
 pub mod system_info;
 pub mod task;
 pub mod thumbnail;
+pub mod tiff;
+pub mod transform_cache;
+pub mod trim;
+pub mod watermark;
+pub mod xmp;
@@ -1,6 +1,8 @@
 // src-tauri/src/core/models.rs
-use crate::core::color_profile::ColorProfile;
+use crate::core::color_profile::{ColorProfile, ProfileSource};
 use crate::core::exif_handler::ExifData;
+use crate::core::iptc::IptcData;
+use crate::core::xmp::XmpData;
 use serde::Serialize;
 
 // --- Modelli per la Comunicazione con il Frontend ---
@@ -11,11 +13,14 @@ pub struct ImageInfo {
     pub mimetype: String,
     pub last_modified: u64,
     pub color_profile: ColorProfile,
+    pub color_profile_source: ProfileSource,
     pub needs_conversion: bool,
     pub preview_path: Option<String>,
     pub thumbnail_path: Option<String>,
     pub exif_data: Option<ExifData>,
     pub has_exif: bool,
+    pub xmp_data: Option<XmpData>,
+    pub iptc_data: Option<IptcData>,
 }
 
 #[derive(Clone, Serialize)]
@@ -25,6 +30,31 @@ pub struct OptimizationResult {
     pub original_size_kb: f64,
     pub optimized_size_kb: f64,
     pub reduction_percentage: f64,
+    pub warnings: Vec<String>,
+    pub crop_rect: Option<CropRect>,
+    /// True se l'output contiene coordinate GPS esatte
+    pub location_exposed: bool,
+}
+
+/// Rettangolo di ritaglio applicato dal trim automatico (coordinate sorgente)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Risultato dell'analisi del gamut di un'immagine rispetto alla destinazione
+#[derive(Clone, Serialize)]
+pub struct GamutReport {
+    pub path: String,
+    pub color_profile: ColorProfile,
+    pub total_pixels: u64,
+    pub out_of_gamut_pixels: u64,
+    pub out_of_gamut_percentage: f64,
+    pub mask_path: Option<String>,
+    pub error: Option<String>,
 }
 
 #[derive(Clone, Serialize)]
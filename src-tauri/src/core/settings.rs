@@ -1,5 +1,9 @@
 // src-tauri/src/core/settings.rs
-use image::{codecs, DynamicImage, ImageFormat};
+use crate::core::color_management;
+use crate::core::exif_handler::{GpsPrecision, MetadataTemplate};
+use crate::core::icc_writer::IccWriter;
+use crate::core::metadata_policy::MetadataPolicy;
+use image::{codecs, ColorType, DynamicImage, ImageEncoder};
 use imagequant;
 use png;
 use serde::{Deserialize, Serialize};
@@ -22,6 +26,9 @@ pub enum CompressionProfile {
     Lossless,
 }
 
+/// Lato massimo consentito per l'output (anche con upscale abilitato)
+const MAX_OUTPUT_DIMENSION: u32 = 16384;
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub enum ResizePreset {
@@ -39,44 +46,94 @@ pub enum ResizePreset {
         width: u32,
         height: u32,
     }, // Dimensioni personalizzate
+    Percent(f32), // Percentuale delle dimensioni originali
+    LongEdge(u32), // Lato lungo in pixel
+    ShortEdge(u32), // Lato corto in pixel
+    MaxMegapixels(f32), // Area massima in megapixel
+    Width(u32), // Solo larghezza, altezza proporzionale
+    Height(u32), // Solo altezza, larghezza proporzionale
 }
 
 impl ResizePreset {
-    /// Restituisce le dimensioni (larghezza, altezza) del preset
+    /// Restituisce le dimensioni (larghezza, altezza) del preset a box fisso.
+    /// Le specifiche relative (percentuale, lati, megapixel) restituiscono None.
     pub fn dimensions(&self) -> Option<(u32, u32)> {
         match self {
-            ResizePreset::None => None,
             ResizePreset::UHD4K => Some((3840, 2160)),
             ResizePreset::QHD2K => Some((2560, 1440)),
             ResizePreset::FullHD => Some((1920, 1080)),
             ResizePreset::HD => Some((1280, 720)),
             ResizePreset::SD => Some((854, 480)),
             ResizePreset::Custom { width, height } => Some((*width, *height)),
+            _ => None,
+        }
+    }
+
+    /// Fattore di scala da applicare all'immagine originale
+    fn scale_ratio(&self, original_width: u32, original_height: u32) -> Option<f32> {
+        let (width, height) = (original_width as f32, original_height as f32);
+
+        let ratio = match self {
+            ResizePreset::None => return None,
+            ResizePreset::Custom {
+                width: target_w,
+                height: target_h,
+            } => (*target_w as f32 / width).min(*target_h as f32 / height),
+            ResizePreset::Percent(percent) => percent / 100.0,
+            ResizePreset::LongEdge(edge) => *edge as f32 / width.max(height),
+            ResizePreset::ShortEdge(edge) => *edge as f32 / width.min(height),
+            ResizePreset::MaxMegapixels(megapixels) => {
+                (megapixels * 1_000_000.0 / (width * height)).sqrt()
+            }
+            ResizePreset::Width(target_w) => *target_w as f32 / width,
+            ResizePreset::Height(target_h) => *target_h as f32 / height,
+            preset => {
+                // I preset a box seguono l'orientamento dell'immagine
+                let (box_w, box_h) = preset.dimensions()?;
+                let (box_w, box_h) = if original_height > original_width {
+                    (box_h, box_w)
+                } else {
+                    (box_w, box_h)
+                };
+                (box_w as f32 / width).min(box_h as f32 / height)
+            }
+        };
+
+        if ratio.is_finite() && ratio > 0.0 {
+            Some(ratio)
+        } else {
+            None
         }
     }
 
-    /// Calcola le nuove dimensioni mantenendo l'aspect ratio
+    /// Calcola le nuove dimensioni mantenendo l'aspect ratio.
+    /// Senza `allow_upscale` le immagini più piccole del target non vengono toccate.
     pub fn calculate_resize(
         &self,
         original_width: u32,
         original_height: u32,
+        allow_upscale: bool,
     ) -> Option<(u32, u32)> {
-        let target_dims = self.dimensions()?;
+        if original_width == 0 || original_height == 0 {
+            return None;
+        }
+
+        let mut ratio = self.scale_ratio(original_width, original_height)?;
 
-        // Se l'immagine è già più piccola del target, non ridimensionare
-        if original_width <= target_dims.0 && original_height <= target_dims.1 {
+        if ratio >= 1.0 && !allow_upscale {
             return None;
         }
 
-        // Calcola il ratio di ridimensionamento mantenendo l'aspect ratio
-        let width_ratio = target_dims.0 as f32 / original_width as f32;
-        let height_ratio = target_dims.1 as f32 / original_height as f32;
+        // Limita l'upscale al lato massimo consentito
+        let long_edge = original_width.max(original_height) as f32;
+        ratio = ratio.min(MAX_OUTPUT_DIMENSION as f32 / long_edge);
 
-        // Usa il ratio più piccolo per far stare l'immagine nel target
-        let ratio = width_ratio.min(height_ratio);
+        let new_width = ((original_width as f32 * ratio).round() as u32).max(1);
+        let new_height = ((original_height as f32 * ratio).round() as u32).max(1);
 
-        let new_width = (original_width as f32 * ratio) as u32;
-        let new_height = (original_height as f32 * ratio) as u32;
+        if (new_width, new_height) == (original_width, original_height) {
+            return None;
+        }
 
         Some((new_width, new_height))
     }
@@ -98,14 +155,174 @@ pub enum ColorConversionIntent {
     AbsoluteColorimetric, // Preserva valori assoluti
 }
 
+/// Spazio colore di destinazione dell'output (il profilo viene embedded nel file)
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum OutputColorSpace {
+    #[default]
+    Srgb,
+    DisplayP3,
+    AdobeRgb,
+    Custom {
+        path: String,
+    }, // Profilo ICC da file
+}
+
+/// Come scrivere il profilo colore nei file di output
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum IccEmbedding {
+    /// Solo profili non sRGB (destinazioni wide-gamut o conversione saltata)
+    #[default]
+    Auto,
+    /// Profilo completo sempre, anche per l'output sRGB
+    Always,
+    /// Come Always, ma per PNG sRGB scrive solo il chunk compatto `sRGB`
+    SrgbTag,
+    /// Nessun profilo
+    Never,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ExifOptions {
     pub preserve_all: bool,
+    /// Quali metadati del sorgente vengono copiati
+    #[serde(default)]
+    pub policy: MetadataPolicy,
     pub strip_gps: bool,
     pub strip_thumbnail: bool,
     pub update_software: bool,
     pub preserve_copyright: bool,
+    /// Rimuove la MakerNote (dati proprietari del produttore)
+    #[serde(default = "default_strip_maker_note")]
+    pub strip_maker_note: bool,
+    /// Precisione delle coordinate conservate (se il GPS non viene rimosso)
+    #[serde(default)]
+    pub gps_precision: GpsPrecision,
+    /// Nei PNG scrive anche Author/Copyright come chunk testuali (tEXt/iTXt)
+    #[serde(default)]
+    pub png_text_chunks: bool,
+    /// Campi scritti in ogni output, anche se il sorgente non li contiene
+    #[serde(default)]
+    pub template: MetadataTemplate,
+}
+
+fn default_strip_maker_note() -> bool {
+    true
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum WatermarkSource {
+    Image {
+        path: String,
+    }, // PNG con canale alpha
+    Text {
+        text: String,
+        #[serde(default = "default_watermark_color")]
+        color: [u8; 3],
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum WatermarkPosition {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    #[default]
+    BottomRight,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WatermarkOptions {
+    pub source: WatermarkSource,
+    #[serde(default)]
+    pub position: WatermarkPosition,
+    #[serde(default = "default_watermark_margin")]
+    pub margin: u32, // Distanza dai bordi in pixel
+    #[serde(default = "default_watermark_scale")]
+    pub scale: f32, // Larghezza relativa all'immagine (0.0-1.0)
+    #[serde(default = "default_watermark_opacity")]
+    pub opacity: f32, // 0.0 trasparente, 1.0 pieno
+    #[serde(default)]
+    pub tile: bool, // Ripete il watermark su tutta l'immagine
+}
+
+/// Regolazioni tonali e di colore (valori neutri = nessuna modifica)
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AdjustmentOptions {
+    pub brightness: f32, // -1.0..1.0, offset sui valori
+    pub contrast: f32,   // -1.0..1.0, attorno al grigio medio
+    pub saturation: f32, // -1.0 (grigio)..1.0 (doppia)
+    pub gamma: f32,      // 1.0 neutro, >1 schiarisce i mezzitoni
+    pub grayscale: bool,
+    pub sepia: bool,
+    pub auto_levels: bool, // Estende ogni canale all'intervallo completo
+}
+
+impl Default for AdjustmentOptions {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 0.0,
+            saturation: 0.0,
+            gamma: 1.0,
+            grayscale: false,
+            sepia: false,
+            auto_levels: false,
+        }
+    }
+}
+
+impl AdjustmentOptions {
+    /// Verifica se le regolazioni lasciano l'immagine invariata
+    pub fn is_identity(&self) -> bool {
+        self.brightness == 0.0
+            && self.contrast == 0.0
+            && self.saturation == 0.0
+            && self.gamma == 1.0
+            && !self.grayscale
+            && !self.sepia
+            && !self.auto_levels
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TrimOptions {
+    #[serde(default = "default_trim_tolerance")]
+    pub tolerance: u8, // Differenza massima per canale rispetto al colore del bordo
+    #[serde(default)]
+    pub padding: u32, // Pixel di bordo da conservare attorno al contenuto
+}
+
+fn default_trim_tolerance() -> u8 {
+    10
+}
+
+fn default_watermark_color() -> [u8; 3] {
+    [255, 255, 255]
+}
+
+fn default_watermark_margin() -> u32 {
+    24
+}
+
+fn default_watermark_scale() -> f32 {
+    0.2
+}
+
+fn default_watermark_opacity() -> f32 {
+    0.5
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -115,20 +332,100 @@ pub struct OptimizationOptions {
     pub resize: ResizePreset,
     pub destination: OutputDestination,
     pub color_intent: ColorConversionIntent,
+    /// Compensazione del punto nero (BPC) nelle conversioni colore
+    #[serde(default)]
+    pub black_point_compensation: bool,
+    /// Spazio colore di destinazione (default sRGB)
+    #[serde(default)]
+    pub output_color_space: OutputColorSpace,
+    /// Profilo CMYK (file ICC) per i JPEG CMYK senza profilo embedded
+    /// (se assente si usa il profilo offset patinato incorporato)
+    #[serde(default)]
+    pub default_cmyk_profile: Option<String>,
+    /// Modalità di embedding del profilo ICC
+    #[serde(default)]
+    pub icc_embedding: IccEmbedding,
     pub exif_options: ExifOptions, // NUOVO
+    /// Ridimensiona in luce lineare (evita di scurire testo e dettagli fini)
+    #[serde(default)]
+    pub linear_resize: bool,
+    /// Consente di ingrandire le immagini più piccole del target
+    #[serde(default)]
+    pub allow_upscale: bool,
+    /// Regolazioni tonali dopo la conversione colore (None = disattivate)
+    #[serde(default)]
+    pub adjustments: Option<AdjustmentOptions>,
+    /// Ritaglio automatico dei bordi uniformi prima del resize (None = disattivato)
+    #[serde(default)]
+    pub trim: Option<TrimOptions>,
+    /// Watermark applicato dopo il resize (None = disattivato)
+    #[serde(default)]
+    pub watermark: Option<WatermarkOptions>,
+    /// Colore di sfondo (RGB) sotto le aree trasparenti quando si esporta in JPEG
+    #[serde(default = "default_matte_color")]
+    pub matte_color: [u8; 3],
+}
+
+/// Opzioni sovrascrivibili per singolo file di un batch (None = valore del batch)
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct OptionOverrides {
+    #[serde(default)]
+    pub color_intent: Option<ColorConversionIntent>,
+    #[serde(default)]
+    pub black_point_compensation: Option<bool>,
+    #[serde(default)]
+    pub profile: Option<CompressionProfile>,
+    #[serde(default)]
+    pub resize: Option<ResizePreset>,
 }
 
-/// Applica il resize all'immagine se necessario
-pub fn apply_resize(img: &DynamicImage, resize: &ResizePreset) -> DynamicImage {
-    match resize.calculate_resize(img.width(), img.height()) {
+impl OptimizationOptions {
+    /// Opzioni del batch con gli override di un singolo file applicati
+    pub fn with_overrides(&self, overrides: &OptionOverrides) -> Self {
+        let mut options = self.clone();
+        if let Some(color_intent) = &overrides.color_intent {
+            options.color_intent = color_intent.clone();
+        }
+        if let Some(black_point_compensation) = overrides.black_point_compensation {
+            options.black_point_compensation = black_point_compensation;
+        }
+        if let Some(profile) = &overrides.profile {
+            options.profile = profile.clone();
+        }
+        if let Some(resize) = &overrides.resize {
+            options.resize = resize.clone();
+        }
+        options
+    }
+}
+
+fn default_matte_color() -> [u8; 3] {
+    [255, 255, 255]
+}
+
+/// Applica il resize all'immagine se necessario.
+/// Con `linear_light` il ricampionamento avviene in luce lineare a 32 bit float.
+pub fn apply_resize(
+    img: &DynamicImage,
+    resize: &ResizePreset,
+    linear_light: bool,
+    allow_upscale: bool,
+) -> DynamicImage {
+    match resize.calculate_resize(img.width(), img.height(), allow_upscale) {
         Some((new_width, new_height)) => {
             println!(
-                "Resizing from {}x{} to {}x{}",
+                "Resizing from {}x{} to {}x{}{}",
                 img.width(),
                 img.height(),
                 new_width,
-                new_height
+                new_height,
+                if linear_light { " (linear light)" } else { "" }
             );
+
+            if linear_light {
+                return resize_linear_light(img, new_width, new_height);
+            }
+
             // Usa Lanczos3 per la migliore qualità di ridimensionamento
             img.resize(new_width, new_height, image::imageops::FilterType::Lanczos3)
         }
@@ -139,22 +436,90 @@ pub fn apply_resize(img: &DynamicImage, resize: &ResizePreset) -> DynamicImage {
     }
 }
 
-/// Codifica un'immagine in un buffer di byte secondo le opzioni fornite.
-pub fn encode_image(img: &DynamicImage, options: &OptimizationOptions) -> Option<Vec<u8>> {
-    // Applica il resize se necessario
-    let img = apply_resize(img, &options.resize);
+/// Resize gamma-correct: sRGB -> lineare premoltiplicato -> Lanczos3 -> sRGB
+fn resize_linear_light(img: &DynamicImage, new_width: u32, new_height: u32) -> DynamicImage {
+    let linear = color_management::to_linear_premultiplied(img);
+    let resized = image::imageops::resize(
+        &linear,
+        new_width,
+        new_height,
+        image::imageops::FilterType::Lanczos3,
+    );
+    let encoded = DynamicImage::ImageRgba32F(color_management::from_linear_premultiplied(&resized));
+
+    // Riporta l'immagine al tipo colore di partenza
+    convert_to_color_type(encoded, img.color())
+}
+
+/// Converte un'immagine (tipicamente RGBA f32 di lavoro) nel tipo colore indicato
+pub fn convert_to_color_type(img: DynamicImage, color: ColorType) -> DynamicImage {
+    match color {
+        ColorType::L8 => DynamicImage::ImageLuma8(img.to_luma8()),
+        ColorType::La8 => DynamicImage::ImageLumaA8(img.to_luma_alpha8()),
+        ColorType::Rgb8 => DynamicImage::ImageRgb8(img.to_rgb8()),
+        ColorType::L16 => DynamicImage::ImageLuma16(img.to_luma16()),
+        ColorType::La16 => DynamicImage::ImageLumaA16(img.to_luma_alpha16()),
+        ColorType::Rgb16 => DynamicImage::ImageRgb16(img.to_rgb16()),
+        ColorType::Rgba16 => DynamicImage::ImageRgba16(img.to_rgba16()),
+        ColorType::Rgb32F => DynamicImage::ImageRgb32F(img.to_rgb32f()),
+        ColorType::Rgba32F => DynamicImage::ImageRgba32F(img.to_rgba32f()),
+        _ => DynamicImage::ImageRgba8(img.to_rgba8()),
+    }
+}
+
+/// Compone l'immagine sopra un colore di sfondo eliminando il canale alpha.
+/// Restituisce anche la frazione (0.0-1.0) di pixel che erano trasparenti.
+pub fn flatten_alpha(img: &DynamicImage, matte: [u8; 3]) -> (DynamicImage, f64) {
+    if !img.color().has_alpha() {
+        return (img.clone(), 0.0);
+    }
 
+    let rgba = img.to_rgba8();
+    let mut transparent_pixels = 0u64;
+
+    let rgb = image::RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let pixel = rgba.get_pixel(x, y);
+        let alpha = pixel[3] as u32;
+        if alpha < 255 {
+            transparent_pixels += 1;
+        }
+
+        let blend = |channel: usize| {
+            ((pixel[channel] as u32 * alpha + matte[channel] as u32 * (255 - alpha) + 127) / 255)
+                as u8
+        };
+        image::Rgb([blend(0), blend(1), blend(2)])
+    });
+
+    let total_pixels = (rgba.width() as u64 * rgba.height() as u64).max(1);
+    (
+        DynamicImage::ImageRgb8(rgb),
+        transparent_pixels as f64 / total_pixels as f64,
+    )
+}
+
+/// Codifica un'immagine in un buffer di byte secondo le opzioni fornite.
+/// L'immagine deve essere già ridimensionata (`apply_resize`): il resize non
+/// viene ripetuto qui. Se presente, il profilo ICC viene embedded nel file.
+pub fn encode_image(
+    img: &DynamicImage,
+    options: &OptimizationOptions,
+    icc_profile: Option<&[u8]>,
+) -> Option<Vec<u8>> {
     match options.format {
         OutputFormat::Jpeg => {
+            let (img, _) = flatten_alpha(img, options.matte_color);
             let mut buffer = Cursor::new(Vec::new());
             let quality = match options.profile {
                 CompressionProfile::SmallestFile => 60,
                 CompressionProfile::Balanced => 75,
                 CompressionProfile::BestQuality | CompressionProfile::Lossless => 90,
             };
-            codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, quality)
-                .encode_image(&img)
-                .ok()?;
+            let mut encoder = codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, quality);
+            if let Some(icc) = icc_profile {
+                encoder.set_icc_profile(icc.to_vec()).ok()?;
+            }
+            encoder.encode_image(&img).ok()?;
             Some(buffer.into_inner())
         }
         OutputFormat::Png => match options.profile {
@@ -190,7 +555,9 @@ pub fn encode_image(img: &DynamicImage, options: &OptimizationOptions) -> Option
                 let (palette, pixels) = quantization_result.remapped(&mut liq_image).ok()?;
 
                 let mut buffer = Cursor::new(Vec::new());
-                let mut encoder = png::Encoder::new(&mut buffer, img.width(), img.height());
+                let mut info = png::Info::with_size(img.width(), img.height());
+                info.icc_profile = icc_profile.map(|icc| icc.to_vec().into());
+                let mut encoder = png::Encoder::with_info(&mut buffer, info).ok()?;
 
                 let palette_rgb: Vec<u8> =
                     palette.iter().flat_map(|c| vec![c.r, c.g, c.b]).collect();
@@ -205,7 +572,18 @@ pub fn encode_image(img: &DynamicImage, options: &OptimizationOptions) -> Option
             }
             CompressionProfile::Lossless => {
                 let mut buffer = Cursor::new(Vec::new());
-                img.write_to(&mut buffer, ImageFormat::Png).ok()?;
+                let mut encoder = codecs::png::PngEncoder::new(&mut buffer);
+                if let Some(icc) = icc_profile {
+                    encoder.set_icc_profile(icc.to_vec()).ok()?;
+                }
+                encoder
+                    .write_image(
+                        img.as_bytes(),
+                        img.width(),
+                        img.height(),
+                        img.color().into(),
+                    )
+                    .ok()?;
 
                 let oxipng_options = oxipng::Options::from_preset(2);
                 oxipng::optimize_from_memory(buffer.get_ref(), &oxipng_options).ok()
@@ -213,14 +591,14 @@ pub fn encode_image(img: &DynamicImage, options: &OptimizationOptions) -> Option
         },
         OutputFormat::Webp => {
             let rgba_image = img.to_rgba8();
-            match options.profile {
+            let encoded = match options.profile {
                 CompressionProfile::Lossless => {
                     let encoder = webp::Encoder::from_rgba(
                         &rgba_image,
                         rgba_image.width(),
                         rgba_image.height(),
                     );
-                    Some(encoder.encode_lossless().to_vec())
+                    encoder.encode_lossless().to_vec()
                 }
                 _ => {
                     let quality = match options.profile {
@@ -234,9 +612,225 @@ pub fn encode_image(img: &DynamicImage, options: &OptimizationOptions) -> Option
                         rgba_image.width(),
                         rgba_image.height(),
                     );
-                    Some(encoder.encode(quality).to_vec())
+                    encoder.encode(quality).to_vec()
                 }
+            };
+
+            match icc_profile {
+                Some(icc) => IccWriter::embed_in_webp(&encoded, icc).ok(),
+                None => Some(encoded),
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_resize_preserves_brightness() {
+        // Scacchiera bianco/nero: la media corretta in luce lineare è ~188, non 128
+        let checker = image::RgbImage::from_fn(64, 64, |x, y| {
+            if (x + y) % 2 == 0 {
+                image::Rgb([255, 255, 255])
+            } else {
+                image::Rgb([0, 0, 0])
+            }
+        });
+        let img = DynamicImage::ImageRgb8(checker);
+        let preset = ResizePreset::Custom {
+            width: 8,
+            height: 8,
+        };
+
+        let gamma = apply_resize(&img, &preset, false, false).to_rgb8();
+        let linear = apply_resize(&img, &preset, true, false).to_rgb8();
+
+        assert_eq!(linear.dimensions(), (8, 8));
+        assert!(gamma.get_pixel(4, 4)[0] < 140);
+        assert!(linear.get_pixel(4, 4)[0] > 180);
+    }
+
+    #[test]
+    fn test_linear_resize_keeps_color_type() {
+        let img = DynamicImage::new_rgba8(100, 50);
+        let preset = ResizePreset::Custom {
+            width: 10,
+            height: 10,
+        };
+
+        let resized = apply_resize(&img, &preset, true, false);
+        assert_eq!(resized.color(), ColorType::Rgba8);
+        assert_eq!((resized.width(), resized.height()), (10, 5));
+    }
+
+    #[test]
+    fn test_box_presets_follow_orientation() {
+        // Un ritratto 4000x6000 in FullHD deve diventare 1080x1620, non 720x1080
+        assert_eq!(
+            ResizePreset::FullHD.calculate_resize(4000, 6000, false),
+            Some((1080, 1620))
+        );
+        assert_eq!(
+            ResizePreset::FullHD.calculate_resize(6000, 4000, false),
+            Some((1620, 1080))
+        );
+        assert_eq!(
+            ResizePreset::FullHD.calculate_resize(1000, 1500, false),
+            None
+        );
+    }
+
+    #[test]
+    fn test_relative_resize_specs() {
+        assert_eq!(
+            ResizePreset::Percent(50.0).calculate_resize(4000, 3000, false),
+            Some((2000, 1500))
+        );
+        assert_eq!(
+            ResizePreset::LongEdge(1000).calculate_resize(3000, 4000, false),
+            Some((750, 1000))
+        );
+        assert_eq!(
+            ResizePreset::ShortEdge(1000).calculate_resize(3000, 4000, false),
+            Some((1000, 1333))
+        );
+        assert_eq!(
+            ResizePreset::MaxMegapixels(3.0).calculate_resize(4000, 3000, false),
+            Some((2000, 1500))
+        );
+        assert_eq!(
+            ResizePreset::Width(800).calculate_resize(1600, 900, false),
+            Some((800, 450))
+        );
+        assert_eq!(
+            ResizePreset::Height(450).calculate_resize(1600, 900, false),
+            Some((800, 450))
+        );
+        assert_eq!(
+            ResizePreset::Percent(0.0).calculate_resize(100, 100, false),
+            None
+        );
+    }
+
+    #[test]
+    fn test_upscale_is_opt_in() {
+        let preset = ResizePreset::LongEdge(2000);
+        assert_eq!(preset.calculate_resize(1000, 500, false), None);
+        assert_eq!(preset.calculate_resize(1000, 500, true), Some((2000, 1000)));
+
+        // Anche con upscale il lato lungo resta entro il limite
+        let huge = ResizePreset::Percent(1000.0).calculate_resize(4000, 2000, true);
+        assert_eq!(huge, Some((MAX_OUTPUT_DIMENSION, MAX_OUTPUT_DIMENSION / 2)));
+    }
+
+    #[test]
+    fn test_resize_preset_serialization() {
+        let preset: ResizePreset = serde_json::from_str(r#"{"longEdge":2048}"#).unwrap();
+        assert!(matches!(preset, ResizePreset::LongEdge(2048)));
+
+        let preset: ResizePreset = serde_json::from_str(r#""fullHD""#).unwrap();
+        assert!(matches!(preset, ResizePreset::FullHD));
+    }
+
+    #[test]
+    fn test_option_overrides() {
+        let options: OptimizationOptions = serde_json::from_str(
+            r#"{
+                "format": "jpeg",
+                "profile": "balanced",
+                "resize": "none",
+                "destination": { "type": "sameFolder" },
+                "color_intent": "relativeColorimetric",
+                "exif_options": {
+                    "preserveAll": true,
+                    "stripGps": false,
+                    "stripThumbnail": true,
+                    "updateSoftware": true,
+                    "preserveCopyright": true
+                }
+            }"#,
+        )
+        .unwrap();
+        assert!(!options.black_point_compensation);
+
+        let overrides: OptionOverrides = serde_json::from_str(
+            r#"{ "color_intent": "perceptual", "black_point_compensation": true }"#,
+        )
+        .unwrap();
+        let overridden = options.with_overrides(&overrides);
+
+        assert!(matches!(
+            overridden.color_intent,
+            ColorConversionIntent::Perceptual
+        ));
+        assert!(overridden.black_point_compensation);
+        // Le opzioni non sovrascritte restano quelle del batch
+        assert!(matches!(overridden.profile, CompressionProfile::Balanced));
+        assert!(matches!(
+            options.color_intent,
+            ColorConversionIntent::RelativeColorimetric
+        ));
+    }
+
+    #[test]
+    fn test_png_percent_resize_applied_once() {
+        let mut options: OptimizationOptions = serde_json::from_str(
+            r#"{
+                "format": "png",
+                "profile": "balanced",
+                "resize": "none",
+                "destination": { "type": "sameFolder" },
+                "color_intent": "perceptual",
+                "exif_options": {
+                    "preserveAll": true,
+                    "stripGps": false,
+                    "stripThumbnail": true,
+                    "updateSoftware": true,
+                    "preserveCopyright": true
+                }
+            }"#,
+        )
+        .unwrap();
+        options.resize = ResizePreset::Percent(50.0);
+
+        // Stessa sequenza della pipeline: resize, poi encoding
+        let img = DynamicImage::new_rgb8(100, 100);
+        let resized = apply_resize(
+            &img,
+            &options.resize,
+            options.linear_resize,
+            options.allow_upscale,
+        );
+        let png = encode_image(&resized, &options, None).unwrap();
+
+        let decoded = image::load_from_memory(&png).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (50, 50));
+    }
+
+    #[test]
+    fn test_flatten_alpha_uses_matte() {
+        let mut rgba = image::RgbaImage::new(4, 1);
+        rgba.put_pixel(0, 0, image::Rgba([0, 0, 0, 0]));
+        rgba.put_pixel(1, 0, image::Rgba([0, 0, 0, 128]));
+        rgba.put_pixel(2, 0, image::Rgba([10, 20, 30, 255]));
+        rgba.put_pixel(3, 0, image::Rgba([10, 20, 30, 255]));
+
+        let (flat, transparent) = flatten_alpha(&DynamicImage::ImageRgba8(rgba), [255, 0, 0]);
+        let rgb = flat.to_rgb8();
+
+        assert_eq!(flat.color(), ColorType::Rgb8);
+        assert_eq!(rgb.get_pixel(0, 0), &image::Rgb([255, 0, 0]));
+        assert_eq!(rgb.get_pixel(1, 0), &image::Rgb([127, 0, 0]));
+        assert_eq!(rgb.get_pixel(2, 0), &image::Rgb([10, 20, 30]));
+        assert!((transparent - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_flatten_alpha_opaque_image() {
+        let (flat, transparent) = flatten_alpha(&DynamicImage::new_rgb8(2, 2), [255, 255, 255]);
+        assert_eq!(flat.color(), ColorType::Rgb8);
+        assert_eq!(transparent, 0.0);
+    }
+}
@@ -1,5 +1,7 @@
 // src-tauri/src/core/thumbnail.rs
 
+use crate::core::exif_handler::ExifHandler;
+use crate::core::image_decoder;
 use image::{imageops::FilterType, DynamicImage, GenericImageView, ImageFormat};
 use std::collections::hash_map::DefaultHasher;
 use std::fs;
@@ -125,6 +127,12 @@ impl ThumbnailCache {
         // Carica l'immagine originale con gestione errori robusta
         let img = image::open(path).map_err(|e| format!("Failed to open image: {}", e))?;
 
+        // Raddrizza le foto scattate in verticale secondo il tag EXIF
+        let img = match ExifHandler::read_orientation(path) {
+            Some(orientation) => image_decoder::apply_exif_orientation(img, orientation),
+            None => img,
+        };
+
         // Validazione dimensioni
         let (width, height) = img.dimensions();
         if width == 0 || height == 0 {
@@ -0,0 +1,802 @@
+// src-tauri/src/core/tiff.rs
+
+use std::collections::HashSet;
+
+// Tipi TIFF
+pub const TYPE_BYTE: u16 = 1;
+pub const TYPE_ASCII: u16 = 2;
+pub const TYPE_SHORT: u16 = 3;
+pub const TYPE_LONG: u16 = 4;
+pub const TYPE_RATIONAL: u16 = 5;
+pub const TYPE_SBYTE: u16 = 6;
+pub const TYPE_UNDEFINED: u16 = 7;
+pub const TYPE_SSHORT: u16 = 8;
+pub const TYPE_SLONG: u16 = 9;
+pub const TYPE_SRATIONAL: u16 = 10;
+pub const TYPE_FLOAT: u16 = 11;
+pub const TYPE_DOUBLE: u16 = 12;
+pub const TYPE_IFD: u16 = 13;
+
+// Tag puntatore: vengono rigenerati in scrittura con gli offset nuovi
+pub const TAG_EXIF_IFD: u16 = 0x8769;
+pub const TAG_GPS_IFD: u16 = 0x8825;
+pub const TAG_INTEROP_IFD: u16 = 0xA005;
+pub const TAG_THUMBNAIL_OFFSET: u16 = 0x0201;
+pub const TAG_THUMBNAIL_LENGTH: u16 = 0x0202;
+
+// Tag modificati durante la copia
+pub const TAG_IMAGE_DESCRIPTION: u16 = 0x010E;
+pub const TAG_ORIENTATION: u16 = 0x0112;
+pub const TAG_SOFTWARE: u16 = 0x0131;
+pub const TAG_ARTIST: u16 = 0x013B;
+pub const TAG_COPYRIGHT: u16 = 0x8298;
+pub const TAG_MAKER_NOTE: u16 = 0x927C;
+pub const TAG_PIXEL_X_DIMENSION: u16 = 0xA002;
+pub const TAG_PIXEL_Y_DIMENSION: u16 = 0xA003;
+
+// Tag della GPS IFD
+pub const TAG_GPS_VERSION_ID: u16 = 0x0000;
+pub const TAG_GPS_LATITUDE_REF: u16 = 0x0001;
+pub const TAG_GPS_LATITUDE: u16 = 0x0002;
+pub const TAG_GPS_LONGITUDE_REF: u16 = 0x0003;
+pub const TAG_GPS_LONGITUDE: u16 = 0x0004;
+pub const TAG_GPS_ALTITUDE_REF: u16 = 0x0005;
+pub const TAG_GPS_ALTITUDE: u16 = 0x0006;
+
+const TAG_COMPRESSION: u16 = 0x0103;
+/// Tag che contengono offset verso altri dati (non rilocabili come valori grezzi)
+const TAG_STRIP_OFFSETS: u16 = 0x0111;
+const TAG_TILE_OFFSETS: u16 = 0x0144;
+
+const TIFF_HEADER_SIZE: usize = 8;
+const IFD_ENTRY_SIZE: usize = 12;
+
+/// Dimensione in byte di un valore del tipo TIFF indicato
+pub fn type_size(field_type: u16) -> Option<usize> {
+    match field_type {
+        TYPE_BYTE | TYPE_ASCII | TYPE_SBYTE | TYPE_UNDEFINED => Some(1),
+        TYPE_SHORT | TYPE_SSHORT => Some(2),
+        TYPE_LONG | TYPE_SLONG | TYPE_FLOAT | TYPE_IFD => Some(4),
+        TYPE_RATIONAL | TYPE_SRATIONAL | TYPE_DOUBLE => Some(8),
+        _ => None,
+    }
+}
+
+/// Dimensione dei numeri che compongono un valore (i RATIONAL sono due LONG):
+/// è l'unità da invertire quando cambia l'ordine dei byte
+fn component_size(field_type: u16) -> usize {
+    match field_type {
+        TYPE_SHORT | TYPE_SSHORT => 2,
+        TYPE_LONG | TYPE_SLONG | TYPE_FLOAT | TYPE_IFD | TYPE_RATIONAL | TYPE_SRATIONAL => 4,
+        TYPE_DOUBLE => 8,
+        _ => 1,
+    }
+}
+
+/// Ordine dei byte del TIFF ("II" little-endian, "MM" big-endian)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteOrder {
+    LittleEndian,
+    BigEndian,
+}
+
+impl ByteOrder {
+    pub fn read_u16(self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        match self {
+            ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+        }
+    }
+
+    pub fn read_u32(self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        match self {
+            ByteOrder::LittleEndian => u32::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u32::from_be_bytes(bytes),
+        }
+    }
+
+    pub fn u16_bytes(self, value: u16) -> [u8; 2] {
+        match self {
+            ByteOrder::LittleEndian => value.to_le_bytes(),
+            ByteOrder::BigEndian => value.to_be_bytes(),
+        }
+    }
+
+    pub fn u32_bytes(self, value: u32) -> [u8; 4] {
+        match self {
+            ByteOrder::LittleEndian => value.to_le_bytes(),
+            ByteOrder::BigEndian => value.to_be_bytes(),
+        }
+    }
+}
+
+/// Valore tipizzato di un tag TIFF
+#[derive(Clone, Debug, PartialEq)]
+pub enum TiffValue {
+    Byte(Vec<u8>),
+    /// Stringa senza terminatore (il NUL viene aggiunto in scrittura)
+    Ascii(String),
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    Rational(Vec<(u32, u32)>),
+    SRational(Vec<(i32, i32)>),
+    Undefined(Vec<u8>),
+}
+
+impl TiffValue {
+    pub fn field_type(&self) -> u16 {
+        match self {
+            TiffValue::Byte(_) => TYPE_BYTE,
+            TiffValue::Ascii(_) => TYPE_ASCII,
+            TiffValue::Short(_) => TYPE_SHORT,
+            TiffValue::Long(_) => TYPE_LONG,
+            TiffValue::Rational(_) => TYPE_RATIONAL,
+            TiffValue::SRational(_) => TYPE_SRATIONAL,
+            TiffValue::Undefined(_) => TYPE_UNDEFINED,
+        }
+    }
+
+    /// Numero di valori e byte codificati nell'ordine indicato
+    fn encode(&self, order: ByteOrder) -> (u32, Vec<u8>) {
+        match self {
+            TiffValue::Byte(bytes) | TiffValue::Undefined(bytes) => {
+                (bytes.len() as u32, bytes.clone())
+            }
+            TiffValue::Ascii(text) => {
+                let mut data = text.as_bytes().to_vec();
+                data.push(0);
+                (data.len() as u32, data)
+            }
+            TiffValue::Short(values) => (
+                values.len() as u32,
+                values.iter().flat_map(|&v| order.u16_bytes(v)).collect(),
+            ),
+            TiffValue::Long(values) => (
+                values.len() as u32,
+                values.iter().flat_map(|&v| order.u32_bytes(v)).collect(),
+            ),
+            TiffValue::Rational(values) => (
+                values.len() as u32,
+                values
+                    .iter()
+                    .flat_map(|&(num, denom)| [order.u32_bytes(num), order.u32_bytes(denom)])
+                    .flatten()
+                    .collect(),
+            ),
+            TiffValue::SRational(values) => (
+                values.len() as u32,
+                values
+                    .iter()
+                    .flat_map(|&(num, denom)| {
+                        [order.u32_bytes(num as u32), order.u32_bytes(denom as u32)]
+                    })
+                    .flatten()
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Entry IFD con il valore grezzo, nell'ordine dei byte del TIFF
+#[derive(Clone, Debug, PartialEq)]
+pub struct IfdEntry {
+    pub tag: u16,
+    pub field_type: u16,
+    pub count: u32,
+    pub data: Vec<u8>,
+}
+
+impl IfdEntry {
+    /// Entry da un valore tipizzato, codificato nell'ordine di byte indicato
+    pub fn new(tag: u16, value: &TiffValue, order: ByteOrder) -> Self {
+        let (count, data) = value.encode(order);
+        Self {
+            tag,
+            field_type: value.field_type(),
+            count,
+            data,
+        }
+    }
+
+    /// Inverte l'ordine dei byte di ogni numero del valore
+    fn swap_byte_order(&mut self) {
+        let size = component_size(self.field_type);
+        if size > 1 {
+            for component in self.data.chunks_exact_mut(size) {
+                component.reverse();
+            }
+        }
+    }
+}
+
+/// IFD: lista di entry (i puntatori alle sotto-IFD sono gestiti da `ExifTree`)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ifd {
+    pub entries: Vec<IfdEntry>,
+}
+
+impl Ifd {
+    pub fn get(&self, tag: u16) -> Option<&IfdEntry> {
+        self.entries.iter().find(|entry| entry.tag == tag)
+    }
+
+    pub fn remove(&mut self, tag: u16) -> Option<IfdEntry> {
+        let index = self.entries.iter().position(|entry| entry.tag == tag)?;
+        Some(self.entries.remove(index))
+    }
+
+    /// Inserisce o sostituisce un'entry mantenendo l'ordine per tag
+    pub fn set(&mut self, entry: IfdEntry) {
+        match self.entries.binary_search_by_key(&entry.tag, |e| e.tag) {
+            Ok(index) => self.entries[index] = entry,
+            Err(index) => self.entries.insert(index, entry),
+        }
+    }
+}
+
+/// Struttura EXIF completa: IFD0, sotto-IFD Exif/GPS/Interop e IFD1 con la thumbnail
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExifTree {
+    pub byte_order: ByteOrder,
+    pub ifd0: Ifd,
+    pub exif: Option<Ifd>,
+    pub gps: Option<Ifd>,
+    pub interop: Option<Ifd>,
+    pub ifd1: Option<Ifd>,
+    pub thumbnail: Option<Vec<u8>>,
+}
+
+impl ExifTree {
+    /// Struttura vuota con il solo IFD0
+    pub fn new(byte_order: ByteOrder) -> Self {
+        Self {
+            byte_order,
+            ifd0: Ifd::default(),
+            exif: None,
+            gps: None,
+            interop: None,
+            ifd1: None,
+            thumbnail: None,
+        }
+    }
+
+    /// Legge la struttura da un blocco TIFF (il contenuto di APP1 dopo "Exif\0\0")
+    pub fn parse(tiff: &[u8]) -> Result<Self, String> {
+        if tiff.len() < TIFF_HEADER_SIZE {
+            return Err("TIFF header too short".to_string());
+        }
+
+        let byte_order = match &tiff[0..2] {
+            b"II" => ByteOrder::LittleEndian,
+            b"MM" => ByteOrder::BigEndian,
+            _ => return Err("Invalid TIFF byte order".to_string()),
+        };
+        if byte_order.read_u16(&tiff[2..4]) != 42 {
+            return Err("Invalid TIFF magic number".to_string());
+        }
+
+        let mut reader = IfdReader {
+            tiff,
+            byte_order,
+            visited: HashSet::new(),
+        };
+
+        let ifd0_offset = byte_order.read_u32(&tiff[4..8]) as usize;
+        let (mut ifd0, ifd1_offset) = reader.read_ifd(ifd0_offset)?;
+
+        let exif_offset = take_pointer(&mut ifd0, TAG_EXIF_IFD, byte_order);
+        let gps_offset = take_pointer(&mut ifd0, TAG_GPS_IFD, byte_order);
+
+        let mut exif = exif_offset
+            .map(|offset| reader.read_ifd(offset).map(|(ifd, _)| ifd))
+            .transpose()?;
+        let interop_offset = exif
+            .as_mut()
+            .and_then(|exif| take_pointer(exif, TAG_INTEROP_IFD, byte_order));
+        let interop = interop_offset
+            .map(|offset| reader.read_ifd(offset).map(|(ifd, _)| ifd))
+            .transpose()?;
+        let gps = gps_offset
+            .map(|offset| reader.read_ifd(offset).map(|(ifd, _)| ifd))
+            .transpose()?;
+
+        // IFD1 danneggiata o non rilocabile: si perde solo la thumbnail
+        let (ifd1, thumbnail) = match ifd1_offset {
+            Some(offset) => reader.read_thumbnail_ifd(offset).unwrap_or_else(|e| {
+                eprintln!("⚠ Skipping EXIF thumbnail: {}", e);
+                (None, None)
+            }),
+            None => (None, None),
+        };
+
+        Ok(Self {
+            byte_order,
+            ifd0,
+            exif,
+            gps,
+            interop,
+            ifd1,
+            thumbnail,
+        })
+    }
+
+    /// Rimuove la IFD GPS
+    pub fn remove_gps(&mut self) {
+        self.gps = None;
+    }
+
+    /// Rimuove IFD1 e la thumbnail JPEG
+    pub fn remove_thumbnail(&mut self) {
+        self.ifd1 = None;
+        self.thumbnail = None;
+    }
+
+    /// Rimuove la MakerNote (dati proprietari del produttore) dalla IFD Exif
+    pub fn remove_maker_note(&mut self) {
+        if let Some(exif) = &mut self.exif {
+            exif.remove(TAG_MAKER_NOTE);
+        }
+    }
+
+    /// Entry da un valore tipizzato nell'ordine dei byte di questo TIFF
+    pub fn entry(&self, tag: u16, value: TiffValue) -> IfdEntry {
+        IfdEntry::new(tag, &value, self.byte_order)
+    }
+
+    /// Imposta la thumbnail JPEG di IFD1 (creando IFD1 se manca)
+    pub fn set_thumbnail(&mut self, jpeg: Vec<u8>) {
+        let compression = self.entry(TAG_COMPRESSION, TiffValue::Short(vec![6]));
+        self.ifd1.get_or_insert_with(Ifd::default).set(compression);
+        self.thumbnail = Some(jpeg);
+    }
+
+    /// Converte tutti i valori in un altro ordine dei byte.
+    /// I dati UNDEFINED (es. MakerNote) restano invariati.
+    pub fn set_byte_order(&mut self, byte_order: ByteOrder) {
+        if byte_order == self.byte_order {
+            return;
+        }
+
+        let ifds = [
+            Some(&mut self.ifd0),
+            self.exif.as_mut(),
+            self.gps.as_mut(),
+            self.interop.as_mut(),
+            self.ifd1.as_mut(),
+        ];
+        for ifd in ifds.into_iter().flatten() {
+            for entry in &mut ifd.entries {
+                entry.swap_byte_order();
+            }
+        }
+        self.byte_order = byte_order;
+    }
+
+    /// Serializza la struttura ricalcolando tutti gli offset.
+    /// I valori vengono copiati byte per byte: la MakerNote resta invariata,
+    /// ma i produttori che usano offset assoluti al suo interno possono risultare spostati.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let order = self.byte_order;
+        let mut out = Vec::new();
+        out.extend_from_slice(match order {
+            ByteOrder::LittleEndian => b"II",
+            ByteOrder::BigEndian => b"MM",
+        });
+        out.extend_from_slice(&order.u16_bytes(42));
+        out.extend_from_slice(&order.u32_bytes(TIFF_HEADER_SIZE as u32));
+
+        // IFD0 con i puntatori (placeholder) verso le sotto-IFD
+        let mut ifd0 = self.ifd0.clone();
+        if self.exif.is_some() {
+            ifd0.set(self.entry(TAG_EXIF_IFD, TiffValue::Long(vec![0])));
+        }
+        if self.gps.is_some() {
+            ifd0.set(self.entry(TAG_GPS_IFD, TiffValue::Long(vec![0])));
+        }
+        let ifd0_layout = write_ifd(&mut out, &ifd0, order);
+
+        if let Some(exif) = &self.exif {
+            let mut exif = exif.clone();
+            if self.interop.is_some() {
+                exif.set(self.entry(TAG_INTEROP_IFD, TiffValue::Long(vec![0])));
+            }
+            let exif_offset = align(&mut out);
+            let exif_layout = write_ifd(&mut out, &exif, order);
+            ifd0_layout.patch(&mut out, TAG_EXIF_IFD, exif_offset, order);
+
+            if let Some(interop) = &self.interop {
+                let interop_offset = align(&mut out);
+                write_ifd(&mut out, interop, order);
+                exif_layout.patch(&mut out, TAG_INTEROP_IFD, interop_offset, order);
+            }
+        }
+
+        if let Some(gps) = &self.gps {
+            let gps_offset = align(&mut out);
+            write_ifd(&mut out, gps, order);
+            ifd0_layout.patch(&mut out, TAG_GPS_IFD, gps_offset, order);
+        }
+
+        if let (Some(ifd1), Some(thumbnail)) = (&self.ifd1, &self.thumbnail) {
+            let mut ifd1 = ifd1.clone();
+            ifd1.set(self.entry(TAG_THUMBNAIL_OFFSET, TiffValue::Long(vec![0])));
+            ifd1.set(self.entry(
+                TAG_THUMBNAIL_LENGTH,
+                TiffValue::Long(vec![thumbnail.len() as u32]),
+            ));
+
+            let ifd1_offset = align(&mut out);
+            let ifd1_layout = write_ifd(&mut out, &ifd1, order);
+            out[ifd0_layout.next_ifd..ifd0_layout.next_ifd + 4]
+                .copy_from_slice(&order.u32_bytes(ifd1_offset));
+
+            let thumbnail_offset = out.len() as u32;
+            out.extend_from_slice(thumbnail);
+            ifd1_layout.patch(&mut out, TAG_THUMBNAIL_OFFSET, thumbnail_offset, order);
+        }
+
+        out
+    }
+}
+
+/// Lettore delle IFD con protezione da offset circolari
+struct IfdReader<'a> {
+    tiff: &'a [u8],
+    byte_order: ByteOrder,
+    visited: HashSet<usize>,
+}
+
+impl IfdReader<'_> {
+    /// Legge un'IFD e restituisce anche l'offset della successiva (se presente)
+    fn read_ifd(&mut self, offset: usize) -> Result<(Ifd, Option<usize>), String> {
+        if !self.visited.insert(offset) {
+            return Err(format!("Circular IFD reference at offset {}", offset));
+        }
+
+        let order = self.byte_order;
+        let count_bytes = self
+            .tiff
+            .get(offset..offset + 2)
+            .ok_or_else(|| format!("IFD offset {} out of bounds", offset))?;
+        let count = order.read_u16(count_bytes) as usize;
+
+        let entries_end = offset + 2 + count * IFD_ENTRY_SIZE;
+        if entries_end + 4 > self.tiff.len() {
+            return Err(format!("IFD at offset {} is truncated", offset));
+        }
+
+        let mut ifd = Ifd::default();
+        for index in 0..count {
+            let start = offset + 2 + index * IFD_ENTRY_SIZE;
+            let raw = &self.tiff[start..start + IFD_ENTRY_SIZE];
+
+            let tag = order.read_u16(&raw[0..2]);
+            let field_type = order.read_u16(&raw[2..4]);
+            let value_count = order.read_u32(&raw[4..8]);
+
+            // Tipi sconosciuti: impossibile sapere quanti byte copiare
+            let Some(size) =
+                type_size(field_type).and_then(|size| size.checked_mul(value_count as usize))
+            else {
+                continue;
+            };
+
+            let data = if size <= 4 {
+                raw[8..8 + size].to_vec()
+            } else {
+                let value_offset = order.read_u32(&raw[8..12]) as usize;
+                match self
+                    .tiff
+                    .get(value_offset..value_offset.saturating_add(size))
+                {
+                    Some(data) => data.to_vec(),
+                    None => {
+                        eprintln!("⚠ Skipping EXIF tag 0x{:04X}: value out of bounds", tag);
+                        continue;
+                    }
+                }
+            };
+
+            ifd.set(IfdEntry {
+                tag,
+                field_type,
+                count: value_count,
+                data,
+            });
+        }
+
+        let next_offset = order.read_u32(&self.tiff[entries_end..entries_end + 4]) as usize;
+        Ok((ifd, (next_offset != 0).then_some(next_offset)))
+    }
+
+    /// Legge IFD1 e i byte della thumbnail JPEG a cui punta
+    fn read_thumbnail_ifd(
+        &mut self,
+        offset: usize,
+    ) -> Result<(Option<Ifd>, Option<Vec<u8>>), String> {
+        let (mut ifd1, _) = self.read_ifd(offset)?;
+
+        // Le thumbnail non compresse (strip/tile) non sono rilocabili
+        if ifd1.get(TAG_STRIP_OFFSETS).is_some() || ifd1.get(TAG_TILE_OFFSETS).is_some() {
+            return Err("uncompressed thumbnails are not supported".to_string());
+        }
+
+        let order = self.byte_order;
+        let thumbnail_offset = take_pointer(&mut ifd1, TAG_THUMBNAIL_OFFSET, order);
+        let thumbnail_length = take_pointer(&mut ifd1, TAG_THUMBNAIL_LENGTH, order);
+
+        match (thumbnail_offset, thumbnail_length) {
+            (Some(start), Some(length)) => {
+                let thumbnail = self
+                    .tiff
+                    .get(start..start.saturating_add(length))
+                    .ok_or_else(|| "thumbnail out of bounds".to_string())?;
+                Ok((Some(ifd1), Some(thumbnail.to_vec())))
+            }
+            _ => Err("IFD1 without JPEG thumbnail".to_string()),
+        }
+    }
+}
+
+/// Rimuove un tag puntatore (LONG o SHORT) e ne restituisce il valore
+fn take_pointer(ifd: &mut Ifd, tag: u16, order: ByteOrder) -> Option<usize> {
+    let entry = ifd.remove(tag)?;
+    let value = match (entry.field_type, entry.data.len()) {
+        (TYPE_SHORT, 2..) => order.read_u16(&entry.data) as usize,
+        (_, 4..) => order.read_u32(&entry.data) as usize,
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Posizioni da correggere dopo aver scritto un'IFD
+struct IfdLayout {
+    /// Posizione del campo valore di ogni entry
+    value_positions: Vec<(u16, usize)>,
+    /// Posizione del campo "offset IFD successiva"
+    next_ifd: usize,
+}
+
+impl IfdLayout {
+    fn patch(&self, out: &mut [u8], tag: u16, value: u32, order: ByteOrder) {
+        if let Some(&(_, position)) = self.value_positions.iter().find(|(t, _)| *t == tag) {
+            out[position..position + 4].copy_from_slice(&order.u32_bytes(value));
+        }
+    }
+}
+
+/// Allinea a una word (gli offset TIFF devono essere pari) e restituisce la posizione
+fn align(out: &mut Vec<u8>) -> u32 {
+    if out.len() % 2 == 1 {
+        out.push(0);
+    }
+    out.len() as u32
+}
+
+/// Scrive un'IFD seguita dai valori che non stanno nei 4 byte dell'entry
+fn write_ifd(out: &mut Vec<u8>, ifd: &Ifd, order: ByteOrder) -> IfdLayout {
+    let start = out.len();
+    let data_start = start + 2 + ifd.entries.len() * IFD_ENTRY_SIZE + 4;
+    let mut data: Vec<u8> = Vec::new();
+    let mut value_positions = Vec::with_capacity(ifd.entries.len());
+
+    out.extend_from_slice(&order.u16_bytes(ifd.entries.len() as u16));
+    for entry in &ifd.entries {
+        out.extend_from_slice(&order.u16_bytes(entry.tag));
+        out.extend_from_slice(&order.u16_bytes(entry.field_type));
+        out.extend_from_slice(&order.u32_bytes(entry.count));
+        value_positions.push((entry.tag, out.len()));
+
+        if entry.data.len() <= 4 {
+            let mut value = [0u8; 4];
+            value[..entry.data.len()].copy_from_slice(&entry.data);
+            out.extend_from_slice(&value);
+        } else {
+            let offset = (data_start + data.len()) as u32;
+            out.extend_from_slice(&order.u32_bytes(offset));
+            data.extend_from_slice(&entry.data);
+            if data.len() % 2 == 1 {
+                data.push(0);
+            }
+        }
+    }
+
+    let next_ifd = out.len();
+    out.extend_from_slice(&[0, 0, 0, 0]);
+    out.extend_from_slice(&data);
+
+    IfdLayout {
+        value_positions,
+        next_ifd,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// TIFF big-endian costruito a mano: IFD0 (Make fuori linea + puntatore Exif),
+    /// IFD Exif con MakerNote, IFD1 con una thumbnail di 4 byte
+    fn sample_tiff() -> Vec<u8> {
+        let mut tiff = b"MM\x00\x2A\x00\x00\x00\x08".to_vec();
+        // IFD0 a 8: 2 entry
+        tiff.extend_from_slice(&[0x00, 0x02]);
+        tiff.extend_from_slice(&[
+            0x01, 0x0F, 0x00, 0x02, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x4A,
+        ]);
+        tiff.extend_from_slice(&[
+            0x87, 0x69, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x26,
+        ]);
+        tiff.extend_from_slice(&[0x00, 0x00, 0x00, 0x50]); // IFD1 a 80
+                                                           // IFD Exif a 38: 1 entry (MakerNote, 4 byte inline)
+        tiff.extend_from_slice(&[0x00, 0x01]);
+        tiff.extend_from_slice(&[
+            0x92, 0x7C, 0x00, 0x07, 0x00, 0x00, 0x00, 0x04, b'N', b'O', b'T', b'E',
+        ]);
+        tiff.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+        // Padding fino a 74, poi "Canon\0"
+        tiff.resize(74, 0);
+        tiff.extend_from_slice(b"Canon\0");
+        // IFD1 a 80: offset e lunghezza della thumbnail
+        tiff.extend_from_slice(&[0x00, 0x02]);
+        tiff.extend_from_slice(&[
+            0x02, 0x01, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x6E,
+        ]);
+        tiff.extend_from_slice(&[
+            0x02, 0x02, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x04,
+        ]);
+        tiff.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+        // Thumbnail a 110
+        tiff.extend_from_slice(&[0xFF, 0xD8, 0xFF, 0xD9]);
+        tiff
+    }
+
+    #[test]
+    fn test_parse_sample() {
+        let tree = ExifTree::parse(&sample_tiff()).unwrap();
+
+        assert_eq!(tree.byte_order, ByteOrder::BigEndian);
+        assert_eq!(tree.ifd0.get(0x010F).unwrap().data, b"Canon\0");
+        assert!(tree.ifd0.get(TAG_EXIF_IFD).is_none());
+        assert_eq!(
+            tree.exif
+                .as_ref()
+                .unwrap()
+                .get(TAG_MAKER_NOTE)
+                .unwrap()
+                .data,
+            b"NOTE"
+        );
+        assert_eq!(
+            tree.thumbnail.as_deref(),
+            Some(&[0xFF, 0xD8, 0xFF, 0xD9][..])
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_relocates_offsets() {
+        let tree = ExifTree::parse(&sample_tiff()).unwrap();
+        let written = tree.to_bytes();
+
+        // La struttura riletta è identica, anche se gli offset sono cambiati
+        assert_eq!(ExifTree::parse(&written).unwrap(), tree);
+
+        // Leggibile anche da kamadak-exif
+        let exif = exif::Reader::new().read_raw(written).unwrap();
+        let make = exif.get_field(exif::Tag::Make, exif::In::PRIMARY).unwrap();
+        assert_eq!(make.display_value().to_string(), "\"Canon\"");
+    }
+
+    #[test]
+    fn test_surgical_removal() {
+        let mut tree = ExifTree::parse(&sample_tiff()).unwrap();
+        tree.remove_maker_note();
+        tree.remove_thumbnail();
+
+        let reparsed = ExifTree::parse(&tree.to_bytes()).unwrap();
+        assert!(reparsed.exif.unwrap().get(TAG_MAKER_NOTE).is_none());
+        assert!(reparsed.ifd1.is_none());
+        assert!(reparsed.thumbnail.is_none());
+        assert_eq!(reparsed.ifd0.get(0x010F).unwrap().data, b"Canon\0");
+    }
+
+    /// Struttura con tutti i tipi supportati, le tre sotto-IFD e la thumbnail
+    fn typed_tree(byte_order: ByteOrder) -> ExifTree {
+        let mut tree = ExifTree::new(byte_order);
+        let model = tree.entry(0x0110, TiffValue::Ascii("Canon EOS 5D Mark IV".to_string()));
+        let x_resolution = tree.entry(0x011A, TiffValue::Rational(vec![(300, 1)]));
+        tree.ifd0.set(model);
+        tree.ifd0.set(x_resolution);
+
+        let mut exif = Ifd::default();
+        exif.set(tree.entry(0x8827, TiffValue::Short(vec![400]))); // ISO
+        exif.set(tree.entry(0x9204, TiffValue::SRational(vec![(-2, 3)]))); // ExposureBias
+        exif.set(tree.entry(0x9000, TiffValue::Undefined(b"0232".to_vec()))); // ExifVersion
+        exif.set(tree.entry(0xA002, TiffValue::Long(vec![6000]))); // PixelXDimension
+        let mut gps = Ifd::default();
+        gps.set(tree.entry(0x0000, TiffValue::Byte(vec![2, 3, 0, 0]))); // GPSVersionID
+        gps.set(tree.entry(
+            0x0002,
+            TiffValue::Rational(vec![(45, 1), (30, 1), (1234, 100)]),
+        )); // GPSLatitude
+        let mut interop = Ifd::default();
+        interop.set(tree.entry(0x0001, TiffValue::Ascii("R98".to_string())));
+
+        tree.exif = Some(exif);
+        tree.gps = Some(gps);
+        tree.interop = Some(interop);
+        tree.set_thumbnail(vec![0xFF, 0xD8, 0x01, 0x02, 0x03, 0xFF, 0xD9]);
+        tree
+    }
+
+    #[test]
+    fn test_typed_values_roundtrip_with_kamadak() {
+        for byte_order in [ByteOrder::LittleEndian, ByteOrder::BigEndian] {
+            let tree = typed_tree(byte_order);
+            let written = tree.to_bytes();
+            assert_eq!(ExifTree::parse(&written).unwrap(), tree);
+
+            let exif = exif::Reader::new().read_raw(written.clone()).unwrap();
+            assert_eq!(exif.little_endian(), byte_order == ByteOrder::LittleEndian);
+            let field = |tag, ifd| &exif.get_field(tag, ifd).unwrap().value;
+
+            assert!(matches!(field(exif::Tag::Model, exif::In::PRIMARY),
+                exif::Value::Ascii(v) if v[0] == b"Canon EOS 5D Mark IV"));
+            assert!(matches!(field(exif::Tag::XResolution, exif::In::PRIMARY),
+                exif::Value::Rational(v) if v[0].num == 300 && v[0].denom == 1));
+            assert_eq!(
+                field(exif::Tag::PhotographicSensitivity, exif::In::PRIMARY).get_uint(0),
+                Some(400)
+            );
+            assert!(
+                matches!(field(exif::Tag::ExposureBiasValue, exif::In::PRIMARY),
+                exif::Value::SRational(v) if v[0].num == -2 && v[0].denom == 3)
+            );
+            assert!(matches!(field(exif::Tag::ExifVersion, exif::In::PRIMARY),
+                exif::Value::Undefined(v, _) if v == b"0232"));
+            assert_eq!(
+                field(exif::Tag::PixelXDimension, exif::In::PRIMARY).get_uint(0),
+                Some(6000)
+            );
+            assert!(matches!(field(exif::Tag::GPSVersionID, exif::In::PRIMARY),
+                exif::Value::Byte(v) if v == &[2, 3, 0, 0]));
+            assert!(matches!(field(exif::Tag::GPSLatitude, exif::In::PRIMARY),
+                exif::Value::Rational(v) if v.len() == 3 && v[2].num == 1234));
+            assert!(
+                matches!(field(exif::Tag::InteroperabilityIndex, exif::In::PRIMARY),
+                exif::Value::Ascii(v) if v[0] == b"R98")
+            );
+
+            // La thumbnail si trova all'offset dichiarato in IFD1
+            let offset = field(exif::Tag::JPEGInterchangeFormat, exif::In::THUMBNAIL)
+                .get_uint(0)
+                .unwrap() as usize;
+            let length = field(exif::Tag::JPEGInterchangeFormatLength, exif::In::THUMBNAIL)
+                .get_uint(0)
+                .unwrap() as usize;
+            assert_eq!(
+                &written[offset..offset + length],
+                tree.thumbnail.as_deref().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_byte_order_conversion() {
+        let mut tree = typed_tree(ByteOrder::LittleEndian);
+        tree.set_byte_order(ByteOrder::BigEndian);
+
+        // Stesso contenuto di una struttura costruita direttamente in big-endian
+        assert_eq!(tree, typed_tree(ByteOrder::BigEndian));
+        assert_eq!(&tree.to_bytes()[0..2], b"MM");
+    }
+
+    #[test]
+    fn test_invalid_tiff() {
+        assert!(ExifTree::parse(b"XX\x00\x2A\x00\x00\x00\x08").is_err());
+        // IFD0 fuori dai limiti
+        assert!(ExifTree::parse(b"II\x2A\x00\xFF\x00\x00\x00").is_err());
+    }
+}
@@ -0,0 +1,280 @@
+// src-tauri/src/core/transform_cache.rs
+
+use crate::core::color_management::ConversionSettings;
+use lcms2::{DisallowCache, Flags, GlobalContext, PixelFormat, Profile, Transform};
+use rayon::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+
+/// Numero massimo di trasformazioni in cache (oltre, la cache viene svuotata)
+const MAX_CACHED_TRANSFORMS: usize = 64;
+/// Sotto questa dimensione (byte in ingresso, ~1 MP RGB) la trasformazione resta su un solo thread
+const PARALLEL_MIN_BYTES: usize = 3_000_000;
+/// Righe elaborate da ciascun task parallelo
+const ROWS_PER_TASK: usize = 64;
+
+/// Trasformazione LCMS2 condivisibile tra thread (creata senza cache interna)
+pub type SharedTransform = Arc<Transform<u8, u8, GlobalContext, DisallowCache>>;
+
+/// Chiave della cache: profili (hash), formati dei pixel, intento e BPC
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TransformKey {
+    pub source: u64,
+    pub destination: u64,
+    pub input_format: u32,
+    pub output_format: u32,
+    pub conversion: ConversionSettings,
+}
+
+impl TransformKey {
+    pub fn new(
+        source: u64,
+        destination: u64,
+        input_format: PixelFormat,
+        output_format: PixelFormat,
+        conversion: ConversionSettings,
+    ) -> Self {
+        Self {
+            source,
+            destination,
+            input_format: input_format.0,
+            output_format: output_format.0,
+            conversion,
+        }
+    }
+}
+
+/// Cache thread-safe delle trasformazioni, condivisa dai worker Rayon
+pub struct TransformCache {
+    transforms: Mutex<HashMap<TransformKey, SharedTransform>>,
+}
+
+impl TransformCache {
+    fn new() -> Self {
+        Self {
+            transforms: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cache globale del processo
+    pub fn global() -> &'static TransformCache {
+        static CACHE: OnceLock<TransformCache> = OnceLock::new();
+        CACHE.get_or_init(TransformCache::new)
+    }
+
+    /// Restituisce la trasformazione per la chiave, creandola (e i profili) solo se manca
+    pub fn get_or_create(
+        &self,
+        key: TransformKey,
+        source: impl FnOnce() -> Result<Profile, String>,
+        destination: impl FnOnce() -> Result<Profile, String>,
+    ) -> Result<SharedTransform, String> {
+        if let Some(transform) = self.lock().get(&key) {
+            return Ok(Arc::clone(transform));
+        }
+
+        // Creazione fuori dal lock: gli altri worker non restano bloccati.
+        // Se due worker chiedono la stessa chiave insieme può essere costruita
+        // due volte, ma in cache resta (e viene usata) solo la prima
+        let source_profile = source()?;
+        let destination_profile = destination()?;
+        // COPY_ALPHA: senza, LCMS2 lascia a zero il canale alpha dell'output RGBA
+        let flags = if key.conversion.black_point_compensation {
+            Flags::NO_CACHE | Flags::COPY_ALPHA | Flags::BLACKPOINT_COMPENSATION
+        } else {
+            Flags::NO_CACHE | Flags::COPY_ALPHA
+        };
+        let transform = Transform::new_flags_context(
+            GlobalContext::new(),
+            &source_profile,
+            PixelFormat(key.input_format),
+            &destination_profile,
+            PixelFormat(key.output_format),
+            key.conversion.intent.to_lcms2(),
+            flags,
+        )
+        .map_err(|e| format!("Failed to create transform: {}", e))?;
+
+        let mut transforms = self.lock();
+        if let Some(existing) = transforms.get(&key) {
+            return Ok(Arc::clone(existing));
+        }
+        if transforms.len() >= MAX_CACHED_TRANSFORMS {
+            transforms.clear();
+        }
+
+        let transform = Arc::new(transform);
+        transforms.insert(key, Arc::clone(&transform));
+        Ok(transform)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<TransformKey, SharedTransform>> {
+        self.transforms.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Numero di trasformazioni in cache
+    pub fn len(&self) -> usize {
+        self.transforms.lock().map(|t| t.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Hash stabile (nel processo) di un profilo o di un identificatore
+pub fn profile_hash(data: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Applica la trasformazione riga per riga; le immagini grandi vengono
+/// divise in blocchi di righe elaborati in parallelo
+pub fn transform_rows(
+    transform: &SharedTransform,
+    input: &[u8],
+    output: &mut [u8],
+    input_row_bytes: usize,
+    output_row_bytes: usize,
+) {
+    if input_row_bytes == 0 || output_row_bytes == 0 {
+        return;
+    }
+
+    if input.len() < PARALLEL_MIN_BYTES {
+        for (input_row, output_row) in input
+            .chunks_exact(input_row_bytes)
+            .zip(output.chunks_exact_mut(output_row_bytes))
+        {
+            transform.transform_pixels(input_row, output_row);
+        }
+        return;
+    }
+
+    input
+        .par_chunks(input_row_bytes * ROWS_PER_TASK)
+        .zip(output.par_chunks_mut(output_row_bytes * ROWS_PER_TASK))
+        .for_each(|(input_rows, output_rows)| {
+            transform.transform_pixels(input_rows, output_rows);
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::color_management::{ColorManager, RenderingIntent};
+    use crate::core::settings::OutputColorSpace;
+
+    fn srgb_to_p3_key(source: &str) -> TransformKey {
+        TransformKey::new(
+            profile_hash(source),
+            profile_hash("display-p3-test"),
+            PixelFormat::RGB_8,
+            PixelFormat::RGB_8,
+            RenderingIntent::Perceptual.into(),
+        )
+    }
+
+    fn p3_profile() -> Result<Profile, String> {
+        let manager = ColorManager::with_destination(&OutputColorSpace::DisplayP3)?;
+        Profile::new_icc(&manager.destination_icc()?).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_cache_reuses_transform() {
+        let cache = TransformCache::new();
+        let key = srgb_to_p3_key("srgb-test");
+
+        let first = cache
+            .get_or_create(key, || Ok(Profile::new_srgb()), p3_profile)
+            .unwrap();
+        // Alla seconda richiesta i profili non devono essere ricostruiti
+        let second = cache
+            .get_or_create(
+                key,
+                || Err("source rebuilt".to_string()),
+                || Err("destination rebuilt".to_string()),
+            )
+            .unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(cache.len(), 1);
+
+        // Un intento diverso, o la BPC, sono trasformazioni diverse
+        let relative = TransformKey {
+            conversion: RenderingIntent::RelativeColorimetric.into(),
+            ..key
+        };
+        cache
+            .get_or_create(relative, || Ok(Profile::new_srgb()), p3_profile)
+            .unwrap();
+        let relative_bpc = TransformKey {
+            conversion: ConversionSettings::new(RenderingIntent::RelativeColorimetric, true),
+            ..key
+        };
+        cache
+            .get_or_create(relative_bpc, || Ok(Profile::new_srgb()), p3_profile)
+            .unwrap();
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn test_concurrent_requests_share_transform() {
+        let cache = TransformCache::new();
+        let key = srgb_to_p3_key("srgb-concurrent-test");
+        let barrier = std::sync::Barrier::new(8);
+
+        let transforms: Vec<SharedTransform> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        barrier.wait();
+                        cache
+                            .get_or_create(key, || Ok(Profile::new_srgb()), p3_profile)
+                            .unwrap()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        // Anche se più worker l'hanno costruita, tutti usano quella in cache
+        assert_eq!(cache.len(), 1);
+        assert!(transforms
+            .iter()
+            .all(|transform| Arc::ptr_eq(transform, &transforms[0])));
+    }
+
+    #[test]
+    fn test_parallel_rows_match_sequential() {
+        let cache = TransformCache::new();
+        let transform = cache
+            .get_or_create(
+                srgb_to_p3_key("srgb-rows-test"),
+                || Ok(Profile::new_srgb()),
+                p3_profile,
+            )
+            .unwrap();
+
+        // 1200x1000 RGB supera la soglia per la conversione parallela
+        let row_bytes = 1200 * 3;
+        let input: Vec<u8> = (0..row_bytes * 1000).map(|i| (i * 7 % 251) as u8).collect();
+        assert!(input.len() >= PARALLEL_MIN_BYTES);
+
+        let mut parallel = vec![0u8; input.len()];
+        transform_rows(&transform, &input, &mut parallel, row_bytes, row_bytes);
+
+        let mut sequential = vec![0u8; input.len()];
+        for (input_row, output_row) in input
+            .chunks_exact(row_bytes)
+            .zip(sequential.chunks_exact_mut(row_bytes))
+        {
+            transform.transform_pixels(input_row, output_row);
+        }
+
+        assert_eq!(parallel, sequential);
+    }
+}
@@ -0,0 +1,156 @@
+// src-tauri/src/core/trim.rs
+
+use crate::core::models::CropRect;
+use crate::core::settings::TrimOptions;
+use image::{DynamicImage, Rgba};
+
+/// Ritaglia i bordi uniformi (o completamente trasparenti) dell'immagine.
+/// Restituisce l'immagine ritagliata e il rettangolo di crop, se applicato.
+pub fn apply_trim(img: &DynamicImage, options: &TrimOptions) -> (DynamicImage, Option<CropRect>) {
+    match detect_trim_rect(img, options) {
+        Some(rect) => {
+            println!(
+                "Trimming borders from {}x{} to {}x{} at ({}, {})",
+                img.width(),
+                img.height(),
+                rect.width,
+                rect.height,
+                rect.x,
+                rect.y
+            );
+            let cropped = img.crop_imm(rect.x, rect.y, rect.width, rect.height);
+            (cropped, Some(rect))
+        }
+        None => (img.clone(), None),
+    }
+}
+
+/// Individua il rettangolo del contenuto escludendo i bordi dello stesso colore
+/// del pixel in alto a sinistra. None se non c'è nulla da ritagliare.
+pub fn detect_trim_rect(img: &DynamicImage, options: &TrimOptions) -> Option<CropRect> {
+    let rgba = img.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let reference = *rgba.get_pixel(0, 0);
+    let tolerance = options.tolerance;
+    let is_background =
+        |x: u32, y: u32| matches_background(rgba.get_pixel(x, y), &reference, tolerance);
+
+    let row_is_background = |y: u32| (0..width).all(|x| is_background(x, y));
+    let column_is_background =
+        |x: u32, top: u32, bottom: u32| (top..=bottom).all(|y| is_background(x, y));
+
+    // Immagine completamente uniforme: niente da ritagliare
+    let top = (0..height).find(|&y| !row_is_background(y))?;
+    let bottom = (0..height).rev().find(|&y| !row_is_background(y))?;
+    let left = (0..width).find(|&x| !column_is_background(x, top, bottom))?;
+    let right = (0..width)
+        .rev()
+        .find(|&x| !column_is_background(x, top, bottom))?;
+
+    // Applica il padding restando dentro i limiti dell'immagine
+    let padding = options.padding;
+    let x = left.saturating_sub(padding);
+    let y = top.saturating_sub(padding);
+    let x_end = right.saturating_add(padding).min(width - 1);
+    let y_end = bottom.saturating_add(padding).min(height - 1);
+
+    let rect = CropRect {
+        x,
+        y,
+        width: x_end - x + 1,
+        height: y_end - y + 1,
+    };
+
+    if rect.width == width && rect.height == height {
+        return None;
+    }
+
+    Some(rect)
+}
+
+/// Un pixel è sfondo se è trasparente come il riferimento o ha lo stesso colore entro la tolleranza
+fn matches_background(pixel: &Rgba<u8>, reference: &Rgba<u8>, tolerance: u8) -> bool {
+    // Bordi completamente trasparenti: il colore RGB non conta
+    if reference[3] == 0 {
+        return pixel[3] <= tolerance;
+    }
+
+    (0..4).all(|channel| pixel[channel].abs_diff(reference[channel]) <= tolerance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    /// Immagine piena di un colore con un rettangolo di contenuto
+    fn framed_image(background: Rgba<u8>, content: Rgba<u8>) -> RgbaImage {
+        RgbaImage::from_fn(100, 80, |x, y| {
+            if (20..60).contains(&x) && (10..50).contains(&y) {
+                content
+            } else {
+                background
+            }
+        })
+    }
+
+    fn options(tolerance: u8, padding: u32) -> TrimOptions {
+        TrimOptions { tolerance, padding }
+    }
+
+    #[test]
+    fn test_trim_white_margins() {
+        let img = DynamicImage::ImageRgba8(framed_image(
+            Rgba([255, 255, 255, 255]),
+            Rgba([200, 30, 30, 255]),
+        ));
+
+        let (trimmed, rect) = apply_trim(&img, &options(10, 0));
+        let rect = rect.unwrap();
+
+        assert_eq!((rect.x, rect.y, rect.width, rect.height), (20, 10, 40, 40));
+        assert_eq!((trimmed.width(), trimmed.height()), (40, 40));
+    }
+
+    #[test]
+    fn test_trim_padding_and_tolerance() {
+        // Sfondo leggermente rumoroso entro la tolleranza
+        let mut frame = framed_image(Rgba([250, 250, 250, 255]), Rgba([0, 0, 0, 255]));
+        frame.put_pixel(5, 5, Rgba([245, 248, 250, 255]));
+        let img = DynamicImage::ImageRgba8(frame);
+
+        let rect = detect_trim_rect(&img, &options(8, 5)).unwrap();
+        assert_eq!((rect.x, rect.y, rect.width, rect.height), (15, 5, 50, 50));
+
+        // Con tolleranza zero il pixel rumoroso fa parte del contenuto
+        let rect = detect_trim_rect(&img, &options(0, 0)).unwrap();
+        assert_eq!((rect.x, rect.y), (5, 5));
+
+        // Un padding enorme non va in overflow: copre tutta l'immagine, niente ritaglio
+        assert!(detect_trim_rect(&img, &options(8, u32::MAX)).is_none());
+    }
+
+    #[test]
+    fn test_trim_transparent_borders() {
+        let img =
+            DynamicImage::ImageRgba8(framed_image(Rgba([12, 34, 56, 0]), Rgba([0, 0, 0, 255])));
+        let rect = detect_trim_rect(&img, &options(0, 0)).unwrap();
+        assert_eq!((rect.width, rect.height), (40, 40));
+    }
+
+    #[test]
+    fn test_no_trim_needed() {
+        // Immagine uniforme o senza bordi: nessun ritaglio
+        let uniform = DynamicImage::new_rgb8(50, 50);
+        assert!(detect_trim_rect(&uniform, &options(10, 0)).is_none());
+
+        let gradient = RgbaImage::from_fn(100, 80, |x, y| Rgba([x as u8 * 2, y as u8 * 2, 0, 255]));
+        let (img, rect) = apply_trim(&DynamicImage::ImageRgba8(gradient), &options(10, 0));
+        assert!(rect.is_none());
+        assert_eq!((img.width(), img.height()), (100, 80));
+    }
+}
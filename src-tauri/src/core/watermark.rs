@@ -0,0 +1,349 @@
+// src-tauri/src/core/watermark.rs
+
+use crate::core::settings::{self, WatermarkOptions, WatermarkPosition, WatermarkSource};
+use image::{imageops, imageops::FilterType, ColorType, DynamicImage, Rgba, RgbaImage};
+
+// Font bitmap 5x7 integrato (nessuna dipendenza da font di sistema)
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+const GLYPH_SPACING: u32 = 1;
+const LINE_SPACING: u32 = 2;
+
+/// Watermark pronto per l'applicazione: sorgente caricata una sola volta per batch
+pub struct Watermark {
+    mark: RgbaImage,
+    filter: FilterType,
+    options: WatermarkOptions,
+}
+
+impl Watermark {
+    /// Carica l'immagine o renderizza il testo del watermark
+    pub fn prepare(options: &WatermarkOptions) -> Result<Self, String> {
+        let (mark, filter) = match &options.source {
+            WatermarkSource::Image { path } => {
+                let img = image::open(path)
+                    .map_err(|e| format!("Failed to open watermark image {}: {}", path, e))?;
+                (img.to_rgba8(), FilterType::Lanczos3)
+            }
+            WatermarkSource::Text { text, color } => {
+                // Nearest mantiene i bordi del font bitmap netti
+                (render_text(text, *color)?, FilterType::Nearest)
+            }
+        };
+
+        if mark.width() == 0 || mark.height() == 0 {
+            return Err("Watermark has invalid dimensions".to_string());
+        }
+
+        Ok(Self {
+            mark,
+            filter,
+            options: options.clone(),
+        })
+    }
+
+    /// Applica il watermark all'immagine (dopo il resize, prima dell'encoding).
+    /// La composizione avviene alla profondità del sorgente (8, 16 bit o float)
+    /// e il risultato torna al tipo colore di partenza (es. scala di grigi).
+    pub fn apply(&self, img: &DynamicImage) -> DynamicImage {
+        let mark = DynamicImage::ImageRgba8(self.scaled_mark(img.width()));
+        let positions = self.positions(
+            (img.width() as i64, img.height() as i64),
+            (mark.width() as i64, mark.height() as i64),
+        );
+
+        let composed = match img.color() {
+            ColorType::L16 | ColorType::La16 | ColorType::Rgb16 | ColorType::Rgba16 => {
+                let mut canvas = img.to_rgba16();
+                let mark = mark.to_rgba16();
+                for &(x, y) in &positions {
+                    imageops::overlay(&mut canvas, &mark, x, y);
+                }
+                DynamicImage::ImageRgba16(canvas)
+            }
+            ColorType::Rgb32F | ColorType::Rgba32F => {
+                let mut canvas = img.to_rgba32f();
+                let mark = mark.to_rgba32f();
+                for &(x, y) in &positions {
+                    imageops::overlay(&mut canvas, &mark, x, y);
+                }
+                DynamicImage::ImageRgba32F(canvas)
+            }
+            _ => {
+                let mut canvas = img.to_rgba8();
+                let mark = mark.to_rgba8();
+                for &(x, y) in &positions {
+                    imageops::overlay(&mut canvas, &mark, x, y);
+                }
+                DynamicImage::ImageRgba8(canvas)
+            }
+        };
+
+        settings::convert_to_color_type(composed, img.color())
+    }
+
+    /// Angoli superiori sinistri delle copie del watermark da disegnare
+    fn positions(
+        &self,
+        (width, height): (i64, i64),
+        (mark_w, mark_h): (i64, i64),
+    ) -> Vec<(i64, i64)> {
+        let margin = self.options.margin as i64;
+        if !self.options.tile {
+            return vec![anchor_position(
+                &self.options.position,
+                (width, height),
+                (mark_w, mark_h),
+                margin,
+            )];
+        }
+
+        // Griglia regolare con il margine come spaziatura
+        let step_x = mark_w + margin.max(1) * 2;
+        let step_y = mark_h + margin.max(1) * 2;
+        let mut positions = Vec::new();
+        let mut y = margin;
+        while y < height {
+            let mut x = margin;
+            while x < width {
+                positions.push((x, y));
+                x += step_x;
+            }
+            y += step_y;
+        }
+        positions
+    }
+
+    /// Ridimensiona il watermark in proporzione alla larghezza dell'immagine e applica l'opacità
+    fn scaled_mark(&self, image_width: u32) -> RgbaImage {
+        let scale = self.options.scale.clamp(0.01, 1.0);
+        let target_w = ((image_width as f32 * scale).round() as u32).max(1);
+        let ratio = target_w as f32 / self.mark.width() as f32;
+        let target_h = ((self.mark.height() as f32 * ratio).round() as u32).max(1);
+
+        let mut mark = imageops::resize(&self.mark, target_w, target_h, self.filter);
+
+        let opacity = self.options.opacity.clamp(0.0, 1.0);
+        if opacity < 1.0 {
+            for pixel in mark.pixels_mut() {
+                pixel[3] = (pixel[3] as f32 * opacity).round() as u8;
+            }
+        }
+
+        mark
+    }
+}
+
+/// Calcola l'angolo superiore sinistro del watermark secondo l'ancoraggio
+fn anchor_position(
+    position: &WatermarkPosition,
+    (width, height): (i64, i64),
+    (mark_w, mark_h): (i64, i64),
+    margin: i64,
+) -> (i64, i64) {
+    let left = margin;
+    let center_x = (width - mark_w) / 2;
+    let right = width - mark_w - margin;
+    let top = margin;
+    let center_y = (height - mark_h) / 2;
+    let bottom = height - mark_h - margin;
+
+    match position {
+        WatermarkPosition::TopLeft => (left, top),
+        WatermarkPosition::Top => (center_x, top),
+        WatermarkPosition::TopRight => (right, top),
+        WatermarkPosition::Left => (left, center_y),
+        WatermarkPosition::Center => (center_x, center_y),
+        WatermarkPosition::Right => (right, center_y),
+        WatermarkPosition::BottomLeft => (left, bottom),
+        WatermarkPosition::Bottom => (center_x, bottom),
+        WatermarkPosition::BottomRight => (right, bottom),
+    }
+}
+
+/// Renderizza il testo con il font bitmap integrato (supporta più righe)
+fn render_text(text: &str, color: [u8; 3]) -> Result<RgbaImage, String> {
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    if lines.is_empty() {
+        return Err("Watermark text is empty".to_string());
+    }
+
+    let max_chars = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as u32;
+    let width = max_chars * (GLYPH_WIDTH + GLYPH_SPACING) + GLYPH_SPACING;
+    let height = lines.len() as u32 * (GLYPH_HEIGHT + LINE_SPACING) + LINE_SPACING;
+
+    let mut canvas = RgbaImage::new(width, height);
+    let ink = Rgba([color[0], color[1], color[2], 255]);
+
+    for (row, line) in lines.iter().enumerate() {
+        let origin_y = LINE_SPACING + row as u32 * (GLYPH_HEIGHT + LINE_SPACING);
+
+        for (col, ch) in line.chars().enumerate() {
+            let origin_x = GLYPH_SPACING + col as u32 * (GLYPH_WIDTH + GLYPH_SPACING);
+
+            for (dy, bits) in glyph(ch).iter().enumerate() {
+                for dx in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - dx)) != 0 {
+                        canvas.put_pixel(origin_x + dx, origin_y + dy as u32, ink);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(canvas)
+}
+
+/// Righe del glifo 5x7 (bit 4 = colonna sinistra). I caratteri mancanti diventano '?'
+fn glyph(ch: char) -> [u8; 7] {
+    match ch.to_ascii_uppercase() {
+        ' ' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1E],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x0A, 0x04, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '\'' => [0x04, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00],
+        '!' => [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04],
+        '&' => [0x0C, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0D],
+        '@' => [0x0E, 0x11, 0x17, 0x15, 0x17, 0x10, 0x0E],
+        '©' => [0x0E, 0x11, 0x17, 0x19, 0x17, 0x11, 0x0E],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // '?'
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_options(position: WatermarkPosition, tile: bool) -> WatermarkOptions {
+        WatermarkOptions {
+            source: WatermarkSource::Text {
+                text: "IRON".to_string(),
+                color: [255, 255, 255],
+            },
+            position,
+            margin: 4,
+            scale: 0.25,
+            opacity: 1.0,
+            tile,
+        }
+    }
+
+    #[test]
+    fn test_render_text() {
+        let mark = render_text("AB\nC", [255, 0, 0]).unwrap();
+        assert_eq!(mark.width(), 2 * 6 + 1);
+        assert_eq!(mark.height(), 2 * 9 + 2);
+        assert!(mark.pixels().any(|p| p == &Rgba([255, 0, 0, 255])));
+        assert!(render_text("  \n", [0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_anchor_positions() {
+        let size = (200, 100);
+        let mark = (40, 20);
+        assert_eq!(
+            anchor_position(&WatermarkPosition::TopLeft, size, mark, 10),
+            (10, 10)
+        );
+        assert_eq!(
+            anchor_position(&WatermarkPosition::Center, size, mark, 10),
+            (80, 40)
+        );
+        assert_eq!(
+            anchor_position(&WatermarkPosition::BottomRight, size, mark, 10),
+            (150, 70)
+        );
+    }
+
+    #[test]
+    fn test_apply_watermark_keeps_format() {
+        let watermark =
+            Watermark::prepare(&text_options(WatermarkPosition::BottomRight, false)).unwrap();
+        let img = DynamicImage::new_rgb8(400, 200);
+        let result = watermark.apply(&img);
+
+        assert_eq!(result.color(), image::ColorType::Rgb8);
+        assert_eq!((result.width(), result.height()), (400, 200));
+
+        // Il testo deve comparire in basso a destra e non in alto a sinistra
+        let rgb = result.to_rgb8();
+        let lit = |x0: u32, y0: u32| {
+            (x0..x0 + 100).any(|x| (y0..y0 + 50).any(|y| rgb.get_pixel(x, y)[0] > 0))
+        };
+        assert!(lit(300, 150));
+        assert!(!lit(0, 0));
+    }
+
+    #[test]
+    fn test_apply_watermark_keeps_depth_and_grayscale() {
+        let watermark =
+            Watermark::prepare(&text_options(WatermarkPosition::TopLeft, false)).unwrap();
+
+        let deep = watermark.apply(&DynamicImage::new_rgb16(200, 100));
+        assert_eq!(deep.color(), image::ColorType::Rgb16);
+        // Il bianco del testo arriva al massimo della scala a 16 bit
+        assert!(deep.to_rgb16().pixels().any(|p| p[0] == u16::MAX));
+
+        let gray = watermark.apply(&DynamicImage::new_luma8(200, 100));
+        assert_eq!(gray.color(), image::ColorType::L8);
+        assert!(gray.to_luma8().pixels().any(|p| p[0] > 0));
+    }
+
+    #[test]
+    fn test_opacity_and_tiling() {
+        let mut options = text_options(WatermarkPosition::Center, true);
+        options.opacity = 0.5;
+        let watermark = Watermark::prepare(&options).unwrap();
+        let result = watermark.apply(&DynamicImage::new_rgb8(400, 400)).to_rgb8();
+
+        // Bianco al 50% su nero: nessun pixel pieno, ma presenti in più quadranti
+        assert!(result.pixels().all(|p| p[0] <= 128));
+        assert_eq!(result.get_pixel(0, 0)[0], 0);
+        let quadrant_lit = |x0: u32, y0: u32| {
+            (x0..x0 + 200).any(|x| (y0..y0 + 200).any(|y| result.get_pixel(x, y)[0] > 0))
+        };
+        assert!(quadrant_lit(0, 0) && quadrant_lit(200, 200));
+    }
+}
@@ -0,0 +1,696 @@
+// src-tauri/src/core/xmp.rs
+
+use crate::core::exif_handler::{ExifOptions, MetadataTemplate};
+use crate::core::icc_writer::{parse_png_chunks, parse_webp_chunks};
+use crate::core::metadata_policy::{MetadataPolicy, TagCategory};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Firma dei segmenti APP1 che contengono un pacchetto XMP
+pub const XMP_JPEG_SIGNATURE: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+/// Keyword del chunk iTXt con l'XMP nei PNG
+pub const XMP_PNG_KEYWORD: &str = "XML:com.adobe.xmp";
+/// Spazio massimo per il pacchetto in un segmento APP1 (65535 - lunghezza - firma)
+const MAX_JPEG_PACKET: usize = 65535 - 2 - 29;
+
+// Namespace delle proprietà lette e scritte
+const NS_RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const NS_DC: &str = "http://purl.org/dc/elements/1.1/";
+const NS_PHOTOSHOP: &str = "http://ns.adobe.com/photoshop/1.0/";
+const NS_XMP: &str = "http://ns.adobe.com/xap/1.0/";
+const NS_XMP_RIGHTS: &str = "http://ns.adobe.com/xap/1.0/rights/";
+
+/// Proprietà XMP usate dai DAM (autore, diritti, crediti, rating, keyword)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XmpData {
+    /// dc:creator
+    pub creator: Vec<String>,
+    /// dc:rights
+    pub rights: Option<String>,
+    /// photoshop:Credit
+    pub credit: Option<String>,
+    /// xmpRights:WebStatement (URL della licenza)
+    pub web_statement: Option<String>,
+    /// dc:title
+    pub title: Option<String>,
+    /// dc:description
+    pub description: Option<String>,
+    /// dc:subject
+    pub keywords: Vec<String>,
+    /// xmp:Rating (-1 = rifiutata, 0-5 stelle)
+    pub rating: Option<i32>,
+}
+
+impl XmpData {
+    /// Legge le proprietà note da un pacchetto XMP (RDF/XML)
+    pub fn parse(packet: &str) -> Result<Self, String> {
+        let root = parse_xml(packet)?;
+
+        let mut namespaces = HashMap::new();
+        collect_namespaces(&root, &mut namespaces);
+
+        let mut data = XmpData::default();
+        let mut descriptions = Vec::new();
+        find_descriptions(&root, &namespaces, &mut descriptions);
+
+        for description in descriptions {
+            // Proprietà semplici come attributi di rdf:Description
+            for (name, value) in &description.attributes {
+                if let Some((uri, local)) = resolve(name, &namespaces) {
+                    data.set(uri, local, vec![value.clone()]);
+                }
+            }
+            // Proprietà come elementi (valori semplici o array rdf:Seq/Bag/Alt)
+            for property in &description.children {
+                if let Some((uri, local)) = resolve(&property.name, &namespaces) {
+                    data.set(uri, local, property_values(property, &namespaces));
+                }
+            }
+        }
+
+        Ok(data)
+    }
+
+    fn set(&mut self, uri: &str, local: &str, values: Vec<String>) {
+        let first = values.first().cloned();
+        match (uri, local) {
+            (NS_DC, "creator") => self.creator = values,
+            (NS_DC, "rights") => self.rights = first,
+            (NS_DC, "title") => self.title = first,
+            (NS_DC, "description") => self.description = first,
+            (NS_DC, "subject") => self.keywords = values,
+            (NS_PHOTOSHOP, "Credit") => self.credit = first,
+            (NS_XMP_RIGHTS, "WebStatement") => self.web_statement = first,
+            (NS_XMP, "Rating") => {
+                self.rating = first
+                    .and_then(|value| value.trim().parse::<f64>().ok())
+                    .map(|rating| rating.round().clamp(-1.0, 5.0) as i32);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == XmpData::default()
+    }
+
+    /// Applica le opzioni di output: senza `preserve_copyright` autore e diritti
+    /// vengono rimossi, e la policy filtra ogni proprietà come un tag EXIF
+    /// (dc:creator = Artist, dc:rights = Copyright, dc:description = ImageDescription).
+    /// Le proprietà non gestite (es. posizione) non vengono mai copiate.
+    pub fn filtered(&self, options: &ExifOptions) -> XmpData {
+        let policy = options.effective_policy();
+        if policy == MetadataPolicy::StripAll {
+            return XmpData::default();
+        }
+
+        let mut data = self.clone();
+        let owner =
+            |name: &str| options.preserve_copyright && policy.keeps(name, Some(TagCategory::Owner));
+        if !owner("Artist") {
+            data.creator.clear();
+        }
+        if !owner("Copyright") {
+            data.rights = None;
+        }
+        if !owner("Credit") {
+            data.credit = None;
+        }
+        if !owner("WebStatement") {
+            data.web_statement = None;
+        }
+        if !policy.keeps("Title", None) {
+            data.title = None;
+        }
+        if !policy.keeps("ImageDescription", None) {
+            data.description = None;
+        }
+        if !policy.keeps("Keywords", None) {
+            data.keywords.clear();
+        }
+        if !policy.keeps("Rating", None) {
+            data.rating = None;
+        }
+        data
+    }
+
+    /// Sovrascrive le proprietà impostate nel template di metadati
+    pub fn apply_template(&mut self, template: &MetadataTemplate) {
+        if let Some(artist) = &template.artist {
+            self.creator = vec![artist.clone()];
+        }
+        if template.copyright.is_some() {
+            self.rights = template.copyright.clone();
+        }
+        if template.description.is_some() {
+            self.description = template.description.clone();
+        }
+        if !template.keywords.is_empty() {
+            self.keywords = template.keywords.clone();
+        }
+        if template.rights_url.is_some() {
+            self.web_statement = template.rights_url.clone();
+        }
+    }
+
+    /// Serializza in un pacchetto XMP completo (con wrapper xpacket).
+    /// Il pacchetto contiene solo le proprietà di `XmpData`: il resto dell'XMP
+    /// sorgente (impostazioni di sviluppo, history, posizione) va perso.
+    pub fn to_packet(&self) -> String {
+        let mut properties = String::new();
+
+        let mut array = |name: &str, kind: &str, values: &[String]| {
+            if values.is_empty() {
+                return;
+            }
+            properties.push_str(&format!("   <{}>\n    <rdf:{}>\n", name, kind));
+            for value in values {
+                let lang = if kind == "Alt" {
+                    " xml:lang=\"x-default\""
+                } else {
+                    ""
+                };
+                properties.push_str(&format!(
+                    "     <rdf:li{}>{}</rdf:li>\n",
+                    lang,
+                    escape_xml(value)
+                ));
+            }
+            properties.push_str(&format!("    </rdf:{}>\n   </{}>\n", kind, name));
+        };
+
+        let alt = |value: &Option<String>| value.iter().cloned().collect::<Vec<_>>();
+        array("dc:creator", "Seq", &self.creator);
+        array("dc:rights", "Alt", &alt(&self.rights));
+        array("dc:title", "Alt", &alt(&self.title));
+        array("dc:description", "Alt", &alt(&self.description));
+        array("dc:subject", "Bag", &self.keywords);
+
+        let mut simple = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                properties.push_str(&format!("   <{0}>{1}</{0}>\n", name, escape_xml(&value)));
+            }
+        };
+        simple("photoshop:Credit", self.credit.clone());
+        simple("xmpRights:WebStatement", self.web_statement.clone());
+        simple("xmp:Rating", self.rating.map(|rating| rating.to_string()));
+
+        format!(
+            "<?xpacket begin=\"\u{FEFF}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+             <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n \
+             <rdf:RDF xmlns:rdf=\"{}\">\n  \
+             <rdf:Description rdf:about=\"\"\n    \
+             xmlns:dc=\"{}\"\n    \
+             xmlns:photoshop=\"{}\"\n    \
+             xmlns:xmp=\"{}\"\n    \
+             xmlns:xmpRights=\"{}\">\n\
+             {}  </rdf:Description>\n \
+             </rdf:RDF>\n\
+             </x:xmpmeta>\n\
+             <?xpacket end=\"w\"?>",
+            NS_RDF, NS_DC, NS_PHOTOSHOP, NS_XMP, NS_XMP_RIGHTS, properties
+        )
+    }
+}
+
+/// Legge e interpreta l'XMP di un'immagine (None se assente o non valido)
+pub fn extract_xmp(path: &Path) -> Option<XmpData> {
+    let packet = read_xmp_packet(path)?;
+    let packet = String::from_utf8_lossy(&packet);
+    match XmpData::parse(&packet) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            eprintln!("⚠ Invalid XMP packet in {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Pacchetto XMP grezzo da JPEG (APP1), PNG (iTXt non compresso) o WebP (chunk "XMP ")
+pub fn read_xmp_packet(path: &Path) -> Option<Vec<u8>> {
+    let data = fs::read(path).ok()?;
+
+    if data.starts_with(&[0xFF, 0xD8]) {
+        jpeg_xmp_packet(&data)
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        png_xmp_packet(&data)
+    } else if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        parse_webp_chunks(&data)
+            .ok()?
+            .into_iter()
+            .find(|(fourcc, _)| fourcc == b"XMP ")
+            .map(|(_, packet)| packet.to_vec())
+    } else {
+        None
+    }
+}
+
+/// Segmento APP1 XMP completo per un JPEG
+pub fn jpeg_xmp_segment(packet: &[u8]) -> Result<Vec<u8>, String> {
+    if packet.len() > MAX_JPEG_PACKET {
+        // L'Extended XMP su più segmenti non è supportato
+        return Err("XMP packet too large for a single APP1 segment".to_string());
+    }
+
+    let mut segment = Vec::with_capacity(packet.len() + 33);
+    segment.extend_from_slice(&[0xFF, 0xE1]);
+    segment
+        .extend_from_slice(&((packet.len() + XMP_JPEG_SIGNATURE.len() + 2) as u16).to_be_bytes());
+    segment.extend_from_slice(XMP_JPEG_SIGNATURE);
+    segment.extend_from_slice(packet);
+    Ok(segment)
+}
+
+fn jpeg_xmp_packet(data: &[u8]) -> Option<Vec<u8>> {
+    let mut i = 2;
+    while i + 4 <= data.len() && data[i] == 0xFF {
+        let marker = data[i + 1];
+        // SOS: i metadati si trovano solo prima dei dati compressi
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+        let length = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        let end = (i + 2 + length).min(data.len());
+        let payload = &data[(i + 4).min(end)..end];
+        if marker == 0xE1 && payload.starts_with(XMP_JPEG_SIGNATURE) {
+            return Some(payload[XMP_JPEG_SIGNATURE.len()..].to_vec());
+        }
+        i += 2 + length;
+    }
+    None
+}
+
+fn png_xmp_packet(data: &[u8]) -> Option<Vec<u8>> {
+    parse_png_chunks(data)
+        .ok()?
+        .into_iter()
+        .filter(|(name, _)| name == b"iTXt")
+        .find_map(|(_, chunk)| {
+            // keyword\0 flag metodo lingua\0 keyword-tradotta\0 testo
+            let mut parts = chunk.splitn(2, |&byte| byte == 0);
+            if parts.next()? != XMP_PNG_KEYWORD.as_bytes() {
+                return None;
+            }
+            let rest = parts.next()?;
+            if rest.len() < 2 || rest[0] != 0 {
+                return None; // Compresso: non supportato
+            }
+            let mut fields = rest[2..].splitn(3, |&byte| byte == 0);
+            fields.next()?; // lingua
+            fields.next()?; // keyword tradotta
+            fields.next().map(<[u8]>::to_vec)
+        })
+}
+
+/// Elemento XML minimale (nome qualificato, attributi, figli, testo)
+#[derive(Debug, Default)]
+struct XmlElement {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<XmlElement>,
+    text: String,
+}
+
+impl XmlElement {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parser XML tollerante, sufficiente per i pacchetti XMP (niente DTD né entità custom)
+fn parse_xml(input: &str) -> Result<XmlElement, String> {
+    let mut stack = vec![XmlElement::default()];
+    let mut rest = input;
+
+    while let Some(start) = rest.find('<') {
+        let text = &rest[..start];
+        if let Some(top) = stack.last_mut() {
+            top.text.push_str(&decode_entities(text));
+        }
+        rest = &rest[start..];
+
+        if let Some(body) = rest.strip_prefix("<![CDATA[") {
+            let end = body.find("]]>").ok_or("Unterminated CDATA")?;
+            if let Some(top) = stack.last_mut() {
+                top.text.push_str(&body[..end]);
+            }
+            rest = &body[end + 3..];
+        } else if rest.starts_with("<!--") {
+            let end = rest.find("-->").ok_or("Unterminated comment")?;
+            rest = &rest[end + 3..];
+        } else if rest.starts_with("<?") {
+            let end = rest
+                .find("?>")
+                .ok_or("Unterminated processing instruction")?;
+            rest = &rest[end + 2..];
+        } else if rest.starts_with("<!") {
+            let end = rest.find('>').ok_or("Unterminated declaration")?;
+            rest = &rest[end + 1..];
+        } else if let Some(body) = rest.strip_prefix("</") {
+            let end = body.find('>').ok_or("Unterminated closing tag")?;
+            let name = body[..end].trim();
+            let element = stack.pop().filter(|_| !stack.is_empty());
+            match element {
+                Some(element) if element.name == name => {
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(element);
+                    }
+                }
+                _ => return Err(format!("Unexpected closing tag </{}>", name)),
+            }
+            rest = &body[end + 1..];
+        } else {
+            let (element, self_closing, consumed) = parse_tag(&rest[1..])?;
+            rest = &rest[1 + consumed..];
+            if self_closing {
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(element);
+                }
+            } else {
+                stack.push(element);
+            }
+        }
+    }
+
+    if stack.len() != 1 {
+        return Err("Unclosed XML elements".to_string());
+    }
+    Ok(stack.pop().unwrap_or_default())
+}
+
+/// Legge un tag di apertura (dopo '<'): elemento, se è auto-chiuso e byte consumati
+fn parse_tag(input: &str) -> Result<(XmlElement, bool, usize), String> {
+    let name_end = input
+        .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .ok_or("Unterminated tag")?;
+    let mut element = XmlElement {
+        name: input[..name_end].to_string(),
+        ..XmlElement::default()
+    };
+
+    let mut position = name_end;
+    loop {
+        let remaining = &input[position..];
+        let trimmed = remaining.trim_start();
+        position += remaining.len() - trimmed.len();
+
+        if trimmed.starts_with("/>") {
+            return Ok((element, true, position + 2));
+        }
+        if trimmed.starts_with('>') {
+            return Ok((element, false, position + 1));
+        }
+
+        let equals = trimmed.find('=').ok_or("Invalid attribute")?;
+        let key = trimmed[..equals].trim().to_string();
+        let value_part = trimmed[equals + 1..].trim_start();
+        let quote = value_part.chars().next().ok_or("Unterminated tag")?;
+        if quote != '"' && quote != '\'' {
+            return Err("Unquoted attribute value".to_string());
+        }
+        let value_end = value_part[1..]
+            .find(quote)
+            .ok_or("Unterminated attribute")?;
+        element
+            .attributes
+            .push((key, decode_entities(&value_part[1..1 + value_end])));
+
+        let consumed = trimmed.len() - value_part.len() + value_end + 2;
+        position += consumed;
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(str::parse::<u32>))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                result.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Dichiarazioni xmlns:prefisso di tutto il documento
+fn collect_namespaces(element: &XmlElement, namespaces: &mut HashMap<String, String>) {
+    for (key, value) in &element.attributes {
+        if let Some(prefix) = key.strip_prefix("xmlns:") {
+            namespaces.insert(prefix.to_string(), value.clone());
+        }
+    }
+    for child in &element.children {
+        collect_namespaces(child, namespaces);
+    }
+}
+
+/// Namespace e nome locale di un nome qualificato "prefisso:nome"
+fn resolve<'a>(
+    qname: &'a str,
+    namespaces: &'a HashMap<String, String>,
+) -> Option<(&'a str, &'a str)> {
+    let (prefix, local) = qname.split_once(':')?;
+    Some((namespaces.get(prefix)?.as_str(), local))
+}
+
+fn is_rdf(qname: &str, local: &str, namespaces: &HashMap<String, String>) -> bool {
+    resolve(qname, namespaces) == Some((NS_RDF, local))
+}
+
+fn find_descriptions<'a>(
+    element: &'a XmlElement,
+    namespaces: &HashMap<String, String>,
+    found: &mut Vec<&'a XmlElement>,
+) {
+    for child in &element.children {
+        if is_rdf(&child.name, "Description", namespaces) {
+            found.push(child);
+        } else {
+            find_descriptions(child, namespaces, found);
+        }
+    }
+}
+
+/// Valori di una proprietà: testo semplice, rdf:resource o elementi rdf:li di un array.
+/// Negli rdf:Alt il valore x-default viene messo per primo.
+fn property_values(property: &XmlElement, namespaces: &HashMap<String, String>) -> Vec<String> {
+    let is_rdf = |name: &str, local: &str| is_rdf(name, local, namespaces);
+
+    if let Some(container) = property.children.iter().find(|child| {
+        is_rdf(&child.name, "Seq") || is_rdf(&child.name, "Bag") || is_rdf(&child.name, "Alt")
+    }) {
+        let mut items: Vec<&XmlElement> = container
+            .children
+            .iter()
+            .filter(|item| is_rdf(&item.name, "li"))
+            .collect();
+        items.sort_by_key(|item| item.attribute("xml:lang") != Some("x-default"));
+        return items
+            .into_iter()
+            .map(|item| item.text.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect();
+    }
+
+    if let Some(resource) = property
+        .attributes
+        .iter()
+        .find(|(key, _)| is_rdf(key, "resource"))
+    {
+        return vec![resource.1.clone()];
+    }
+
+    let text = property.text.trim();
+    if text.is_empty() {
+        Vec::new()
+    } else {
+        vec![text.to_string()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_PACKET: &str = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <!-- Prefissi non standard: contano i namespace -->
+  <rdf:Description rdf:about=""
+    xmlns:d="http://purl.org/dc/elements/1.1/"
+    xmlns:ps="http://ns.adobe.com/photoshop/1.0/"
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmp:Rating="4"
+    ps:Credit="Agency &amp; Co">
+   <d:creator><rdf:Seq><rdf:li>Jane Doe</rdf:li><rdf:li>John Roe</rdf:li></rdf:Seq></d:creator>
+   <d:rights>
+    <rdf:Alt>
+     <rdf:li xml:lang="it-IT">Tutti i diritti riservati</rdf:li>
+     <rdf:li xml:lang="x-default">All rights reserved</rdf:li>
+    </rdf:Alt>
+   </d:rights>
+   <d:subject><rdf:Bag><rdf:li>harbor</rdf:li><rdf:li>sunset</rdf:li></rdf:Bag></d:subject>
+  </rdf:Description>
+  <rdf:Description rdf:about="" xmlns:xmpRights="http://ns.adobe.com/xap/1.0/rights/">
+   <xmpRights:WebStatement rdf:resource="https://example.com/license"/>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#;
+
+    #[test]
+    fn test_parse_packet() {
+        let data = XmpData::parse(SAMPLE_PACKET).unwrap();
+
+        assert_eq!(data.creator, vec!["Jane Doe", "John Roe"]);
+        assert_eq!(data.rights.as_deref(), Some("All rights reserved"));
+        assert_eq!(data.credit.as_deref(), Some("Agency & Co"));
+        assert_eq!(data.keywords, vec!["harbor", "sunset"]);
+        assert_eq!(data.rating, Some(4));
+        assert_eq!(
+            data.web_statement.as_deref(),
+            Some("https://example.com/license")
+        );
+    }
+
+    #[test]
+    fn test_packet_roundtrip() {
+        let data = XmpData {
+            creator: vec!["Zoë <Studio>".to_string()],
+            rights: Some("© 2024 \"Zoë\"".to_string()),
+            credit: Some("Agency".to_string()),
+            web_statement: Some("https://example.com/license".to_string()),
+            title: Some("Porto".to_string()),
+            description: None,
+            keywords: vec!["a & b".to_string(), "c".to_string()],
+            rating: Some(-1),
+        };
+
+        assert_eq!(XmpData::parse(&data.to_packet()).unwrap(), data);
+    }
+
+    #[test]
+    fn test_filtered_by_options() {
+        let data = XmpData::parse(SAMPLE_PACKET).unwrap();
+        let options = ExifOptions {
+            preserve_copyright: false,
+            ..ExifOptions::default()
+        };
+
+        let filtered = data.filtered(&options);
+        assert!(filtered.creator.is_empty());
+        assert!(filtered.rights.is_none());
+        assert!(filtered.credit.is_none());
+        assert!(filtered.web_statement.is_none());
+        assert_eq!(filtered.keywords, data.keywords);
+        assert_eq!(filtered.rating, Some(4));
+
+        // Ogni proprietà segue la policy come un tag EXIF
+        let options = ExifOptions {
+            policy: MetadataPolicy::AllowList {
+                categories: vec![TagCategory::Camera],
+                tags: vec!["Credit".to_string()],
+            },
+            ..ExifOptions::default()
+        };
+        let filtered = data.filtered(&options);
+        assert!(filtered.creator.is_empty());
+        assert!(filtered.keywords.is_empty());
+        assert!(filtered.rating.is_none());
+        assert_eq!(filtered.credit.as_deref(), Some("Agency & Co"));
+    }
+
+    #[test]
+    fn test_read_packet_from_containers() {
+        let packet = XmpData {
+            rating: Some(3),
+            ..XmpData::default()
+        }
+        .to_packet();
+
+        // JPEG: segmento APP1 dopo SOI
+        let mut jpeg = Vec::new();
+        image::DynamicImage::new_rgb8(2, 2)
+            .write_to(
+                &mut std::io::Cursor::new(&mut jpeg),
+                image::ImageFormat::Jpeg,
+            )
+            .unwrap();
+        let segment = jpeg_xmp_segment(packet.as_bytes()).unwrap();
+        jpeg.splice(2..2, segment);
+        let jpeg_path = std::env::temp_dir().join("iron_test_xmp.jpg");
+        fs::write(&jpeg_path, &jpeg).unwrap();
+
+        // PNG: chunk iTXt non compresso
+        let mut png = Vec::new();
+        image::DynamicImage::new_rgb8(2, 2)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let png = crate::core::icc_writer::IccWriter::embed_metadata_in_png(
+            &png,
+            None,
+            &[],
+            Some(packet.as_bytes()),
+        )
+        .unwrap();
+        let png_path = std::env::temp_dir().join("iron_test_xmp.png");
+        fs::write(&png_path, &png).unwrap();
+
+        let jpeg_xmp = extract_xmp(&jpeg_path);
+        let png_xmp = extract_xmp(&png_path);
+        fs::remove_file(&jpeg_path).ok();
+        fs::remove_file(&png_path).ok();
+
+        assert_eq!(jpeg_xmp.unwrap().rating, Some(3));
+        assert_eq!(png_xmp.unwrap().rating, Some(3));
+    }
+
+    #[test]
+    fn test_invalid_xml() {
+        assert!(XmpData::parse("<a><b></a>").is_err());
+        assert!(XmpData::parse("<a attr=unquoted/>").is_err());
+    }
+}
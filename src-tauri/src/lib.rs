@@ -2,6 +2,7 @@
 
 pub mod core;
 
+use crate::core::gamut::analyze_gamut;
 use crate::core::image_processing::{
     get_image_metadata, get_image_metadata_progressive, optimize_images,
 };
@@ -17,7 +18,8 @@ pub fn run() {
             get_image_metadata,
             get_image_metadata_progressive, // NUOVO comando progressivo
             optimize_images,
-            get_system_info
+            get_system_info,
+            analyze_gamut
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -22,6 +22,7 @@ import { ProcessingTable, ImageFile } from "./components/ProcessingTable";
 import { PreviewPanel } from "./components/PreviewPanel";
 import { Footer, SystemInfo } from "./components/Footer";
 import { SettingsPage, OptimizationOptions } from "./components/SettingsPage";
+import type { IptcData, XmpData } from "./components/ExifViewer";
 import { OptimizationHeader } from "./components/OptimizationHeader";
 import { SuccessMetrics } from "./components/SuccessMetrics";
 import { FiAlertTriangle, FiImage } from "solid-icons/fi";
@@ -63,11 +64,14 @@ type ImageInfo = {
   mimetype: string;
   last_modified: number;
   color_profile: ColorProfile;
+  color_profile_source: "icc" | "pngChunks" | "assumed";
   needs_conversion: boolean;
   preview_path?: string;
   thumbnail_path?: string;
   exifData?: ExifData;
   hasExif?: boolean;
+  xmp_data?: XmpData;
+  iptc_data?: IptcData;
 };
 
 type ColorProfile =
@@ -83,6 +87,9 @@ type OptimizationResult = {
   original_size_kb: number;
   optimized_size_kb: number;
   reduction_percentage: number;
+  warnings: string[];
+  crop_rect: { x: number; y: number; width: number; height: number } | null;
+  location_exposed: boolean;
 };
 
 type ProgressPayload = {
@@ -124,12 +131,18 @@ function App() {
       resize: "qhd2k",
       destination: { type: "sameFolder" },
       colorIntent: "perceptual",
+      blackPointCompensation: false,
       exifOptions: {
         preserveAll: true,
-        stripGps: false,
+        policy: { type: "keepAll" },
+        stripGps: true,
+        gpsPrecision: "exact",
         stripThumbnail: true,
         updateSoftware: true,
         preserveCopyright: true,
+        stripMakerNote: true,
+        pngTextChunks: false,
+        template: { keywords: [] },
       },
     };
     try {
@@ -142,7 +155,18 @@ function App() {
           resize: parsed.resize || defaults.resize,
           destination: parsed.destination || defaults.destination,
           colorIntent: parsed.colorIntent || defaults.colorIntent,
-          exifOptions: parsed.exifOptions || defaults.exifOptions,
+          blackPointCompensation:
+            parsed.blackPointCompensation ?? defaults.blackPointCompensation,
+          exifOptions: {
+            ...defaults.exifOptions,
+            ...parsed.exifOptions,
+            // Impostazioni salvate prima delle policy: preserveAll = false equivale a stripAll
+            policy:
+              parsed.exifOptions?.policy ??
+              (parsed.exifOptions?.preserveAll === false
+                ? { type: "stripAll" }
+                : defaults.exifOptions.policy),
+          },
         };
       }
     } catch (error) {
@@ -365,6 +389,9 @@ function App() {
               optimized_path: res.optimized_path,
               optimized_size_kb: res.optimized_size_kb,
               reduction_percentage: res.reduction_percentage,
+              warnings: res.warnings,
+              crop_rect: res.crop_rect,
+              location_exposed: res.location_exposed,
             },
           });
         },
@@ -376,12 +403,18 @@ function App() {
         resize: options.resize,
         destination: options.destination,
         color_intent: options.colorIntent || "perceptual",
+        black_point_compensation: options.blackPointCompensation ?? false,
         exif_options: options.exifOptions || {
           preserveAll: true,
-          stripGps: false,
+          policy: { type: "keepAll" },
+          stripGps: true,
+          gpsPrecision: "exact",
           stripThumbnail: true,
           updateSoftware: true,
           preserveCopyright: true,
+          stripMakerNote: true,
+          pngTextChunks: false,
+          template: { keywords: [] },
         },
       };
 
@@ -1,5 +1,5 @@
 // src/components/ExifViewer.tsx
-import { Show } from "solid-js";
+import { For, Show } from "solid-js";
 import {
   FiCamera,
   FiMapPin,
@@ -8,6 +8,8 @@ import {
   FiSun,
   FiUser,
   FiInfo,
+  FiTag,
+  FiFileText,
 } from "solid-icons/fi";
 
 export type ExifData = {
@@ -52,9 +54,40 @@ export type ExifData = {
   sceneType?: string;
 };
 
+export type XmpData = {
+  creator: string[];
+  rights?: string;
+  credit?: string;
+  webStatement?: string;
+  title?: string;
+  description?: string;
+  keywords: string[];
+  rating?: number;
+};
+
+export type IptcData = {
+  objectName?: string;
+  headline?: string;
+  caption?: string;
+  keywords: string[];
+  byLine: string[];
+  byLineTitle?: string;
+  credit?: string;
+  source?: string;
+  copyrightNotice?: string;
+  dateCreated?: string;
+  city?: string;
+  provinceState?: string;
+  country?: string;
+};
+
 type ExifViewerProps = {
   exifData: ExifData | null;
   hasExif: boolean;
+  xmpData?: XmpData | null;
+  iptcData?: IptcData | null;
+  // Calcolato dal backend dopo l'ottimizzazione (undefined prima)
+  locationExposed?: boolean;
 };
 
 export function ExifViewer(props: ExifViewerProps) {
@@ -91,257 +124,382 @@ export function ExifViewer(props: ExifViewerProps) {
   };
 
   return (
-    <Show
-      when={props.hasExif && props.exifData}
-      fallback={
-        <div class="bg-base-200/50 rounded-xl p-4 text-center text-base-content/40">
-          <FiInfo class="inline-block mb-2" size={24} />
-          <p class="text-sm">No EXIF metadata available</p>
-        </div>
-      }
-    >
-      <div class="space-y-3">
-        {/* Camera Info Section */}
-        <Show when={hasCameraInfo()}>
-          <div class="bg-base-200/50 rounded-xl p-4 border border-base-300">
-            <div class="flex items-center gap-2 mb-3">
-              <FiCamera class="text-primary" size={18} />
-              <h4 class="font-bold text-sm uppercase tracking-wider">Camera</h4>
-            </div>
-            <div class="space-y-2 text-sm">
-              <Show
-                when={props.exifData!.cameraMake || props.exifData!.cameraModel}
-              >
-                <div class="flex justify-between">
-                  <span class="text-base-content/60">Model:</span>
-                  <span class="font-semibold">
-                    {props.exifData!.cameraMake} {props.exifData!.cameraModel}
-                  </span>
-                </div>
-              </Show>
-              <Show when={props.exifData!.lensModel}>
-                <div class="flex justify-between">
-                  <span class="text-base-content/60">Lens:</span>
-                  <span class="font-semibold">{props.exifData!.lensModel}</span>
-                </div>
-              </Show>
-            </div>
+    <div class="space-y-3">
+      <Show
+        when={props.hasExif && props.exifData}
+        fallback={
+          <div class="bg-base-200/50 rounded-xl p-4 text-center text-base-content/40">
+            <FiInfo class="inline-block mb-2" size={24} />
+            <p class="text-sm">No EXIF metadata available</p>
           </div>
-        </Show>
-
-        {/* Capture Settings Section */}
-        <Show when={hasCaptureSettings()}>
-          <div class="bg-base-200/50 rounded-xl p-4 border border-base-300">
-            <div class="flex items-center gap-2 mb-3">
-              <FiAperture class="text-secondary" size={18} />
-              <h4 class="font-bold text-sm uppercase tracking-wider">
-                Capture Settings
-              </h4>
+        }
+      >
+        <div class="space-y-3">
+          {/* Camera Info Section */}
+          <Show when={hasCameraInfo()}>
+            <div class="bg-base-200/50 rounded-xl p-4 border border-base-300">
+              <div class="flex items-center gap-2 mb-3">
+                <FiCamera class="text-primary" size={18} />
+                <h4 class="font-bold text-sm uppercase tracking-wider">Camera</h4>
+              </div>
+              <div class="space-y-2 text-sm">
+                <Show
+                  when={props.exifData!.cameraMake || props.exifData!.cameraModel}
+                >
+                  <div class="flex justify-between">
+                    <span class="text-base-content/60">Model:</span>
+                    <span class="font-semibold">
+                      {props.exifData!.cameraMake} {props.exifData!.cameraModel}
+                    </span>
+                  </div>
+                </Show>
+                <Show when={props.exifData!.lensModel}>
+                  <div class="flex justify-between">
+                    <span class="text-base-content/60">Lens:</span>
+                    <span class="font-semibold">{props.exifData!.lensModel}</span>
+                  </div>
+                </Show>
+              </div>
             </div>
-            <div class="grid grid-cols-2 gap-3 text-sm">
-              <Show when={props.exifData!.aperture}>
-                <div>
-                  <div class="text-base-content/60 text-xs mb-1">Aperture</div>
-                  <div class="font-mono font-bold text-secondary">
-                    {props.exifData!.aperture}
+          </Show>
+
+          {/* Capture Settings Section */}
+          <Show when={hasCaptureSettings()}>
+            <div class="bg-base-200/50 rounded-xl p-4 border border-base-300">
+              <div class="flex items-center gap-2 mb-3">
+                <FiAperture class="text-secondary" size={18} />
+                <h4 class="font-bold text-sm uppercase tracking-wider">
+                  Capture Settings
+                </h4>
+              </div>
+              <div class="grid grid-cols-2 gap-3 text-sm">
+                <Show when={props.exifData!.aperture}>
+                  <div>
+                    <div class="text-base-content/60 text-xs mb-1">Aperture</div>
+                    <div class="font-mono font-bold text-secondary">
+                      {props.exifData!.aperture}
+                    </div>
                   </div>
-                </div>
-              </Show>
-              <Show when={props.exifData!.shutterSpeed}>
-                <div>
-                  <div class="text-base-content/60 text-xs mb-1">Shutter</div>
-                  <div class="font-mono font-bold text-secondary">
-                    {props.exifData!.shutterSpeed}
+                </Show>
+                <Show when={props.exifData!.shutterSpeed}>
+                  <div>
+                    <div class="text-base-content/60 text-xs mb-1">Shutter</div>
+                    <div class="font-mono font-bold text-secondary">
+                      {props.exifData!.shutterSpeed}
+                    </div>
                   </div>
-                </div>
-              </Show>
-              <Show when={props.exifData!.iso}>
-                <div>
-                  <div class="text-base-content/60 text-xs mb-1">ISO</div>
-                  <div class="font-mono font-bold text-secondary">
-                    ISO {props.exifData!.iso}
+                </Show>
+                <Show when={props.exifData!.iso}>
+                  <div>
+                    <div class="text-base-content/60 text-xs mb-1">ISO</div>
+                    <div class="font-mono font-bold text-secondary">
+                      ISO {props.exifData!.iso}
+                    </div>
                   </div>
-                </div>
-              </Show>
-              <Show when={props.exifData!.focalLength}>
-                <div>
-                  <div class="text-base-content/60 text-xs mb-1">
-                    Focal Length
+                </Show>
+                <Show when={props.exifData!.focalLength}>
+                  <div>
+                    <div class="text-base-content/60 text-xs mb-1">
+                      Focal Length
+                    </div>
+                    <div class="font-mono font-bold text-secondary">
+                      {props.exifData!.focalLength}
+                    </div>
                   </div>
-                  <div class="font-mono font-bold text-secondary">
-                    {props.exifData!.focalLength}
+                </Show>
+                <Show when={props.exifData!.exposureBias}>
+                  <div>
+                    <div class="text-base-content/60 text-xs mb-1">Exp. Bias</div>
+                    <div class="font-mono font-bold">
+                      {props.exifData!.exposureBias}
+                    </div>
                   </div>
-                </div>
-              </Show>
-              <Show when={props.exifData!.exposureBias}>
-                <div>
-                  <div class="text-base-content/60 text-xs mb-1">Exp. Bias</div>
-                  <div class="font-mono font-bold">
-                    {props.exifData!.exposureBias}
+                </Show>
+                <Show when={props.exifData!.flash}>
+                  <div>
+                    <div class="text-base-content/60 text-xs mb-1">Flash</div>
+                    <div class="font-mono font-bold">{props.exifData!.flash}</div>
                   </div>
+                </Show>
+              </div>
+            </div>
+          </Show>
+
+          {/* Date & Time Section */}
+          <Show when={props.exifData!.dateTaken}>
+            <div class="bg-base-200/50 rounded-xl p-4 border border-base-300">
+              <div class="flex items-center gap-2 mb-3">
+                <FiCalendar class="text-accent" size={18} />
+                <h4 class="font-bold text-sm uppercase tracking-wider">
+                  Date & Time
+                </h4>
+              </div>
+              <div class="space-y-2 text-sm">
+                <div class="flex justify-between">
+                  <span class="text-base-content/60">Taken:</span>
+                  <span class="font-mono">{props.exifData!.dateTaken}</span>
                 </div>
-              </Show>
-              <Show when={props.exifData!.flash}>
-                <div>
-                  <div class="text-base-content/60 text-xs mb-1">Flash</div>
-                  <div class="font-mono font-bold">{props.exifData!.flash}</div>
+              </div>
+            </div>
+          </Show>
+
+          {/* GPS Location Section */}
+          <Show when={hasLocation()}>
+            <div class="bg-warning/10 rounded-xl p-4 border border-warning/30">
+              <div class="flex items-center gap-2 mb-3">
+                <FiMapPin class="text-warning" size={18} />
+                <h4 class="font-bold text-sm uppercase tracking-wider">
+                  Location
+                </h4>
+                <Show
+                  when={props.locationExposed !== false}
+                  fallback={
+                    <span class="badge badge-success badge-xs">
+                      Not Exact in Output
+                    </span>
+                  }
+                >
+                  <span class="badge badge-warning badge-xs">
+                    {props.locationExposed
+                      ? "Exact in Output"
+                      : "Privacy Sensitive"}
+                  </span>
+                </Show>
+              </div>
+              <div class="space-y-2 text-sm">
+                <div class="flex justify-between items-center">
+                  <span class="text-base-content/60">Coordinates:</span>
+                  <span class="font-mono text-xs">
+                    {formatGPS(
+                      props.exifData!.gpsLatitude,
+                      props.exifData!.gpsLongitude,
+                    )}
+                  </span>
                 </div>
-              </Show>
+                <Show when={props.exifData!.gpsAltitude}>
+                  <div class="flex justify-between">
+                    <span class="text-base-content/60">Altitude:</span>
+                    <span class="font-mono">
+                      {props.exifData!.gpsAltitude?.toFixed(1)}m
+                    </span>
+                  </div>
+                </Show>
+                <Show
+                  when={getGoogleMapsLink(
+                    props.exifData!.gpsLatitude,
+                    props.exifData!.gpsLongitude,
+                  )}
+                >
+                  <a
+                    href={
+                      getGoogleMapsLink(
+                        props.exifData!.gpsLatitude,
+                        props.exifData!.gpsLongitude,
+                      )!
+                    }
+                    target="_blank"
+                    rel="noopener noreferrer"
+                    class="btn btn-warning btn-xs btn-block mt-2"
+                  >
+                    <FiMapPin size={14} />
+                    View on Map
+                  </a>
+                </Show>
+              </div>
             </div>
-          </div>
-        </Show>
+          </Show>
 
-        {/* Date & Time Section */}
-        <Show when={props.exifData!.dateTaken}>
-          <div class="bg-base-200/50 rounded-xl p-4 border border-base-300">
-            <div class="flex items-center gap-2 mb-3">
-              <FiCalendar class="text-accent" size={18} />
-              <h4 class="font-bold text-sm uppercase tracking-wider">
-                Date & Time
-              </h4>
+          {/* Copyright & Author Section */}
+          <Show when={hasCopyright()}>
+            <div class="bg-base-200/50 rounded-xl p-4 border border-base-300">
+              <div class="flex items-center gap-2 mb-3">
+                <FiUser class="text-info" size={18} />
+                <h4 class="font-bold text-sm uppercase tracking-wider">
+                  Copyright
+                </h4>
+              </div>
+              <div class="space-y-2 text-sm">
+                <Show when={props.exifData!.artist}>
+                  <div class="flex justify-between">
+                    <span class="text-base-content/60">Artist:</span>
+                    <span class="font-semibold">{props.exifData!.artist}</span>
+                  </div>
+                </Show>
+                <Show when={props.exifData!.copyright}>
+                  <div class="flex justify-between">
+                    <span class="text-base-content/60">Copyright:</span>
+                    <span class="font-semibold text-xs">
+                      {props.exifData!.copyright}
+                    </span>
+                  </div>
+                </Show>
+              </div>
             </div>
-            <div class="space-y-2 text-sm">
-              <div class="flex justify-between">
-                <span class="text-base-content/60">Taken:</span>
-                <span class="font-mono">{props.exifData!.dateTaken}</span>
+          </Show>
+
+          {/* Additional Info Section */}
+          <Show
+            when={props.exifData!.whiteBalance || props.exifData!.meteringMode}
+          >
+            <div class="bg-base-200/50 rounded-xl p-4 border border-base-300">
+              <div class="flex items-center gap-2 mb-3">
+                <FiSun class="text-warning" size={18} />
+                <h4 class="font-bold text-sm uppercase tracking-wider">
+                  Additional
+                </h4>
+              </div>
+              <div class="grid grid-cols-2 gap-3 text-sm">
+                <Show when={props.exifData!.whiteBalance}>
+                  <div>
+                    <div class="text-base-content/60 text-xs mb-1">
+                      White Balance
+                    </div>
+                    <div class="font-semibold">
+                      {props.exifData!.whiteBalance}
+                    </div>
+                  </div>
+                </Show>
+                <Show when={props.exifData!.meteringMode}>
+                  <div>
+                    <div class="text-base-content/60 text-xs mb-1">Metering</div>
+                    <div class="font-semibold">
+                      {props.exifData!.meteringMode}
+                    </div>
+                  </div>
+                </Show>
+                <Show when={props.exifData!.colorSpace}>
+                  <div>
+                    <div class="text-base-content/60 text-xs mb-1">
+                      Color Space
+                    </div>
+                    <div class="font-semibold">{props.exifData!.colorSpace}</div>
+                  </div>
+                </Show>
+                <Show when={props.exifData!.software}>
+                  <div>
+                    <div class="text-base-content/60 text-xs mb-1">Software</div>
+                    <div class="font-semibold text-xs">
+                      {props.exifData!.software}
+                    </div>
+                  </div>
+                </Show>
               </div>
             </div>
-          </div>
-        </Show>
+          </Show>
+        </div>
+      </Show>
 
-        {/* GPS Location Section */}
-        <Show when={hasLocation()}>
-          <div class="bg-warning/10 rounded-xl p-4 border border-warning/30">
+      {/* XMP Section */}
+      <Show when={props.xmpData}>
+        {(xmp) => (
+          <div class="bg-base-200/50 rounded-xl p-4 border border-base-300">
             <div class="flex items-center gap-2 mb-3">
-              <FiMapPin class="text-warning" size={18} />
-              <h4 class="font-bold text-sm uppercase tracking-wider">
-                Location
-              </h4>
-              <span class="badge badge-warning badge-xs">
-                Privacy Sensitive
-              </span>
+              <FiTag class="text-secondary" size={18} />
+              <h4 class="font-bold text-sm uppercase tracking-wider">XMP</h4>
             </div>
             <div class="space-y-2 text-sm">
-              <div class="flex justify-between items-center">
-                <span class="text-base-content/60">Coordinates:</span>
-                <span class="font-mono text-xs">
-                  {formatGPS(
-                    props.exifData!.gpsLatitude,
-                    props.exifData!.gpsLongitude,
-                  )}
-                </span>
-              </div>
-              <Show when={props.exifData!.gpsAltitude}>
+              <Show when={xmp().creator.length > 0}>
+                <div class="flex justify-between">
+                  <span class="text-base-content/60">Creator:</span>
+                  <span class="font-semibold">{xmp().creator.join(", ")}</span>
+                </div>
+              </Show>
+              <Show when={xmp().rights}>
+                <div class="flex justify-between">
+                  <span class="text-base-content/60">Rights:</span>
+                  <span class="font-semibold text-xs">{xmp().rights}</span>
+                </div>
+              </Show>
+              <Show when={xmp().credit}>
+                <div class="flex justify-between">
+                  <span class="text-base-content/60">Credit:</span>
+                  <span class="font-semibold">{xmp().credit}</span>
+                </div>
+              </Show>
+              <Show when={xmp().rating !== undefined && xmp().rating !== null}>
                 <div class="flex justify-between">
-                  <span class="text-base-content/60">Altitude:</span>
-                  <span class="font-mono">
-                    {props.exifData!.gpsAltitude?.toFixed(1)}m
+                  <span class="text-base-content/60">Rating:</span>
+                  <span class="font-semibold">
+                    {xmp().rating! < 0 ? "Rejected" : "★".repeat(xmp().rating!)}
                   </span>
                 </div>
               </Show>
-              <Show
-                when={getGoogleMapsLink(
-                  props.exifData!.gpsLatitude,
-                  props.exifData!.gpsLongitude,
-                )}
-              >
-                <a
-                  href={
-                    getGoogleMapsLink(
-                      props.exifData!.gpsLatitude,
-                      props.exifData!.gpsLongitude,
-                    )!
-                  }
-                  target="_blank"
-                  rel="noopener noreferrer"
-                  class="btn btn-warning btn-xs btn-block mt-2"
-                >
-                  <FiMapPin size={14} />
-                  View on Map
-                </a>
+              <Show when={xmp().keywords.length > 0}>
+                <div class="flex flex-wrap gap-1 pt-1">
+                  <For each={xmp().keywords}>
+                    {(keyword) => (
+                      <span class="badge badge-ghost badge-sm">{keyword}</span>
+                    )}
+                  </For>
+                </div>
               </Show>
             </div>
           </div>
-        </Show>
+        )}
+      </Show>
 
-        {/* Copyright & Author Section */}
-        <Show when={hasCopyright()}>
+      {/* IPTC Section */}
+      <Show when={props.iptcData}>
+        {(iptc) => (
           <div class="bg-base-200/50 rounded-xl p-4 border border-base-300">
             <div class="flex items-center gap-2 mb-3">
-              <FiUser class="text-info" size={18} />
-              <h4 class="font-bold text-sm uppercase tracking-wider">
-                Copyright
-              </h4>
+              <FiFileText class="text-secondary" size={18} />
+              <h4 class="font-bold text-sm uppercase tracking-wider">IPTC</h4>
             </div>
             <div class="space-y-2 text-sm">
-              <Show when={props.exifData!.artist}>
+              <Show when={iptc().headline}>
+                <div class="font-semibold">{iptc().headline}</div>
+              </Show>
+              <Show when={iptc().caption}>
+                <div class="text-xs text-base-content/80">{iptc().caption}</div>
+              </Show>
+              <Show when={iptc().byLine.length > 0}>
                 <div class="flex justify-between">
-                  <span class="text-base-content/60">Artist:</span>
-                  <span class="font-semibold">{props.exifData!.artist}</span>
+                  <span class="text-base-content/60">By-line:</span>
+                  <span class="font-semibold">{iptc().byLine.join(", ")}</span>
                 </div>
               </Show>
-              <Show when={props.exifData!.copyright}>
+              <Show when={iptc().credit}>
                 <div class="flex justify-between">
-                  <span class="text-base-content/60">Copyright:</span>
-                  <span class="font-semibold text-xs">
-                    {props.exifData!.copyright}
-                  </span>
+                  <span class="text-base-content/60">Credit:</span>
+                  <span class="font-semibold">{iptc().credit}</span>
                 </div>
               </Show>
-            </div>
-          </div>
-        </Show>
-
-        {/* Additional Info Section */}
-        <Show
-          when={props.exifData!.whiteBalance || props.exifData!.meteringMode}
-        >
-          <div class="bg-base-200/50 rounded-xl p-4 border border-base-300">
-            <div class="flex items-center gap-2 mb-3">
-              <FiSun class="text-warning" size={18} />
-              <h4 class="font-bold text-sm uppercase tracking-wider">
-                Additional
-              </h4>
-            </div>
-            <div class="grid grid-cols-2 gap-3 text-sm">
-              <Show when={props.exifData!.whiteBalance}>
-                <div>
-                  <div class="text-base-content/60 text-xs mb-1">
-                    White Balance
-                  </div>
-                  <div class="font-semibold">
-                    {props.exifData!.whiteBalance}
-                  </div>
+              <Show when={iptc().source}>
+                <div class="flex justify-between">
+                  <span class="text-base-content/60">Source:</span>
+                  <span class="font-semibold">{iptc().source}</span>
                 </div>
               </Show>
-              <Show when={props.exifData!.meteringMode}>
-                <div>
-                  <div class="text-base-content/60 text-xs mb-1">Metering</div>
-                  <div class="font-semibold">
-                    {props.exifData!.meteringMode}
-                  </div>
+              <Show when={iptc().copyrightNotice}>
+                <div class="flex justify-between">
+                  <span class="text-base-content/60">Copyright:</span>
+                  <span class="font-semibold text-xs">{iptc().copyrightNotice}</span>
                 </div>
               </Show>
-              <Show when={props.exifData!.colorSpace}>
-                <div>
-                  <div class="text-base-content/60 text-xs mb-1">
-                    Color Space
-                  </div>
-                  <div class="font-semibold">{props.exifData!.colorSpace}</div>
+              <Show when={iptc().city || iptc().country}>
+                <div class="flex justify-between">
+                  <span class="text-base-content/60">Location:</span>
+                  <span class="font-semibold">
+                    {[iptc().city, iptc().provinceState, iptc().country]
+                      .filter(Boolean)
+                      .join(", ")}
+                  </span>
                 </div>
               </Show>
-              <Show when={props.exifData!.software}>
-                <div>
-                  <div class="text-base-content/60 text-xs mb-1">Software</div>
-                  <div class="font-semibold text-xs">
-                    {props.exifData!.software}
-                  </div>
+              <Show when={iptc().keywords.length > 0}>
+                <div class="flex flex-wrap gap-1 pt-1">
+                  <For each={iptc().keywords}>
+                    {(keyword) => (
+                      <span class="badge badge-ghost badge-sm">{keyword}</span>
+                    )}
+                  </For>
                 </div>
               </Show>
             </div>
           </div>
-        </Show>
-      </div>
-    </Show>
+        )}
+      </Show>
+    </div>
   );
 }
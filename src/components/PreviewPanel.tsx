@@ -185,6 +185,9 @@ export function PreviewPanel(props: PreviewPanelProps) {
                 <ExifViewer
                   exifData={file.exif_data || null}
                   hasExif={file.has_exif || false}
+                  xmpData={file.xmp_data || null}
+                  iptcData={file.iptc_data || null}
+                  locationExposed={file.result?.location_exposed}
                 />
               }
             >
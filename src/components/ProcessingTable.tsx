@@ -2,6 +2,7 @@
 import { For, Switch, Match, Show } from "solid-js";
 import { open } from "@tauri-apps/plugin-shell";
 import { convertFileSrc } from "@tauri-apps/api/core";
+import type { IptcData, XmpData } from "./ExifViewer";
 import {
   FiCheckCircle,
   FiClock,
@@ -11,6 +12,8 @@ import {
   FiLoader,
   FiAlertCircle,
   FiFile,
+  FiAlertTriangle,
+  FiCrop,
 } from "solid-icons/fi";
 
 export type ColorProfile =
@@ -20,6 +23,15 @@ export type ColorProfile =
   | "proPhotoRgb"
   | { unknown: string };
 
+export type ProfileSource = "icc" | "pngChunks" | "assumed";
+
+export type CropRect = {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+};
+
 export type ImageFile = {
   id: string;
   path: string;
@@ -27,16 +39,22 @@ export type ImageFile = {
   mimetype: string;
   last_modified: number;
   color_profile: ColorProfile;
+  color_profile_source: ProfileSource;
   needs_conversion: boolean;
   preview_path?: string;
   thumbnail_path?: string;
   exif_data?: any; // Cambiato da exif_data a exifData (camelCase)
   has_exif?: boolean; // Cambiato da has_exif a hasExif (camelCase)
+  xmp_data?: XmpData;
+  iptc_data?: IptcData;
   status: "pending" | "done";
   result?: {
     optimized_path: string;
     optimized_size_kb: number;
     reduction_percentage: number;
+    warnings: string[];
+    crop_rect: CropRect | null;
+    location_exposed: boolean;
   };
 };
 
@@ -69,6 +87,17 @@ function getColorProfileDisplay(profile: ColorProfile): string {
   return "Unknown";
 }
 
+function getProfileSourceDisplay(source: ProfileSource): string {
+  switch (source) {
+    case "icc":
+      return "From embedded ICC profile";
+    case "pngChunks":
+      return "From PNG sRGB/gAMA/cHRM chunks";
+    default:
+      return "No color information, assumed sRGB";
+  }
+}
+
 function getColorProfileBadgeClass(profile: ColorProfile): string {
   if (profile === "srgb") {
     return "badge-success";
@@ -210,6 +239,27 @@ export function ProcessingTable(props: ProcessingTableProps) {
                               <FiFile size={12} /> open
                             </button>
                           </div>
+                          <Show when={file.result!.crop_rect}>
+                            {(crop) => (
+                              <div
+                                class="flex items-center gap-2 text-xs text-base-content/60"
+                                title={`Offset ${crop().x}, ${crop().y}`}
+                              >
+                                <FiCrop size={12} />
+                                <span>
+                                  Trimmed to {crop().width}×{crop().height}
+                                </span>
+                              </div>
+                            )}
+                          </Show>
+                          <For each={file.result!.warnings}>
+                            {(warning) => (
+                              <div class="flex items-start gap-2 text-xs text-warning">
+                                <FiAlertTriangle size={12} class="mt-0.5 shrink-0" />
+                                <span>{warning}</span>
+                              </div>
+                            )}
+                          </For>
                         </Match>
                         <Match
                           when={props.isOptimizing && file.status === "pending"}
@@ -241,6 +291,7 @@ export function ProcessingTable(props: ProcessingTableProps) {
                     <div class="flex flex-col gap-1">
                       <span
                         class={`badge badge-sm ${getColorProfileBadgeClass(file.color_profile)}`}
+                        title={getProfileSourceDisplay(file.color_profile_source)}
                       >
                         {getColorProfileDisplay(file.color_profile)}
                       </span>
@@ -11,6 +11,7 @@ import {
   FiFolderPlus,
   FiDroplet,
   FiShield,
+  FiEdit3,
 } from "solid-icons/fi";
 
 // --- Tipi ---
@@ -28,7 +29,13 @@ export type ResizePreset =
   | "fullHd"
   | "hd"
   | "sd"
-  | { custom: { width: number; height: number } };
+  | { custom: { width: number; height: number } }
+  | { percent: number }
+  | { longEdge: number }
+  | { shortEdge: number }
+  | { maxMegapixels: number }
+  | { width: number }
+  | { height: number };
 
 export type OutputDestination =
   | { type: "sameFolder" }
@@ -40,12 +47,42 @@ export type ColorConversionIntent =
   | "saturation"
   | "absoluteColorimetric";
 
+export type GpsPrecision = "exact" | "approximate" | "city";
+
+export type TagCategory =
+  | "camera"
+  | "capture"
+  | "datetime"
+  | "location"
+  | "owner"
+  | "software"
+  | "makerNotes";
+
+export type MetadataPolicy =
+  | { type: "stripAll" }
+  | { type: "keepAll" }
+  | { type: "allowList"; categories: TagCategory[]; tags: string[] }
+  | { type: "denyList"; categories: TagCategory[]; tags: string[] };
+
+export type MetadataTemplate = {
+  artist?: string;
+  copyright?: string;
+  description?: string;
+  keywords: string[];
+  rightsUrl?: string;
+};
+
 export type ExifOptions = {
   preserveAll: boolean;
+  policy: MetadataPolicy;
   stripGps: boolean;
+  gpsPrecision: GpsPrecision;
   stripThumbnail: boolean;
   updateSoftware: boolean;
   preserveCopyright: boolean;
+  stripMakerNote: boolean;
+  pngTextChunks: boolean;
+  template: MetadataTemplate;
 };
 
 export type OptimizationOptions = {
@@ -54,6 +91,7 @@ export type OptimizationOptions = {
   resize: ResizePreset;
   destination: OutputDestination;
   colorIntent: ColorConversionIntent;
+  blackPointCompensation: boolean;
   exifOptions: ExifOptions; // NUOVO
 };
 
@@ -67,6 +105,49 @@ type SettingsPageProps = {
 
 // --- Dati Centralizzati ---
 
+const POLICY_OPTIONS: {
+  value: MetadataPolicy["type"];
+  label: string;
+  description: string;
+}[] = [
+  {
+    value: "keepAll",
+    label: "Keep all metadata",
+    description: "Copy all EXIF data, filtered by the privacy options below",
+  },
+  {
+    value: "allowList",
+    label: "Keep only selected",
+    description: "Copy only the selected categories and tags",
+  },
+  {
+    value: "denyList",
+    label: "Keep all except selected",
+    description: "Copy everything except the selected categories and tags",
+  },
+  {
+    value: "stripAll",
+    label: "Strip all metadata",
+    description: "Write no metadata from the source (the template still applies)",
+  },
+];
+
+const TAG_CATEGORY_OPTIONS: { value: TagCategory; label: string }[] = [
+  { value: "camera", label: "Camera & lens" },
+  { value: "capture", label: "Capture settings" },
+  { value: "datetime", label: "Date & time" },
+  { value: "location", label: "Location (GPS)" },
+  { value: "owner", label: "Owner & copyright" },
+  { value: "software", label: "Software" },
+  { value: "makerNotes", label: "Maker notes" },
+];
+
+const GPS_PRECISION_OPTIONS: { value: GpsPrecision; label: string }[] = [
+  { value: "exact", label: "Exact coordinates" },
+  { value: "approximate", label: "Approximate (0.01°, ~1 km)" },
+  { value: "city", label: "City level (0.1°, ~11 km)" },
+];
+
 const FORMAT_OPTIONS: {
   value: OutputFormat;
   label: string;
@@ -255,6 +336,58 @@ export function SettingsPage(props: SettingsPageProps) {
     }
   };
 
+  const handlePolicyTypeChange = (type: MetadataPolicy["type"]) => {
+    const current = props.options.exifOptions.policy;
+    const lists =
+      current.type === "allowList" || current.type === "denyList"
+        ? { categories: current.categories, tags: current.tags }
+        : { categories: [], tags: [] };
+    const policy: MetadataPolicy =
+      type === "allowList" || type === "denyList"
+        ? { type, ...lists }
+        : { type };
+    props.setOptions("exifOptions", {
+      ...props.options.exifOptions,
+      policy,
+      preserveAll: type !== "stripAll",
+    });
+  };
+
+  const policyLists = () => {
+    const policy = props.options.exifOptions.policy;
+    return policy.type === "allowList" || policy.type === "denyList"
+      ? policy
+      : null;
+  };
+
+  const handlePolicyListsChange = (
+    patch: Partial<{ categories: TagCategory[]; tags: string[] }>,
+  ) => {
+    const policy = policyLists();
+    if (policy) {
+      props.setOptions("exifOptions", {
+        ...props.options.exifOptions,
+        policy: { ...policy, ...patch },
+      });
+    }
+  };
+
+  const toggleCategory = (category: TagCategory, checked: boolean) => {
+    const categories = policyLists()?.categories ?? [];
+    handlePolicyListsChange({
+      categories: checked
+        ? [...categories, category]
+        : categories.filter((c) => c !== category),
+    });
+  };
+
+  const handleTemplateChange = (patch: Partial<MetadataTemplate>) => {
+    props.setOptions("exifOptions", {
+      ...props.options.exifOptions,
+      template: { ...props.options.exifOptions.template, ...patch },
+    });
+  };
+
   const isCustomDestination = () =>
     props.options.destination.type === "customFolder";
   const customDestinationPath = () =>
@@ -652,6 +785,32 @@ export function SettingsPage(props: SettingsPageProps) {
                 </For>
               </div>
 
+              <div class="form-control mt-4">
+                <label class="label cursor-pointer justify-start gap-4">
+                  <input
+                    type="checkbox"
+                    class="toggle toggle-info toggle-sm"
+                    checked={props.options.blackPointCompensation}
+                    onChange={(e) =>
+                      props.setOptions(
+                        "blackPointCompensation",
+                        e.currentTarget.checked,
+                      )
+                    }
+                  />
+                  <div class="flex-1">
+                    <span class="label-text font-semibold">
+                      Black Point Compensation
+                    </span>
+                    <p class="text-xs text-base-content/60 mt-1">
+                      Maps the source black to the destination black, keeping
+                      shadow detail with Relative Colorimetric (e.g. ProPhoto
+                      sources)
+                    </p>
+                  </div>
+                </label>
+              </div>
+
               <div class="alert alert-info mt-4">
                 <FiInfo />
                 <div>
@@ -684,38 +843,105 @@ export function SettingsPage(props: SettingsPageProps) {
                 <div class="text-sm">
                   <div class="font-bold">Format Support</div>
                   <div>
-                    EXIF preservation is{" "}
-                    <strong>fully supported only for JPEG</strong> format. PNG
-                    and WebP formats will skip EXIF preservation.
+                    EXIF preservation is supported for{" "}
+                    <strong>JPEG, PNG and WebP</strong> formats. PNG uses the{" "}
+                    <code>eXIf</code> chunk, which some older viewers ignore.
+                  </div>
+                  <div class="mt-1">
+                    XMP is rewritten with only creator, rights, credit, rights
+                    URL, title, description, keywords and rating; other XMP
+                    properties (e.g. Lightroom edits, XMP location, history) are
+                    not copied.
                   </div>
                 </div>
               </div>
 
               <div class="space-y-4">
-                {/* Preserve All Toggle */}
+                {/* Metadata Policy */}
                 <div class="form-control">
-                  <label class="label cursor-pointer justify-start gap-4">
-                    <input
-                      type="checkbox"
-                      class="toggle toggle-success"
-                      checked={props.options.exifOptions.preserveAll}
-                      onChange={(e) =>
-                        props.setOptions("exifOptions", {
-                          ...props.options.exifOptions,
-                          preserveAll: e.currentTarget.checked,
-                        })
-                      }
-                    />
+                  <label class="label justify-start gap-4">
                     <div class="flex-1">
-                      <span class="label-text font-bold">
-                        Preserve All Metadata
-                      </span>
+                      <span class="label-text font-bold">Metadata Policy</span>
                       <p class="text-xs text-base-content/60 mt-1">
-                        Keep all EXIF data in optimized images (camera settings,
-                        dates, etc.)
+                        {
+                          POLICY_OPTIONS.find(
+                            (option) =>
+                              option.value ===
+                              props.options.exifOptions.policy.type,
+                          )?.description
+                        }
                       </p>
                     </div>
+                    <select
+                      class="select select-bordered select-sm"
+                      value={props.options.exifOptions.policy.type}
+                      onChange={(e) =>
+                        handlePolicyTypeChange(
+                          e.currentTarget.value as MetadataPolicy["type"],
+                        )
+                      }
+                    >
+                      <For each={POLICY_OPTIONS}>
+                        {(option) => (
+                          <option value={option.value}>{option.label}</option>
+                        )}
+                      </For>
+                    </select>
                   </label>
+
+                  <Show when={policyLists()}>
+                    {(policy) => (
+                      <div class="space-y-3 mt-2">
+                        <div class="grid grid-cols-2 md:grid-cols-4 gap-2">
+                          <For each={TAG_CATEGORY_OPTIONS}>
+                            {(category) => (
+                              <label class="label cursor-pointer justify-start gap-2">
+                                <input
+                                  type="checkbox"
+                                  class="checkbox checkbox-sm"
+                                  checked={policy().categories.includes(
+                                    category.value,
+                                  )}
+                                  onChange={(e) =>
+                                    toggleCategory(
+                                      category.value,
+                                      e.currentTarget.checked,
+                                    )
+                                  }
+                                />
+                                <span class="label-text text-sm">
+                                  {category.label}
+                                </span>
+                              </label>
+                            )}
+                          </For>
+                        </div>
+                        <label class="form-control">
+                          <span class="label-text text-xs mb-1">
+                            Additional EXIF tags (comma separated, e.g.
+                            DateTimeOriginal, LensModel)
+                          </span>
+                          <input
+                            type="text"
+                            class="input input-bordered input-sm"
+                            value={policy().tags.join(", ")}
+                            onChange={(e) =>
+                              handlePolicyListsChange({
+                                tags: e.currentTarget.value
+                                  .split(",")
+                                  .map((tag) => tag.trim())
+                                  .filter((tag) => tag.length > 0),
+                              })
+                            }
+                          />
+                        </label>
+                        <p class="text-xs text-base-content/60">
+                          Orientation, color space and image dimensions are
+                          always kept
+                        </p>
+                      </div>
+                    )}
+                  </Show>
                 </div>
 
                 <div class="divider my-2"></div>
@@ -728,27 +954,55 @@ export function SettingsPage(props: SettingsPageProps) {
                   </h4>
 
                   <div class="space-y-3">
-                    <div class="form-control opacity-50">
-                      <label class="label cursor-not-allowed justify-start gap-4">
+                    <div class="form-control">
+                      <label class="label cursor-pointer justify-start gap-4">
                         <input
                           type="checkbox"
                           class="toggle toggle-warning toggle-sm"
-                          checked={true}
-                          disabled={true}
+                          checked={props.options.exifOptions.stripGps}
+                          onChange={(e) =>
+                            props.setOptions("exifOptions", {
+                              ...props.options.exifOptions,
+                              stripGps: e.currentTarget.checked,
+                            })
+                          }
                         />
                         <div class="flex-1">
-                          <span class="label-text font-semibold flex items-center gap-2">
+                          <span class="label-text font-semibold">
                             Strip GPS Location
-                            <span class="badge badge-ghost badge-xs">
-                              Auto-Enabled
-                            </span>
                           </span>
                           <p class="text-xs text-base-content/60 mt-1">
-                            GPS data is <strong>never preserved</strong> in the
-                            current version for privacy protection
+                            Remove geotags for privacy. Turn off to keep
+                            latitude, longitude and altitude in JPEG outputs
                           </p>
                         </div>
                       </label>
+                      <Show when={!props.options.exifOptions.stripGps}>
+                        <label class="label justify-start gap-4 pl-14">
+                          <span class="label-text text-sm">
+                            Location precision
+                          </span>
+                          <select
+                            class="select select-bordered select-sm"
+                            value={props.options.exifOptions.gpsPrecision}
+                            onChange={(e) =>
+                              props.setOptions("exifOptions", {
+                                ...props.options.exifOptions,
+                                gpsPrecision: e.currentTarget
+                                  .value as GpsPrecision,
+                              })
+                            }
+                          >
+                            <For each={GPS_PRECISION_OPTIONS}>
+                              {(option) => (
+                                <option value={option.value}>
+                                  {option.label}
+                                </option>
+                              )}
+                            </For>
+                          </select>
+                        </label>
+                      </Show>
                     </div>
 
                     <div class="form-control">
@@ -775,6 +1029,31 @@ export function SettingsPage(props: SettingsPageProps) {
                         </div>
                       </label>
                     </div>
+
+                    <div class="form-control">
+                      <label class="label cursor-pointer justify-start gap-4">
+                        <input
+                          type="checkbox"
+                          class="toggle toggle-sm"
+                          checked={props.options.exifOptions.stripMakerNote}
+                          onChange={(e) =>
+                            props.setOptions("exifOptions", {
+                              ...props.options.exifOptions,
+                              stripMakerNote: e.currentTarget.checked,
+                            })
+                          }
+                        />
+                        <div class="flex-1">
+                          <span class="label-text font-semibold">
+                            Remove MakerNote
+                          </span>
+                          <p class="text-xs text-base-content/60 mt-1">
+                            Drop proprietary camera data (serial numbers,
+                            internal settings); all other EXIF is kept as is
+                          </p>
+                        </div>
+                      </label>
+                    </div>
                   </div>
                 </div>
 
@@ -811,6 +1090,32 @@ export function SettingsPage(props: SettingsPageProps) {
                       </label>
                     </div>
 
+                    <div class="form-control">
+                      <label class="label cursor-pointer justify-start gap-4">
+                        <input
+                          type="checkbox"
+                          class="toggle toggle-sm"
+                          checked={props.options.exifOptions.pngTextChunks}
+                          disabled={!props.options.exifOptions.preserveCopyright}
+                          onChange={(e) =>
+                            props.setOptions("exifOptions", {
+                              ...props.options.exifOptions,
+                              pngTextChunks: e.currentTarget.checked,
+                            })
+                          }
+                        />
+                        <div class="flex-1">
+                          <span class="label-text font-semibold">
+                            PNG Text Fields
+                          </span>
+                          <p class="text-xs text-base-content/60 mt-1">
+                            Also write Author and Copyright as PNG text chunks
+                            for tools that don't read EXIF
+                          </p>
+                        </div>
+                      </label>
+                    </div>
+
                     <div class="form-control">
                       <label class="label cursor-pointer justify-start gap-4">
                         <input
@@ -836,6 +1141,97 @@ export function SettingsPage(props: SettingsPageProps) {
                     </div>
                   </div>
                 </div>
+
+                {/* Metadata Template */}
+                <div class="bg-base-200/50 rounded-lg p-4 border border-base-300">
+                  <h4 class="font-bold text-sm mb-1 flex items-center gap-2">
+                    <FiEdit3 />
+                    Metadata Template
+                  </h4>
+                  <p class="text-xs text-base-content/60 mb-3">
+                    Filled fields are written to every output, replacing the
+                    original values. Use <code>{"{year}"}</code> in the
+                    copyright for the current year.
+                  </p>
+
+                  <div class="grid grid-cols-1 md:grid-cols-2 gap-3">
+                    <label class="form-control">
+                      <span class="label-text text-xs mb-1">Artist</span>
+                      <input
+                        type="text"
+                        class="input input-bordered input-sm"
+                        placeholder="Jane Doe"
+                        value={props.options.exifOptions.template.artist ?? ""}
+                        onChange={(e) =>
+                          handleTemplateChange({ artist: e.currentTarget.value })
+                        }
+                      />
+                    </label>
+                    <label class="form-control">
+                      <span class="label-text text-xs mb-1">Copyright</span>
+                      <input
+                        type="text"
+                        class="input input-bordered input-sm"
+                        placeholder="© {year} Agency"
+                        value={props.options.exifOptions.template.copyright ?? ""}
+                        onChange={(e) =>
+                          handleTemplateChange({
+                            copyright: e.currentTarget.value,
+                          })
+                        }
+                      />
+                    </label>
+                    <label class="form-control md:col-span-2">
+                      <span class="label-text text-xs mb-1">Description</span>
+                      <input
+                        type="text"
+                        class="input input-bordered input-sm"
+                        value={
+                          props.options.exifOptions.template.description ?? ""
+                        }
+                        onChange={(e) =>
+                          handleTemplateChange({
+                            description: e.currentTarget.value,
+                          })
+                        }
+                      />
+                    </label>
+                    <label class="form-control">
+                      <span class="label-text text-xs mb-1">
+                        Keywords (comma separated)
+                      </span>
+                      <input
+                        type="text"
+                        class="input input-bordered input-sm"
+                        value={props.options.exifOptions.template.keywords.join(
+                          ", ",
+                        )}
+                        onChange={(e) =>
+                          handleTemplateChange({
+                            keywords: e.currentTarget.value
+                              .split(",")
+                              .map((keyword) => keyword.trim())
+                              .filter((keyword) => keyword.length > 0),
+                          })
+                        }
+                      />
+                    </label>
+                    <label class="form-control">
+                      <span class="label-text text-xs mb-1">Rights URL</span>
+                      <input
+                        type="text"
+                        class="input input-bordered input-sm"
+                        placeholder="https://example.com/license"
+                        value={props.options.exifOptions.template.rightsUrl ?? ""}
+                        onChange={(e) =>
+                          handleTemplateChange({
+                            rightsUrl: e.currentTarget.value,
+                          })
+                        }
+                      />
+                    </label>
+                  </div>
+                </div>
               </div>
 
               <div class="alert alert-info mt-4">
@@ -846,13 +1242,18 @@ export function SettingsPage(props: SettingsPageProps) {
                     <strong>Current Limitations:</strong>
                     <ul class="list-disc list-inside mt-1 space-y-1">
                       <li>
-                        <strong>GPS data is never preserved</strong> (automatic
-                        privacy protection)
+                        GPS data is kept only when{" "}
+                        <strong>Strip GPS Location</strong> is off and the
+                        metadata policy keeps the location category
+                      </li>
+                      <li>
+                        Supported for <strong>JPEG</strong>,{" "}
+                        <strong>PNG</strong> and <strong>WebP</strong> formats
                       </li>
                       <li>
-                        Full support only for <strong>JPEG</strong> format
+                        Some PNG viewers only read the textual Author and
+                        Copyright fields
                       </li>
-                      <li>PNG and WebP will skip EXIF preservation</li>
                     </ul>
                   </div>
                 </div>